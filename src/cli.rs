@@ -1,10 +1,19 @@
-use clap::Parser;
+use anyhow::Context;
+use clap::{ArgMatches, Parser, ValueEnum, parser::ValueSource};
 use log::{debug, trace};
 use owo_colors::OwoColorize;
-use std::fmt::Display;
-use time::format_description;
+use serde::Deserialize;
+use std::{fmt::Display, path::Path, time::SystemTime};
+use time::{
+    OffsetDateTime, UtcOffset,
+    format_description::{self, OwnedFormatItem},
+};
 
-use crate::commands::Commands;
+use crate::{
+    commands::Commands,
+    config::{Config, DEFAULT_DATETIME_FORMAT, Flags},
+    timestamps::TimeSource,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Simple CLI file organizer", next_line_help = true)]
@@ -13,10 +22,50 @@ pub struct Cli {
     pub verbose: bool,
     #[arg(long, global = true)]
     pub trace: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = TimeSource::default(),
+        help = "Where to take file creation time from, when filesystem has no birth time"
+    )]
+    pub time_source: TimeSource,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value = "auto",
+        help = "When to color output, 'auto' colors only terminals"
+    )]
+    pub color: ColorMode,
     #[command(subcommand)]
     pub command: Option<Commands>,
+    #[clap(skip = default_datetime_format())]
+    pub datetime_format: OwnedFormatItem,
     #[clap(skip)]
-    pub datetime_format: Vec<format_description::BorrowedFormatItem<'static>>,
+    pub config: Config,
+    /// Offset of local timezone, UTC until `configure` reads it
+    #[clap(skip = UtcOffset::UTC)]
+    pub local_offset: UtcOffset,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorMode> for anstream::ColorChoice {
+    fn from(mode: ColorMode) -> Self {
+        match mode {
+            ColorMode::Auto => anstream::ColorChoice::Auto,
+            ColorMode::Always => anstream::ColorChoice::Always,
+            ColorMode::Never => anstream::ColorChoice::Never,
+        }
+    }
 }
 
 impl Display for Cli {
@@ -24,6 +73,8 @@ impl Display for Cli {
         writeln!(f, "{}", "Parsed arguments:".bold())?;
         writeln!(f, "{}: {}", "verbose".bright_cyan(), self.verbose)?;
         writeln!(f, "{}: {}", "trace".bright_cyan(), self.trace)?;
+        writeln!(f, "{}: {}", "time_source".bright_cyan(), self.time_source)?;
+        writeln!(f, "{}: {:?}", "color".bright_cyan(), self.color)?;
 
         if let Some(command) = &self.command {
             writeln!(
@@ -45,14 +96,49 @@ impl Default for Cli {
         Cli {
             verbose: false,
             trace: false,
+            time_source: TimeSource::default(),
+            color: ColorMode::default(),
             command: None,
-            datetime_format: format_description::parse("[day]-[month]-[year] [hour]:[minute]")
-                .expect("Should never fail since the format is hardcoded and correct"),
+            datetime_format: default_datetime_format(),
+            config: Config::default(),
+            local_offset: UtcOffset::UTC,
         }
     }
 }
 
 impl Cli {
+    /// Load config layers for directory of the command and apply them to values not given as flags
+    pub fn configure(&mut self, matches: &ArgMatches) -> anyhow::Result<()> {
+        let directory = self
+            .command
+            .as_ref()
+            .and_then(Commands::directory)
+            .unwrap_or(Path::new("."));
+        let config = Config::load(directory, &flags(matches))?;
+
+        self.datetime_format = format_description::parse_owned::<2>(&config.datetime_format.value)
+            .with_context(|| {
+                format!(
+                    "Invalid datetime format from {}",
+                    config.datetime_format.origin
+                )
+            })?;
+        self.color = config.color.value;
+        anstream::ColorChoice::write_global(self.color.into());
+        // Can be read only while process has a single thread
+        self.local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        if let Some(command) = &mut self.command {
+            command.configure(&config);
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Time in local timezone
+    pub fn local_time(&self, time: SystemTime) -> OffsetDateTime {
+        OffsetDateTime::from(time).to_offset(self.local_offset)
+    }
+
     pub fn execute_command(&self) -> anyhow::Result<()> {
         match &self.command {
             None => {
@@ -75,3 +161,85 @@ impl Cli {
             .join("\n")
     }
 }
+
+fn default_datetime_format() -> OwnedFormatItem {
+    format_description::parse_owned::<2>(DEFAULT_DATETIME_FORMAT)
+        .expect("Should never fail since the format is hardcoded and correct")
+}
+
+/// Config settings that were given on command line
+fn flags(matches: &ArgMatches) -> Flags {
+    let mut flags = Flags {
+        color: given(matches, "color"),
+        ..Default::default()
+    };
+    if let Some((_, matches)) = matches.subcommand() {
+        flags.sort_by = given(matches, "sort_by");
+        flags.size_buckets = given(matches, "size_buckets");
+        flags.hidden = given(matches, "search_hidden")
+            .or(given::<bool>(matches, "no_hidden").map(|no_hidden| !no_hidden));
+        flags.plan_format = given(matches, "plan_format");
+        flags.color = flags.color.or(given(matches, "color"));
+    }
+    flags
+}
+
+/// Value of argument only when it was given on command line, not taken from its default.
+/// Subcommands without the argument give `None`.
+fn given<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, id: &str) -> Option<T> {
+    // Asking clap about id unknown to subcommand panics
+    if !matches.ids().any(|known| known == id)
+        || matches.value_source(id) != Some(ValueSource::CommandLine)
+    {
+        return None;
+    }
+    matches.get_one::<T>(id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::commands::{PlanFormat, SortBy};
+
+    #[test]
+    fn test_flags_only_include_given_values() {
+        let matches = Cli::command()
+            .try_get_matches_from(["fo", "sort", "photos", "-s", "ext", "--color", "never"])
+            .unwrap();
+
+        assert_eq!(
+            flags(&matches),
+            Flags {
+                sort_by: Some(SortBy::Ext),
+                size_buckets: None,
+                hidden: None,
+                plan_format: None,
+                color: Some(ColorMode::Never),
+            }
+        );
+
+        let matches = Cli::command()
+            .try_get_matches_from(["fo", "clean", "-a", "--plan-format", "table"])
+            .unwrap();
+        let flags = flags(&matches);
+        assert_eq!(flags.hidden, Some(true));
+        assert_eq!(flags.plan_format, Some(PlanFormat::Table));
+        assert_eq!(flags.color, None);
+    }
+
+    #[test]
+    fn test_no_all_flag_turns_hidden_off() {
+        let matches = Cli::command()
+            .try_get_matches_from(["fo", "find", "--no-all"])
+            .unwrap();
+        assert_eq!(flags(&matches).hidden, Some(false));
+
+        // The last of the two wins
+        let matches = Cli::command()
+            .try_get_matches_from(["fo", "dupes", "--no-all", "-a"])
+            .unwrap();
+        assert_eq!(flags(&matches).hidden, Some(true));
+    }
+}
@@ -1,21 +1,35 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::Path};
 
 use clap::Subcommand;
 use owo_colors::OwoColorize;
 
 use crate::{
     cli::Cli,
-    commands::{clean::CleanCommand, find::FindCommand, sort::SortCommand},
+    commands::{
+        clean::CleanCommand, config::ConfigCommand, dupes::DupesCommand, find::FindCommand,
+        history::HistoryCommand, sort::SortCommand, trash::TrashCommand, undo::UndoCommand,
+    },
+    config::Config,
 };
 
 mod clean;
+mod config;
+mod dupes;
 mod find;
+mod history;
+mod plan;
 mod sort;
+mod trash;
+mod undo;
+
+pub(crate) use clean::CleanConfig;
+pub(crate) use plan::{Operation, PlanFormat};
+pub(crate) use sort::{SizeBuckets, SortBy};
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     #[command(name = "find", about = "Find files with specific criteria")]
-    Find(FindCommand),
+    Find(Box<FindCommand>),
     #[command(
         name = "sort",
         about = "Sort files into directories based on specific criteria"
@@ -26,6 +40,25 @@ pub enum Commands {
         about = "Delete junk and temporary files from a directory"
     )]
     Clean(CleanCommand),
+    #[command(
+        name = "dupes",
+        about = "Find duplicate files and remove or hardlink the extra copies"
+    )]
+    Dupes(DupesCommand),
+    #[command(
+        name = "undo",
+        about = "Revert files moved or created by a previous run"
+    )]
+    Undo(UndoCommand),
+    #[command(name = "history", about = "List runs recorded in the undo journal")]
+    History(HistoryCommand),
+    #[command(name = "trash", about = "List, restore or empty trashed files")]
+    Trash(TrashCommand),
+    #[command(
+        name = "config",
+        about = "Show configuration merged from all config layers"
+    )]
+    Config(ConfigCommand),
 }
 
 impl Commands {
@@ -34,6 +67,36 @@ impl Commands {
             Commands::Find(cmd) => cmd.execute(context),
             Commands::Sort(cmd) => cmd.execute(context),
             Commands::Clean(cmd) => cmd.execute(context),
+            Commands::Dupes(cmd) => cmd.execute(context),
+            Commands::Undo(cmd) => cmd.execute(context),
+            Commands::History(cmd) => cmd.execute(context),
+            Commands::Trash(cmd) => cmd.execute(context),
+            Commands::Config(cmd) => cmd.execute(context),
+        }
+    }
+
+    /// Directory the command works on, project config files are looked up from it
+    pub fn directory(&self) -> Option<&Path> {
+        match self {
+            Commands::Find(cmd) => Some(cmd.directory()),
+            Commands::Sort(cmd) => Some(cmd.directory()),
+            Commands::Clean(cmd) => Some(cmd.directory()),
+            Commands::Dupes(cmd) => Some(cmd.directory()),
+            Commands::Config(cmd) => Some(cmd.directory()),
+            Commands::Undo(_) | Commands::History(_) | Commands::Trash(_) => None,
+        }
+    }
+
+    /// Take settings from config. Values given as flags are the top layer of config,
+    /// so commands can overwrite their settings unconditionally
+    pub fn configure(&mut self, config: &Config) {
+        match self {
+            Commands::Find(cmd) => cmd.configure(config),
+            Commands::Sort(cmd) => cmd.configure(config),
+            Commands::Clean(cmd) => cmd.configure(config),
+            Commands::Dupes(cmd) => cmd.configure(config),
+            Commands::Undo(cmd) => cmd.configure(config),
+            Commands::History(_) | Commands::Trash(_) | Commands::Config(_) => {}
         }
     }
 }
@@ -56,6 +119,31 @@ impl Display for Commands {
                 writeln!(f, "{}", cmd)?;
                 Ok(())
             }
+            Commands::Dupes(cmd) => {
+                writeln!(f, "{}: dupes", "command_name".bright_cyan())?;
+                writeln!(f, "{}", cmd)?;
+                Ok(())
+            }
+            Commands::Undo(cmd) => {
+                writeln!(f, "{}: undo", "command_name".bright_cyan())?;
+                writeln!(f, "{}", cmd)?;
+                Ok(())
+            }
+            Commands::History(cmd) => {
+                writeln!(f, "{}: history", "command_name".bright_cyan())?;
+                writeln!(f, "{}", cmd)?;
+                Ok(())
+            }
+            Commands::Trash(cmd) => {
+                writeln!(f, "{}: trash", "command_name".bright_cyan())?;
+                writeln!(f, "{}", cmd)?;
+                Ok(())
+            }
+            Commands::Config(cmd) => {
+                writeln!(f, "{}: config", "command_name".bright_cyan())?;
+                writeln!(f, "{}", cmd)?;
+                Ok(())
+            }
         }
     }
 }
@@ -1,62 +1,33 @@
-use std::{fmt::Display, fs, path::PathBuf};
-
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anstream::{print, println};
 use clap::Parser;
-use log::{debug, info, trace, warn};
+use log::{debug, trace};
 use owo_colors::OwoColorize;
 
 use crate::cli::Cli;
-use crate::confirmation::confirm;
+use crate::commands::plan::{Operation, Plan, PlanFormat};
+use crate::config::Config;
+use crate::confirmation::choose;
+use crate::journal::Journal;
 
 pub(super) use super::find::FilesList;
+use projects::find_projects;
+use retention::{RetainedFile, RetentionConfig, RetentionGroup, RetentionPolicy};
+pub use rules::CleanConfig;
+use rules::{JunkRule, JunkRules};
+use selection::{Candidate, review};
 
-/// File name patterns to delete (checked via glob-like matching)
-const JUNK_FILE_PATTERNS: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
-
-/// File extensions to delete (without the leading dot)
-const JUNK_EXTENSIONS: &[&str] = &[
-    "tmp",
-    "temp",
-    "swp",
-    "swo",
-    "bak",
-    "old",
-    "autosave",
-    "log",
-    "core",
-    "dmp",
-    "stackdump",
-    "pyc",
-    "pyo",
-    "class",
-    "ilk",
-];
-
-/// Prefixes/suffixes checked separately:
-/// - files ending with `~`
-/// - files matching `*.log.*`
-///
-/// Directory names to delete entirely
-const JUNK_DIR_NAMES: &[&str] = &[
-    "tmp",
-    "temp",
-    ".cache",
-    "cache",
-    "Cache",
-    "Caches",
-    "__pycache__",
-    "target",
-    "dist",
-    "build",
-    "out",
-    ".gradle",
-    ".pytest_cache",
-    ".mypy_cache",
-    ".ruff_cache",
-    ".parcel-cache",
-    ".vite",
-    ".next",
-    ".nuxt",
-];
+mod projects;
+mod retention;
+mod rules;
+mod selection;
 
 #[derive(Parser, Debug, Default)]
 pub struct CleanCommand {
@@ -65,110 +36,316 @@ pub struct CleanCommand {
     #[arg(
         short = 'a',
         long = "all",
+        overrides_with = "no_hidden",
         help = "Include hidden files (dotfiles) in the search"
     )]
     search_hidden: bool,
+    #[arg(
+        long = "no-all",
+        overrides_with = "search_hidden",
+        help = "Leave out hidden files even when config includes them"
+    )]
+    no_hidden: bool,
     #[arg(
         short = 'r',
         long = "recursive",
         help = "Search directories recursively"
     )]
     search_recursive: bool,
+    #[arg(
+        long,
+        help = "Move junk files to trash instead of deleting them, so they can be restored"
+    )]
+    trash: bool,
+    #[arg(
+        long,
+        help = "Report build artifacts and reclaimable size of every project found recursively, without deleting anything"
+    )]
+    projects: bool,
+    #[arg(
+        long,
+        help = "Use only junk rules of profile from config, e.g. 'dev' or 'media'"
+    )]
+    profile: Option<String>,
+    #[arg(long, help = "Print effective junk rules and profiles, then exit")]
+    list_rules: bool,
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Apply retention to files with matching names instead of deleting them as junk. Can be repeated"
+    )]
+    retain: Vec<String>,
+    #[arg(
+        long,
+        requires = "retain",
+        help = "Always keep this many newest files of every directory"
+    )]
+    keep_newest: Option<usize>,
+    #[arg(
+        long,
+        requires = "retain",
+        help = "Delete retained files not modified for longer, e.g. '30d'"
+    )]
+    max_age: Option<String>,
+    #[arg(
+        long,
+        requires = "retain",
+        help = "Keep newest retained files of every directory until their total size reaches limit, e.g. '1G'"
+    )]
+    max_total_size: Option<String>,
+    #[arg(
+        short = 'y',
+        long,
+        help = "Remove all found junk without asking, for use in scripts"
+    )]
+    yes: bool,
+    #[arg(
+        long,
+        help = "Print junk files that would be deleted without deleting them"
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "tree",
+        help = "How planned operations are printed by --dry-run"
+    )]
+    plan_format: PlanFormat,
 }
 
 impl CleanCommand {
-    pub fn execute(&self, _context: &Cli) -> anyhow::Result<()> {
+    pub fn configure(&mut self, config: &Config) {
+        self.search_hidden = config.hidden.value;
+        self.plan_format = config.plan_format.value;
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
         debug!("Executing 'clean' command");
         trace!("with configuration: {self}");
 
-        if !confirm(
-            "This command will permanently delete junk files and directories. Make sure that you have closed all applications that might be using these files. Are you sure you want to proceed?",
-        )? {
-            debug!("User declined to delete files. Aborting command execution.");
+        if self.projects {
+            return self.report_projects();
+        }
+
+        let rules = JunkRules::new(context.config.clean.clone())?;
+        let active = rules.active(self.profile.as_deref())?;
+        let command_line_policy = self.retention_policy()?;
+        let mut retention = rules.active_retention(self.profile.as_deref())?;
+        retention.extend(command_line_policy.as_ref());
+        if self.list_rules {
+            print_rules(&rules, &active, &retention);
             return Ok(());
         }
 
-        let files = FilesList::new(&self.directory, self.search_recursive, self.search_hidden)?;
+        let (candidates, retention_groups) = self.scan(&active, &retention)?;
+        if self.dry_run {
+            into_plan(candidates).print(self.plan_format);
+            print_retention(&retention_groups);
+            return Ok(());
+        }
 
-        let mut deleted_files: u64 = 0;
-        let mut deleted_dirs: u64 = 0;
-        let mut total_bytes: u64 = 0;
+        if candidates.is_empty() {
+            print_retention(&retention_groups);
+            println!("{}", "No junk files found".bright_green());
+            return Ok(());
+        }
 
-        for entry in files {
-            let entry = entry?;
-            let metadata = entry.metadata()?;
-            let name = entry.file_name().to_string_lossy().into_owned();
-
-            if metadata.is_dir() {
-                if Self::is_junk_dir(&name) {
-                    let path = entry.path();
-                    let dir_size = dir_size_recursive(&path);
-                    info!("{} {}", "Deleting directory:".bright_red(), path.display());
-                    match fs::remove_dir_all(&path) {
-                        Ok(()) => {
-                            deleted_dirs += 1;
-                            total_bytes += dir_size;
-                        }
-                        Err(e) => {
-                            warn!("Failed to delete directory {}: {}", path.display(), e);
-                        }
-                    }
-                }
-            } else if Self::is_junk_file(&name) {
-                let path = entry.path();
-                let size = metadata.len();
-                info!("{} {}", "Deleting file:".bright_red(), path.display());
-                match fs::remove_file(&path) {
-                    Ok(()) => {
-                        deleted_files += 1;
-                        total_bytes += size;
-                    }
-                    Err(e) => {
-                        warn!("Failed to delete file {}: {}", path.display(), e);
-                    }
-                }
-            }
+        let plan = if self.yes {
+            into_plan(candidates)
+        } else {
+            println!(
+                "Found {} junk files and directories to {}. Make sure that you have closed all applications that might be using them.",
+                candidates.len().bright_purple(),
+                if self.trash {
+                    "move to trash"
+                } else {
+                    "permanently delete"
+                },
+            );
+            review(candidates, choose)?.into_iter().collect()
+        };
+        if plan.is_empty() {
+            debug!("User selected nothing to remove. Aborting command execution.");
+            println!("{}", "Nothing selected".bright_green());
+            return Ok(());
         }
 
-        println!(
-            "\n{}\n  {} file(s) deleted\n  {} directory(ies) deleted\n  {} freed",
-            "Clean summary:".bold(),
-            deleted_files.bright_green(),
-            deleted_dirs.bright_green(),
-            format_bytes(total_bytes).bright_green(),
-        );
+        let summary = plan.execute(&mut Journal::open()?.start_run()?)?;
+
+        if self.trash {
+            println!(
+                "\n{}\n  {} file(s) trashed\n  {} directory(ies) trashed",
+                "Clean summary:".bold(),
+                summary.trashed_files.bright_green(),
+                summary.trashed_dirs.bright_green(),
+            );
+        } else {
+            println!(
+                "\n{}\n  {} file(s) deleted\n  {} directory(ies) deleted\n  {} freed",
+                "Clean summary:".bold(),
+                summary.deleted_files.bright_green(),
+                summary.deleted_dirs.bright_green(),
+                format_bytes(summary.freed_bytes).bright_green(),
+            );
+        }
+        print_retention(&retention_groups);
 
         Ok(())
     }
 
-    fn is_junk_file(name: &str) -> bool {
-        // Exact name match
-        if JUNK_FILE_PATTERNS.contains(&name) {
-            return true;
+    /// Retention policy from command line flags
+    fn retention_policy(&self) -> anyhow::Result<Option<RetentionPolicy>> {
+        if self.retain.is_empty() {
+            return Ok(None);
         }
 
-        // Files ending with ~
-        if name.ends_with('~') {
-            return true;
+        let config = RetentionConfig {
+            patterns: self.retain.clone(),
+            keep_newest: self.keep_newest,
+            max_age: self.max_age.clone(),
+            max_total_size: self.max_total_size.clone(),
+            ..Default::default()
+        };
+        Ok(Some(RetentionPolicy::new(
+            "command-line".to_string(),
+            config,
+        )?))
+    }
+
+    /// Collect files and directories matched by junk rules as removal candidates.
+    /// Files matched by retention policies are only removed by their policy.
+    fn scan(
+        &self,
+        rules: &[&JunkRule],
+        retention: &[&RetentionPolicy],
+    ) -> anyhow::Result<(Vec<Candidate>, Vec<RetentionGroup>)> {
+        let files = FilesList::new(&self.directory, self.search_recursive, self.search_hidden)?;
+        let mut candidates = Vec::new();
+        let mut junk_dirs: Vec<PathBuf> = Vec::new();
+        let mut retained: BTreeMap<(usize, PathBuf), Vec<RetainedFile>> = BTreeMap::new();
+
+        for entry in files {
+            let entry = entry?;
+            let path = entry.path();
+            // Contents of junk directory are deleted together with it
+            if junk_dirs.iter().any(|dir| path.starts_with(dir)) {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            let is_dir = metadata.is_dir();
+            if !is_dir
+                && let Some(index) = retention.iter().position(|policy| policy.matches(&path))
+            {
+                retained
+                    .entry((index, retention[index].group_key(&path)))
+                    .or_default()
+                    .push(RetainedFile {
+                        path,
+                        size: metadata.len(),
+                        modified: metadata.modified()?,
+                    });
+                continue;
+            }
+
+            let mut size = None;
+            let mut size_of = || {
+                *size.get_or_insert_with(|| {
+                    if is_dir {
+                        dir_size_recursive(&path)
+                    } else {
+                        metadata.len()
+                    }
+                })
+            };
+
+            let Some(rule) = rules.iter().find(|rule| {
+                rule.matches_path(&path, is_dir) && rule.matches_limits(&metadata, &mut size_of)
+            }) else {
+                continue;
+            };
+            trace!("{} matched rule '{}'", path.display(), rule.name);
+
+            candidates.push(Candidate {
+                source: format!("Rule '{}'", rule.name),
+                operation: self.removal(path.clone(), size_of(), is_dir),
+            });
+            if is_dir {
+                junk_dirs.push(path);
+            }
         }
 
-        // Extension match (e.g. *.tmp, *.bak, etc.)
-        if let Some(ext) = name.rsplit('.').next()
-            && JUNK_EXTENSIONS.contains(&ext)
-        {
-            return true;
+        let now = SystemTime::now();
+        let mut groups = Vec::new();
+        for ((index, key), files) in retained {
+            let decisions = retention[index].apply(files, now);
+            for decision in decisions.iter().filter(|decision| !decision.keep) {
+                candidates.push(Candidate {
+                    source: format!("Retention '{}'", retention[index].name),
+                    operation: self.removal(decision.file.path.clone(), decision.file.size, false),
+                });
+            }
+            groups.push(RetentionGroup {
+                policy: retention[index].name.clone(),
+                key,
+                decisions,
+            });
         }
 
-        // *.log.* pattern (e.g. app.log.1, error.log.2023-01-01)
-        if name.contains(".log.") {
-            return true;
+        Ok((candidates, groups))
+    }
+
+    fn report_projects(&self) -> anyhow::Result<()> {
+        let projects = find_projects(&self.directory, self.search_hidden, dir_size_recursive)?;
+        for project in &projects {
+            println!(
+                "{} {} {}",
+                project.root.display().bold().bright_cyan(),
+                format!("[{}]", project.kinds.join(", ")).bright_purple(),
+                format_bytes(project.reclaimable_bytes()).bright_yellow()
+            );
+            for (path, size) in &project.artifacts {
+                println!(
+                    "  {:>10} {}",
+                    format_bytes(*size).bright_yellow(),
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                );
+            }
         }
 
-        false
+        let total: u64 = projects
+            .iter()
+            .map(|project| project.reclaimable_bytes())
+            .sum();
+        println!();
+        println!(
+            "{} {}",
+            "Projects:".bright_green(),
+            projects.len().to_string().bright_purple()
+        );
+        println!(
+            "{} {}",
+            "Reclaimable space:".bright_green(),
+            format_bytes(total).bright_purple()
+        );
+        Ok(())
     }
 
-    fn is_junk_dir(name: &str) -> bool {
-        JUNK_DIR_NAMES.contains(&name)
+    fn removal(&self, path: PathBuf, size: u64, is_dir: bool) -> Operation {
+        if self.trash {
+            Operation::Trash {
+                path,
+                size,
+                is_dir,
+                trashed: None,
+            }
+        } else {
+            Operation::Delete { path, size, is_dir }
+        }
     }
 }
 
@@ -192,18 +369,122 @@ impl Display for CleanCommand {
             "search_recursive".bright_cyan(),
             self.search_recursive
         )?;
+        writeln!(f, "{}: {}", "trash".bright_cyan(), self.trash)?;
+        writeln!(f, "{}: {}", "projects".bright_cyan(), self.projects)?;
+        writeln!(f, "{}: {:?}", "profile".bright_cyan(), self.profile)?;
+        writeln!(f, "{}: {}", "list_rules".bright_cyan(), self.list_rules)?;
+        writeln!(f, "{}: {:?}", "retain".bright_cyan(), self.retain)?;
+        writeln!(f, "{}: {:?}", "keep_newest".bright_cyan(), self.keep_newest)?;
+        writeln!(f, "{}: {:?}", "max_age".bright_cyan(), self.max_age)?;
+        writeln!(
+            f,
+            "{}: {:?}",
+            "max_total_size".bright_cyan(),
+            self.max_total_size
+        )?;
+        writeln!(f, "{}: {}", "yes".bright_cyan(), self.yes)?;
+        writeln!(f, "{}: {}", "dry_run".bright_cyan(), self.dry_run)?;
+        writeln!(f, "{}: {:?}", "plan_format".bright_cyan(), self.plan_format)?;
         Ok(())
     }
 }
 
+fn print_rules(rules: &JunkRules, active: &[&JunkRule], retention: &[&RetentionPolicy]) {
+    println!("{}", "Active rules:".bold().bright_green());
+    for rule in active {
+        print!("{rule}");
+    }
+
+    let inactive = rules
+        .all()
+        .iter()
+        .filter(|rule| !active.iter().any(|active| active.name == rule.name))
+        .map(|rule| rule.name.as_str())
+        .collect::<Vec<_>>();
+    if !inactive.is_empty() {
+        println!();
+        println!(
+            "{} {}",
+            "Inactive rules:".bold().bright_green(),
+            inactive.join(", ")
+        );
+    }
+
+    if !retention.is_empty() {
+        println!();
+        println!("{}", "Active retention policies:".bold().bright_green());
+        for policy in retention {
+            println!("{}: {policy}", policy.name.bold().bright_cyan());
+        }
+    }
+    let inactive_retention = rules
+        .retention()
+        .iter()
+        .filter(|policy| !retention.iter().any(|active| active.name == policy.name))
+        .map(|policy| policy.name.as_str())
+        .collect::<Vec<_>>();
+    if !inactive_retention.is_empty() {
+        println!(
+            "{} {}",
+            "Inactive retention policies:".bold().bright_green(),
+            inactive_retention.join(", ")
+        );
+    }
+
+    println!();
+    println!("{}", "Profiles:".bold().bright_green());
+    for (profile, names) in rules.profiles() {
+        println!("  {}: {}", profile.bright_cyan(), names.join(", "));
+    }
+}
+
+fn into_plan(candidates: Vec<Candidate>) -> Plan {
+    candidates
+        .into_iter()
+        .map(|candidate| candidate.operation)
+        .collect()
+}
+
+/// Print what every retention policy kept or deleted and why
+fn print_retention(groups: &[RetentionGroup]) {
+    for group in groups {
+        println!();
+        println!(
+            "{} {}",
+            format!("Retention '{}':", group.policy)
+                .bold()
+                .bright_green(),
+            group.key.display().bright_cyan()
+        );
+        for decision in &group.decisions {
+            let action = if decision.keep {
+                "keep  ".bright_green().to_string()
+            } else {
+                "delete".bright_red().to_string()
+            };
+            println!(
+                "  {action} {:>10} {} ({})",
+                format_bytes(decision.file.size).bright_yellow(),
+                decision
+                    .file
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                decision.reason
+            );
+        }
+    }
+}
+
 /// Recursively calculate directory size
-fn dir_size_recursive(path: &PathBuf) -> u64 {
+fn dir_size_recursive(path: &Path) -> u64 {
     let mut total: u64 = 0;
     if let Ok(entries) = fs::read_dir(path) {
         for entry in entries.flatten() {
             if let Ok(meta) = entry.metadata() {
                 if meta.is_dir() {
-                    total += dir_size_recursive(&entry.path().to_path_buf());
+                    total += dir_size_recursive(&entry.path());
                 } else {
                     total += meta.len();
                 }
@@ -214,7 +495,7 @@ fn dir_size_recursive(path: &PathBuf) -> u64 {
 }
 
 /// Format bytes into human-readable string
-fn format_bytes(bytes: u64) -> String {
+pub(super) fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * KB;
     const GB: u64 = 1024 * MB;
@@ -232,82 +513,271 @@ fn format_bytes(bytes: u64) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::CleanCommand;
+    use std::path::Path;
+
+    use super::{CleanCommand, CleanConfig, JunkRules, into_plan};
+    use crate::commands::plan::Operation;
+
+    fn is_junk(name: &str, is_dir: bool) -> bool {
+        let rules = JunkRules::new(CleanConfig::default()).unwrap();
+        // Absolute parent without manifests, so project detection doesn't look at the working directory
+        let path = Path::new("/fo-test-missing").join(name);
+        rules
+            .active(None)
+            .unwrap()
+            .iter()
+            .any(|rule| rule.matches_path(&path, is_dir))
+    }
+
+    fn is_junk_file(name: &str) -> bool {
+        is_junk(name, false)
+    }
+
+    fn is_junk_dir(name: &str) -> bool {
+        is_junk(name, true)
+    }
 
     #[test]
     fn test_is_junk_file_exact_names() {
-        assert!(CleanCommand::is_junk_file(".DS_Store"));
-        assert!(CleanCommand::is_junk_file("Thumbs.db"));
-        assert!(CleanCommand::is_junk_file("desktop.ini"));
+        assert!(is_junk_file(".DS_Store"));
+        assert!(is_junk_file("Thumbs.db"));
+        assert!(is_junk_file("desktop.ini"));
     }
 
     #[test]
     fn test_is_junk_file_extensions() {
-        assert!(CleanCommand::is_junk_file("file.tmp"));
-        assert!(CleanCommand::is_junk_file("data.temp"));
-        assert!(CleanCommand::is_junk_file(".file.swp"));
-        assert!(CleanCommand::is_junk_file(".file.swo"));
-        assert!(CleanCommand::is_junk_file("backup.bak"));
-        assert!(CleanCommand::is_junk_file("config.old"));
-        assert!(CleanCommand::is_junk_file("draft.autosave"));
-        assert!(CleanCommand::is_junk_file("app.log"));
-        assert!(CleanCommand::is_junk_file("core.core"));
-        assert!(CleanCommand::is_junk_file("crash.dmp"));
-        assert!(CleanCommand::is_junk_file("crash.stackdump"));
-        assert!(CleanCommand::is_junk_file("module.pyc"));
-        assert!(CleanCommand::is_junk_file("module.pyo"));
-        assert!(CleanCommand::is_junk_file("Main.class"));
-        assert!(CleanCommand::is_junk_file("lib.ilk"));
+        assert!(is_junk_file("file.tmp"));
+        assert!(is_junk_file("data.temp"));
+        assert!(is_junk_file(".file.swp"));
+        assert!(is_junk_file(".file.swo"));
+        assert!(is_junk_file("backup.bak"));
+        assert!(is_junk_file("config.old"));
+        assert!(is_junk_file("draft.autosave"));
+        assert!(is_junk_file("app.log"));
+        assert!(is_junk_file("core.core"));
+        assert!(is_junk_file("crash.dmp"));
+        assert!(is_junk_file("crash.stackdump"));
+        assert!(is_junk_file("module.pyc"));
+        assert!(is_junk_file("module.pyo"));
+        assert!(is_junk_file("Main.class"));
+        assert!(is_junk_file("lib.ilk"));
     }
 
     #[test]
     fn test_is_junk_file_tilde() {
-        assert!(CleanCommand::is_junk_file("document~"));
-        assert!(CleanCommand::is_junk_file("file.txt~"));
+        assert!(is_junk_file("document~"));
+        assert!(is_junk_file("file.txt~"));
     }
 
     #[test]
     fn test_is_junk_file_log_dot() {
-        assert!(CleanCommand::is_junk_file("app.log.1"));
-        assert!(CleanCommand::is_junk_file("error.log.2023-01-01"));
+        assert!(is_junk_file("app.log.1"));
+        assert!(is_junk_file("error.log.2023-01-01"));
     }
 
     #[test]
     fn test_is_not_junk_file() {
-        assert!(!CleanCommand::is_junk_file("main.rs"));
-        assert!(!CleanCommand::is_junk_file("Cargo.toml"));
-        assert!(!CleanCommand::is_junk_file("README.md"));
-        assert!(!CleanCommand::is_junk_file("data.json"));
+        assert!(!is_junk_file("main.rs"));
+        assert!(!is_junk_file("Cargo.toml"));
+        assert!(!is_junk_file("README.md"));
+        assert!(!is_junk_file("data.json"));
     }
 
     #[test]
     fn test_is_junk_dir() {
-        assert!(CleanCommand::is_junk_dir("tmp"));
-        assert!(CleanCommand::is_junk_dir("temp"));
-        assert!(CleanCommand::is_junk_dir(".cache"));
-        assert!(CleanCommand::is_junk_dir("cache"));
-        assert!(CleanCommand::is_junk_dir("Cache"));
-        assert!(CleanCommand::is_junk_dir("Caches"));
-        assert!(CleanCommand::is_junk_dir("__pycache__"));
-        assert!(CleanCommand::is_junk_dir("target"));
-        assert!(CleanCommand::is_junk_dir("dist"));
-        assert!(CleanCommand::is_junk_dir("build"));
-        assert!(CleanCommand::is_junk_dir("out"));
-        assert!(CleanCommand::is_junk_dir(".gradle"));
-        assert!(CleanCommand::is_junk_dir(".pytest_cache"));
-        assert!(CleanCommand::is_junk_dir(".mypy_cache"));
-        assert!(CleanCommand::is_junk_dir(".ruff_cache"));
-        assert!(CleanCommand::is_junk_dir(".parcel-cache"));
-        assert!(CleanCommand::is_junk_dir(".vite"));
-        assert!(CleanCommand::is_junk_dir(".next"));
-        assert!(CleanCommand::is_junk_dir(".nuxt"));
+        assert!(is_junk_dir("tmp"));
+        assert!(is_junk_dir("temp"));
+        assert!(is_junk_dir(".cache"));
+        assert!(is_junk_dir("cache"));
+        assert!(is_junk_dir("Cache"));
+        assert!(is_junk_dir("Caches"));
+        assert!(is_junk_dir("__pycache__"));
+        assert!(is_junk_dir(".pytest_cache"));
+        assert!(is_junk_dir(".mypy_cache"));
+        assert!(is_junk_dir(".ruff_cache"));
     }
 
     #[test]
     fn test_is_not_junk_dir() {
-        assert!(!CleanCommand::is_junk_dir("src"));
-        assert!(!CleanCommand::is_junk_dir("lib"));
-        assert!(!CleanCommand::is_junk_dir("tests"));
+        assert!(!is_junk_dir("src"));
+        assert!(!is_junk_dir("lib"));
+        assert!(!is_junk_dir("tests"));
+        // Build output is junk only next to a project manifest
+        assert!(!is_junk_dir("target"));
+        assert!(!is_junk_dir("build"));
+    }
+
+    #[test]
+    fn test_plan_deletes_build_dirs_only_in_projects() {
+        let temp_dir = std::env::temp_dir().join("fo_test_clean_plan_projects");
+        std::fs::create_dir_all(temp_dir.join("app/target")).unwrap();
+        std::fs::create_dir_all(temp_dir.join("blueprints/build")).unwrap();
+        std::fs::write(temp_dir.join("app/Cargo.toml"), "").unwrap();
+        std::fs::write(temp_dir.join("app/target/app"), "12").unwrap();
+        std::fs::write(temp_dir.join("blueprints/build/house.dwg"), "123").unwrap();
+        let command = CleanCommand {
+            directory: temp_dir.clone(),
+            search_recursive: true,
+            ..Default::default()
+        };
+        let rules = JunkRules::new(CleanConfig::default()).unwrap();
+
+        let operations = into_plan(command.scan(&rules.active(None).unwrap(), &[]).unwrap().0)
+            .operations()
+            .to_vec();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            operations,
+            vec![Operation::Delete {
+                path: temp_dir.join("app/target"),
+                size: 2,
+                is_dir: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_applies_rule_limits() {
+        let temp_dir = std::env::temp_dir().join("fo_test_clean_plan_limits");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        for (name, content, days_old) in [
+            ("small_old.log", "123", 30),
+            ("large_new.log", "12345", 0),
+            ("large_old.log", "12345", 30),
+        ] {
+            let path = temp_dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            let modified =
+                std::time::SystemTime::now() - std::time::Duration::from_secs(days_old * 24 * 3600);
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+        let config: CleanConfig =
+            toml::from_str("[rules.logs]\nmin_age = \"14d\"\nmin_size = \"4\"").unwrap();
+        let rules = JunkRules::new(config).unwrap();
+        let command = CleanCommand {
+            directory: temp_dir.clone(),
+            ..Default::default()
+        };
+
+        let operations = into_plan(command.scan(&rules.active(None).unwrap(), &[]).unwrap().0);
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            operations.operations(),
+            [Operation::Delete {
+                path: temp_dir.join("large_old.log"),
+                size: 5,
+                is_dir: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_retention_overrides_junk_rules() {
+        let temp_dir = std::env::temp_dir().join("fo_test_clean_plan_retention");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, days_old) in [
+            ("app.log.1", 1),
+            ("app.log.2", 2),
+            ("app.log.3", 3),
+            ("x.tmp", 0),
+        ] {
+            let path = temp_dir.join(name);
+            std::fs::write(&path, "12").unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - std::time::Duration::from_secs(days_old * 24 * 3600))
+                .unwrap();
+        }
+        let command = CleanCommand {
+            directory: temp_dir.clone(),
+            retain: vec!["*.log.*".to_string()],
+            keep_newest: Some(2),
+            ..Default::default()
+        };
+        let rules = JunkRules::new(CleanConfig::default()).unwrap();
+        let policy = command.retention_policy().unwrap().unwrap();
+
+        let (candidates, groups) = command
+            .scan(&rules.active(None).unwrap(), &[&policy])
+            .unwrap();
+        let mut deleted = candidates
+            .iter()
+            .map(|candidate| match &candidate.operation {
+                Operation::Delete { path, .. } => (
+                    candidate.source.as_str(),
+                    path.file_name().unwrap().to_owned(),
+                ),
+                other => panic!("unexpected operation {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        deleted.sort();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            deleted,
+            vec![
+                ("Retention 'command-line'", "app.log.3".into()),
+                ("Rule 'temp'", "x.tmp".into())
+            ]
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]
+                .decisions
+                .iter()
+                .map(|decision| decision.keep)
+                .collect::<Vec<_>>(),
+            vec![true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_plan_skips_contents_of_junk_dirs() {
+        let temp_dir = std::env::temp_dir().join("fo_test_clean_plan");
+        std::fs::create_dir_all(temp_dir.join("cache")).unwrap();
+        std::fs::write(temp_dir.join("cache/inner.tmp"), "12345").unwrap();
+        std::fs::write(temp_dir.join("notes.bak"), "123").unwrap();
+        std::fs::write(temp_dir.join("notes.txt"), "1").unwrap();
+        let command = CleanCommand {
+            directory: temp_dir.clone(),
+            search_recursive: true,
+            ..Default::default()
+        };
+        let rules = JunkRules::new(CleanConfig::default()).unwrap();
+
+        let mut operations = into_plan(command.scan(&rules.active(None).unwrap(), &[]).unwrap().0)
+            .operations()
+            .to_vec();
+        let still_exists = temp_dir.join("cache/inner.tmp").exists();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+        operations.sort_by_key(|operation| format!("{operation:?}"));
+
+        assert!(still_exists, "planning must not delete anything");
+        assert_eq!(
+            operations,
+            vec![
+                Operation::Delete {
+                    path: temp_dir.join("cache"),
+                    size: 5,
+                    is_dir: true,
+                },
+                Operation::Delete {
+                    path: temp_dir.join("notes.bak"),
+                    size: 3,
+                    is_dir: false,
+                },
+            ]
+        );
     }
 
     #[test]
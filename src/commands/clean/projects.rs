@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{trace, warn};
+
+/// Build system recognized by its manifest file, with directories it generates next to it
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProjectKind {
+    pub name: &'static str,
+    manifests: &'static [&'static str],
+    artifact_dirs: &'static [&'static str],
+}
+
+/// Known project kinds. Artifact directories are junk only next to a matching manifest,
+/// so e.g. a `build` folder with blueprints is never touched.
+pub const PROJECT_KINDS: &[ProjectKind] = &[
+    ProjectKind {
+        name: "Rust",
+        manifests: &["Cargo.toml"],
+        artifact_dirs: &["target"],
+    },
+    ProjectKind {
+        name: "Maven",
+        manifests: &["pom.xml"],
+        artifact_dirs: &["target"],
+    },
+    ProjectKind {
+        name: "sbt",
+        manifests: &["build.sbt"],
+        artifact_dirs: &["target"],
+    },
+    ProjectKind {
+        name: "Gradle",
+        manifests: &[
+            "build.gradle",
+            "build.gradle.kts",
+            "settings.gradle",
+            "settings.gradle.kts",
+        ],
+        artifact_dirs: &["build", "out", ".gradle"],
+    },
+    ProjectKind {
+        name: "Node",
+        manifests: &["package.json"],
+        artifact_dirs: &[
+            "dist",
+            "build",
+            "out",
+            ".next",
+            ".nuxt",
+            ".parcel-cache",
+            ".vite",
+        ],
+    },
+    ProjectKind {
+        name: "Python",
+        manifests: &["pyproject.toml", "setup.py", "setup.cfg"],
+        artifact_dirs: &["build", "dist"],
+    },
+    ProjectKind {
+        name: "CMake",
+        manifests: &["CMakeLists.txt"],
+        artifact_dirs: &["build", "out"],
+    },
+];
+
+impl ProjectKind {
+    fn is_root(&self, dir: &Path) -> bool {
+        self.manifests
+            .iter()
+            .any(|manifest| dir.join(manifest).is_file())
+    }
+}
+
+/// Project kind whose artifact directory `path` is, if it sits next to the kind's manifest
+pub fn artifact_kind(path: &Path) -> Option<&'static ProjectKind> {
+    let name = path.file_name()?.to_str()?;
+    let parent = path.parent()?;
+    PROJECT_KINDS
+        .iter()
+        .find(|kind| kind.artifact_dirs.contains(&name) && kind.is_root(parent))
+}
+
+/// Project root with artifact directories that can be deleted
+#[derive(Debug, PartialEq)]
+pub struct Project {
+    pub root: PathBuf,
+    pub kinds: Vec<&'static str>,
+    /// Existing artifact directories with their sizes
+    pub artifacts: Vec<(PathBuf, u64)>,
+}
+
+impl Project {
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.artifacts.iter().map(|(_, size)| size).sum()
+    }
+}
+
+/// Recursively find project roots under `directory`. Artifact directories are not searched,
+/// but other subdirectories are, so nested projects (e.g. workspace members) are found too.
+pub fn find_projects(
+    directory: &Path,
+    search_hidden: bool,
+    dir_size: impl Fn(&Path) -> u64 + Copy,
+) -> std::io::Result<Vec<Project>> {
+    let mut projects = Vec::new();
+    visit(directory, search_hidden, dir_size, &mut projects)?;
+    Ok(projects)
+}
+
+fn visit(
+    directory: &Path,
+    search_hidden: bool,
+    dir_size: impl Fn(&Path) -> u64 + Copy,
+    projects: &mut Vec<Project>,
+) -> std::io::Result<()> {
+    let kinds = PROJECT_KINDS
+        .iter()
+        .filter(|kind| kind.is_root(directory))
+        .collect::<Vec<_>>();
+
+    let mut subdirectories = Vec::new();
+    let mut artifacts = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        // Symlinked directories are not followed, they may point outside of the project
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if kinds
+            .iter()
+            .any(|kind| kind.artifact_dirs.contains(&name.as_str()))
+        {
+            let size = dir_size(&path);
+            artifacts.push((path, size));
+        } else if search_hidden || !name.starts_with('.') {
+            subdirectories.push(path);
+        }
+    }
+
+    if !kinds.is_empty() {
+        trace!("Found project {}", directory.display());
+        artifacts.sort();
+        projects.push(Project {
+            root: directory.to_path_buf(),
+            kinds: kinds.iter().map(|kind| kind.name).collect(),
+            artifacts,
+        });
+    }
+
+    subdirectories.sort();
+    for subdirectory in subdirectories {
+        if let Err(err) = visit(&subdirectory, search_hidden, dir_size, projects) {
+            warn!("Failed to read {}: {}", subdirectory.display(), err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("Cargo.toml", "target", Some("Rust") ; "rust target")]
+    #[test_case("package.json", "dist", Some("Node") ; "node dist")]
+    #[test_case("build.gradle.kts", "build", Some("Gradle") ; "gradle build")]
+    #[test_case("CMakeLists.txt", "out", Some("CMake") ; "cmake out")]
+    #[test_case("pyproject.toml", "target", None ; "target is not python artifact")]
+    #[test_case("notes.txt", "build", None ; "no manifest")]
+    fn test_artifact_kind(manifest: &str, artifact: &str, expected: Option<&str>) {
+        let temp_dir = std::env::temp_dir()
+            .join("fo_test_artifact_kind")
+            .join(format!("{manifest}_{artifact}"));
+        fs::create_dir_all(temp_dir.join(artifact)).unwrap();
+        fs::write(temp_dir.join(manifest), "").unwrap();
+
+        let kind = artifact_kind(&temp_dir.join(artifact)).map(|kind| kind.name);
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(kind, expected);
+    }
+
+    #[test]
+    fn test_find_projects() {
+        let temp_dir = std::env::temp_dir().join("fo_test_find_projects");
+        let app = temp_dir.join("app");
+        let member = app.join("crates/member");
+        let blueprints = temp_dir.join("blueprints");
+        for dir in [
+            app.join("target/debug"),
+            app.join("dist"),
+            member.join("target"),
+            blueprints.join("build"),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(app.join("Cargo.toml"), "").unwrap();
+        fs::write(app.join("package.json"), "").unwrap();
+        fs::write(member.join("Cargo.toml"), "").unwrap();
+        // Manifest inside artifact dir doesn't make a project
+        fs::write(app.join("target/debug/Cargo.toml"), "").unwrap();
+
+        let projects = find_projects(&temp_dir, false, |_| 10).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            projects,
+            vec![
+                Project {
+                    root: app.clone(),
+                    kinds: vec!["Rust", "Node"],
+                    artifacts: vec![(app.join("dist"), 10), (app.join("target"), 10)],
+                },
+                Project {
+                    root: member.clone(),
+                    kinds: vec!["Rust"],
+                    artifacts: vec![(member.join("target"), 10)],
+                },
+            ]
+        );
+        assert_eq!(projects[0].reclaimable_bytes(), 20);
+    }
+}
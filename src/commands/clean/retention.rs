@@ -0,0 +1,333 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::find::{PathGlob, parse_duration},
+    size::parse_size,
+};
+
+/// `[clean.retention.<name>]` section of config
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Globs matched against file name
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub group_by: GroupBy,
+    /// Newest files that are always kept
+    pub keep_newest: Option<usize>,
+    /// Files not modified for longer are deleted, e.g. `14d`
+    pub max_age: Option<String>,
+    /// Newest files are kept until their total size reaches limit, e.g. `100M`
+    pub max_total_size: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Which files of a policy are compared with each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    /// All matching files in the same directory
+    #[default]
+    Directory,
+    /// Files in the same directory with the same name before the first dot,
+    /// so `app.log.1` and `db.log.1` are separate rotations
+    Name,
+}
+
+/// Why file is kept or deleted
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    Newest(usize),
+    WithinLimits,
+    TooOld(String),
+    OverTotalSize(String),
+    BeyondNewest(usize),
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Newest(count) => write!(f, "one of {count} newest"),
+            Reason::WithinLimits => write!(f, "within limits"),
+            Reason::TooOld(age) => write!(f, "older than {age}"),
+            Reason::OverTotalSize(size) => write!(f, "over total size {size}"),
+            Reason::BeyondNewest(count) => write!(f, "not among {count} newest"),
+        }
+    }
+}
+
+/// File matched by retention policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetainedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub file: RetainedFile,
+    pub keep: bool,
+    pub reason: Reason,
+}
+
+/// Decisions for one group of files
+#[derive(Debug, PartialEq)]
+pub struct RetentionGroup {
+    pub policy: String,
+    pub key: PathBuf,
+    pub decisions: Vec<Decision>,
+}
+
+pub struct RetentionPolicy {
+    pub name: String,
+    pub enabled: bool,
+    patterns: Vec<String>,
+    globs: Vec<PathGlob>,
+    group_by: GroupBy,
+    keep_newest: Option<usize>,
+    max_age: Option<(String, Duration)>,
+    max_total_size: Option<(String, u64)>,
+}
+
+impl RetentionPolicy {
+    pub fn new(name: String, config: RetentionConfig) -> anyhow::Result<Self> {
+        if config.keep_newest.is_none()
+            && config.max_age.is_none()
+            && config.max_total_size.is_none()
+        {
+            bail!("Retention policy needs at least one of keep_newest, max_age or max_total_size");
+        }
+        if config.patterns.is_empty() {
+            bail!("Retention policy needs at least one pattern");
+        }
+
+        let globs = config
+            .patterns
+            .iter()
+            .map(|pattern| PathGlob::new(pattern, false))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let max_age = match config.max_age {
+            Some(text) => {
+                let age = parse_duration(&text)?.unsigned_abs();
+                Some((text, age))
+            }
+            None => None,
+        };
+        let max_total_size = match config.max_total_size {
+            Some(text) => {
+                let size = parse_size(&text)?;
+                Some((text, size))
+            }
+            None => None,
+        };
+
+        Ok(RetentionPolicy {
+            name,
+            enabled: config.enabled.unwrap_or(true),
+            patterns: config.patterns,
+            globs,
+            group_by: config.group_by,
+            keep_newest: config.keep_newest,
+            max_age,
+            max_total_size,
+        })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.globs.iter().any(|glob| glob.is_matched(&name))
+    }
+
+    /// Files with the same key are compared with each other
+    pub fn group_key(&self, path: &Path) -> PathBuf {
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        match self.group_by {
+            GroupBy::Directory => directory,
+            GroupBy::Name => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                directory.join(name.split('.').next().unwrap_or_default())
+            }
+        }
+    }
+
+    /// Decide for every file of group, newest first
+    pub fn apply(&self, mut files: Vec<RetainedFile>, now: SystemTime) -> Vec<Decision> {
+        files.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)));
+
+        let mut kept_size = 0;
+        let mut decisions = Vec::new();
+        for (index, file) in files.into_iter().enumerate() {
+            let age = now.duration_since(file.modified).unwrap_or_default();
+            let deletion = match (self.keep_newest, &self.max_age, &self.max_total_size) {
+                (Some(count), _, _) if index < count => None,
+                (_, Some((text, max_age)), _) if age > *max_age => {
+                    Some(Reason::TooOld(text.clone()))
+                }
+                (_, _, Some((text, limit))) if kept_size + file.size > *limit => {
+                    Some(Reason::OverTotalSize(text.clone()))
+                }
+                (Some(count), None, None) => Some(Reason::BeyondNewest(count)),
+                _ => None,
+            };
+
+            let decision = match deletion {
+                Some(reason) => Decision {
+                    file,
+                    keep: false,
+                    reason,
+                },
+                None => {
+                    kept_size += file.size;
+                    let reason = match self.keep_newest {
+                        Some(count) if index < count => Reason::Newest(count),
+                        _ => Reason::WithinLimits,
+                    };
+                    Decision {
+                        file,
+                        keep: true,
+                        reason,
+                    }
+                }
+            };
+            decisions.push(decision);
+        }
+        decisions
+    }
+}
+
+impl Display for RetentionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.patterns.join(", "))?;
+        if let Some(count) = self.keep_newest {
+            write!(f, ", keep newest {count}")?;
+        }
+        if let Some((age, _)) = &self.max_age {
+            write!(f, ", max age {age}")?;
+        }
+        if let Some((size, _)) = &self.max_total_size {
+            write!(f, ", max total size {size}")?;
+        }
+        if self.group_by == GroupBy::Name {
+            write!(f, ", grouped by name")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn policy(config: &str) -> RetentionPolicy {
+        RetentionPolicy::new("test".to_string(), toml::from_str(config).unwrap()).unwrap()
+    }
+
+    /// Files `0..count`, file `n` is `n` days old and `n + 1` bytes large
+    fn files(now: SystemTime, count: u64) -> Vec<RetainedFile> {
+        (0..count)
+            .map(|days| RetainedFile {
+                path: PathBuf::from(format!("/logs/app.log.{days}")),
+                size: days + 1,
+                modified: now - DAY * days as u32,
+            })
+            .collect()
+    }
+
+    fn kept(decisions: &[Decision]) -> Vec<(bool, String)> {
+        decisions
+            .iter()
+            .map(|decision| (decision.keep, decision.reason.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_newest_and_max_age() {
+        let now = SystemTime::now();
+        let policy = policy("patterns = [\"*.log.*\"]\nkeep_newest = 2\nmax_age = \"2d\"");
+
+        let decisions = policy.apply(files(now, 5), now);
+
+        assert_eq!(
+            kept(&decisions),
+            vec![
+                (true, "one of 2 newest".to_string()),
+                (true, "one of 2 newest".to_string()),
+                (true, "within limits".to_string()),
+                (false, "older than 2d".to_string()),
+                (false, "older than 2d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keep_newest_only() {
+        let now = SystemTime::now();
+        let policy = policy("patterns = [\"*\"]\nkeep_newest = 1");
+
+        let decisions = policy.apply(files(now, 3), now);
+
+        assert_eq!(
+            kept(&decisions),
+            vec![
+                (true, "one of 1 newest".to_string()),
+                (false, "not among 1 newest".to_string()),
+                (false, "not among 1 newest".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_total_size() {
+        let now = SystemTime::now();
+        // Sizes newest first are 1, 2, 3, 4
+        let policy = policy("patterns = [\"*\"]\nmax_total_size = \"6\"");
+
+        let decisions = policy.apply(files(now, 4), now);
+
+        assert_eq!(
+            decisions
+                .iter()
+                .map(|decision| decision.keep)
+                .collect::<Vec<_>>(),
+            vec![true, true, true, false]
+        );
+        assert_eq!(decisions[3].reason, Reason::OverTotalSize("6".to_string()));
+    }
+
+    #[test]
+    fn test_group_key() {
+        let by_directory = policy("patterns = [\"*\"]\nkeep_newest = 1");
+        let by_name = policy("patterns = [\"*\"]\nkeep_newest = 1\ngroup_by = \"name\"");
+        let path = Path::new("/logs/app.log.1");
+
+        assert_eq!(by_directory.group_key(path), PathBuf::from("/logs"));
+        assert_eq!(by_name.group_key(path), PathBuf::from("/logs/app"));
+        assert!(by_name.matches(path));
+    }
+
+    #[test_case("99999999999999y" ; "out of range")]
+    #[test_case("2 days" ; "not a duration")]
+    fn test_invalid_max_age(max_age: &str) {
+        let config =
+            toml::from_str(&format!("patterns = [\"*.log\"]\nmax_age = \"{max_age}\"")).unwrap();
+        assert!(RetentionPolicy::new("test".to_string(), config).is_err());
+    }
+
+    #[test]
+    fn test_policy_without_limits_is_invalid() {
+        let config = toml::from_str("patterns = [\"*\"]").unwrap();
+        assert!(RetentionPolicy::new("test".to_string(), config).is_err());
+    }
+}
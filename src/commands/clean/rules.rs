@@ -0,0 +1,454 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs::Metadata,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, anyhow, bail};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    projects::artifact_kind,
+    retention::{RetentionConfig, RetentionPolicy},
+};
+use crate::{
+    commands::find::{PathGlob, parse_duration},
+    size::parse_size,
+};
+
+/// Rules used when config doesn't override them, in the same format as user config
+const BUILTIN_RULES: &str = r#"
+[rules.system]
+names = [".DS_Store", "Thumbs.db", "desktop.ini"]
+
+[rules.backups]
+names = ["*~"]
+extensions = ["bak", "old", "autosave", "swp", "swo"]
+
+[rules.temp]
+extensions = ["tmp", "temp"]
+dirs = ["tmp", "temp"]
+
+[rules.logs]
+names = ["*.log.*"]
+extensions = ["log"]
+
+[rules.crash-dumps]
+extensions = ["core", "dmp", "stackdump"]
+
+[rules.bytecode]
+extensions = ["pyc", "pyo", "class", "ilk"]
+
+[rules.caches]
+dirs = [".cache", "cache", "Cache", "Caches", "__pycache__", ".pytest_cache", ".mypy_cache", ".ruff_cache"]
+
+[rules.build-output]
+project_artifacts = true
+
+[profiles]
+dev = ["backups", "temp", "logs", "crash-dumps", "bytecode", "caches", "build-output"]
+media = ["system", "backups", "temp"]
+"#;
+
+/// `[clean]` section of config file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CleanConfig {
+    #[serde(default)]
+    rules: BTreeMap<String, RuleConfig>,
+    /// Profile name to names of rules and retention policies it enables
+    #[serde(default)]
+    profiles: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    retention: BTreeMap<String, RetentionConfig>,
+}
+
+/// Rule as written in config. Every field is optional, so a built-in rule can be partially overridden.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    /// Globs matched against file name, directories are matched only by `dirs`
+    names: Option<Vec<String>>,
+    /// File extensions without the leading dot
+    extensions: Option<Vec<String>>,
+    /// Directory names, deleted with all contents
+    dirs: Option<Vec<String>>,
+    /// Only entries not modified for at least this long, e.g. `7d`
+    min_age: Option<String>,
+    /// Only entries at least this large, e.g. `10M`
+    min_size: Option<String>,
+    /// Build output directories next to a project manifest, see [`artifact_kind`]
+    project_artifacts: Option<bool>,
+    enabled: Option<bool>,
+}
+
+impl CleanConfig {
+    /// Overlay config of higher priority. Rules are merged field by field,
+    /// profiles and retention policies are replaced as a whole.
+    pub fn merge(&mut self, other: CleanConfig) {
+        for (name, rule) in other.rules {
+            self.rules.entry(name).or_default().merge(rule);
+        }
+        self.profiles.extend(other.profiles);
+        self.retention.extend(other.retention);
+    }
+
+    /// Names of all entries, e.g. `rules.logs` or `profiles.dev`
+    pub fn keys(&self) -> Vec<String> {
+        let rules = self.rules.keys().map(|name| format!("rules.{name}"));
+        let profiles = self.profiles.keys().map(|name| format!("profiles.{name}"));
+        let retention = self
+            .retention
+            .keys()
+            .map(|name| format!("retention.{name}"));
+        rules.chain(profiles).chain(retention).collect()
+    }
+
+    /// Merged fields of entry named like in [`keys`](Self::keys), table for rules and
+    /// retention policies, list of enabled names for profiles
+    pub fn entry(&self, key: &str) -> Option<toml::Value> {
+        let (kind, name) = key.split_once('.')?;
+        let value = match kind {
+            "rules" => toml::Value::try_from(self.rules.get(name)?),
+            "profiles" => toml::Value::try_from(self.profiles.get(name)?),
+            "retention" => toml::Value::try_from(self.retention.get(name)?),
+            _ => return None,
+        };
+        value.ok()
+    }
+
+    pub fn builtin() -> Self {
+        toml::from_str(BUILTIN_RULES).expect("Built-in rules should be valid")
+    }
+}
+
+impl RuleConfig {
+    fn merge(&mut self, other: RuleConfig) {
+        self.names = other.names.or(self.names.take());
+        self.extensions = other.extensions.or(self.extensions.take());
+        self.dirs = other.dirs.or(self.dirs.take());
+        self.min_age = other.min_age.or(self.min_age.take());
+        self.min_size = other.min_size.or(self.min_size.take());
+        self.project_artifacts = other.project_artifacts.or(self.project_artifacts);
+        self.enabled = other.enabled.or(self.enabled);
+    }
+}
+
+/// Compiled junk rule
+pub struct JunkRule {
+    pub name: String,
+    pub enabled: bool,
+    config: RuleConfig,
+    globs: Vec<PathGlob>,
+    min_age: Option<Duration>,
+    min_size: Option<u64>,
+}
+
+impl JunkRule {
+    fn new(name: String, config: RuleConfig) -> anyhow::Result<Self> {
+        let globs = config
+            .names
+            .iter()
+            .flatten()
+            .map(|pattern| PathGlob::new(pattern, false))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let min_age = config
+            .min_age
+            .as_deref()
+            .map(parse_duration)
+            .transpose()?
+            .map(|age| age.unsigned_abs());
+        let min_size = config.min_size.as_deref().map(parse_size).transpose()?;
+
+        Ok(JunkRule {
+            name,
+            enabled: config.enabled.unwrap_or(true),
+            config,
+            globs,
+            min_age,
+            min_size,
+        })
+    }
+
+    /// Whether name, extension or project location of entry matches the rule
+    pub fn matches_path(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            return false;
+        };
+        if is_dir {
+            self.config.dirs.iter().flatten().any(|dir| *dir == name)
+                || (self.config.project_artifacts == Some(true) && artifact_kind(path).is_some())
+        } else {
+            self.globs.iter().any(|glob| glob.is_matched(&name))
+                || path.extension().is_some_and(|extension| {
+                    self.config
+                        .extensions
+                        .iter()
+                        .flatten()
+                        .any(|junk| extension.eq_ignore_ascii_case(junk))
+                })
+        }
+    }
+
+    /// Whether entry is old and large enough. Size is only calculated when rule has a size limit.
+    pub fn matches_limits(&self, metadata: &Metadata, size: impl FnOnce() -> u64) -> bool {
+        if let Some(min_age) = self.min_age {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+            if age.is_none_or(|age| age < min_age) {
+                return false;
+            }
+        }
+        self.min_size.is_none_or(|min_size| size() >= min_size)
+    }
+}
+
+impl Display for JunkRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.enabled {
+            "enabled".bright_green().to_string()
+        } else {
+            "disabled".bright_red().to_string()
+        };
+        writeln!(f, "{} ({status})", self.name.bold().bright_cyan())?;
+
+        let lists = [
+            ("names", &self.config.names),
+            ("extensions", &self.config.extensions),
+            ("dirs", &self.config.dirs),
+        ];
+        for (key, values) in lists {
+            if let Some(values) = values {
+                writeln!(f, "  {}: {}", key.bright_purple(), values.join(", "))?;
+            }
+        }
+        if self.config.project_artifacts == Some(true) {
+            writeln!(
+                f,
+                "  {}: build output next to project manifests",
+                "project_artifacts".bright_purple()
+            )?;
+        }
+        if let Some(min_age) = &self.config.min_age {
+            writeln!(f, "  {}: {min_age}", "min_age".bright_purple())?;
+        }
+        if let Some(min_size) = &self.config.min_size {
+            writeln!(f, "  {}: {min_size}", "min_size".bright_purple())?;
+        }
+        Ok(())
+    }
+}
+
+/// Built-in rules merged with user config
+pub struct JunkRules {
+    rules: Vec<JunkRule>,
+    retention: Vec<RetentionPolicy>,
+    profiles: BTreeMap<String, Vec<String>>,
+}
+
+impl JunkRules {
+    pub fn new(user_config: CleanConfig) -> anyhow::Result<Self> {
+        let mut config = CleanConfig::builtin();
+        config.merge(user_config);
+
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|(name, rule)| {
+                JunkRule::new(name.clone(), rule).with_context(|| format!("Invalid rule '{name}'"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let retention = config
+            .retention
+            .into_iter()
+            .map(|(name, policy)| {
+                RetentionPolicy::new(name.clone(), policy)
+                    .with_context(|| format!("Invalid retention policy '{name}'"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        for (profile, names) in &config.profiles {
+            if let Some(unknown) = names.iter().find(|name| {
+                !rules.iter().any(|rule| rule.name == **name)
+                    && !retention.iter().any(|policy| policy.name == **name)
+            }) {
+                bail!("Profile '{profile}' refers to unknown rule '{unknown}'");
+            }
+        }
+
+        Ok(JunkRules {
+            rules,
+            retention,
+            profiles: config.profiles,
+        })
+    }
+
+    pub fn all(&self) -> &[JunkRule] {
+        &self.rules
+    }
+
+    pub fn profiles(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.profiles
+    }
+
+    /// Enabled rules of profile, or all enabled rules without profile
+    pub fn active(&self, profile: Option<&str>) -> anyhow::Result<Vec<&JunkRule>> {
+        let names = self.profile_names(profile)?;
+        Ok(self
+            .rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter(|rule| names.is_none_or(|names| names.contains(&rule.name)))
+            .collect())
+    }
+
+    /// Enabled retention policies of profile, or all enabled policies without profile
+    pub fn active_retention(&self, profile: Option<&str>) -> anyhow::Result<Vec<&RetentionPolicy>> {
+        let names = self.profile_names(profile)?;
+        Ok(self
+            .retention
+            .iter()
+            .filter(|policy| policy.enabled)
+            .filter(|policy| names.is_none_or(|names| names.contains(&policy.name)))
+            .collect())
+    }
+
+    pub fn retention(&self) -> &[RetentionPolicy] {
+        &self.retention
+    }
+
+    fn profile_names(&self, profile: Option<&str>) -> anyhow::Result<Option<&Vec<String>>> {
+        let Some(profile) = profile else {
+            return Ok(None);
+        };
+        self.profiles.get(profile).map(Some).ok_or_else(|| {
+            anyhow!(
+                "Unknown profile '{profile}', available profiles: {}",
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_config(content: &str) -> CleanConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_user_config_overrides_builtin_rules() {
+        let rules = JunkRules::new(user_config(
+            r#"
+            [rules.logs]
+            min_age = "14d"
+
+            [rules.system]
+            enabled = false
+
+            [rules.downloads]
+            names = ["*.part"]
+            min_size = "1M"
+
+            [profiles]
+            dev = ["logs", "downloads"]
+            "#,
+        ))
+        .unwrap();
+
+        let logs = rules.all().iter().find(|rule| rule.name == "logs").unwrap();
+        assert_eq!(logs.config.extensions, Some(vec!["log".to_string()]));
+        assert_eq!(logs.min_age, Some(Duration::from_secs(14 * 24 * 3600)));
+
+        let active = |profile| {
+            rules
+                .active(profile)
+                .unwrap()
+                .iter()
+                .map(|rule| rule.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert!(!active(None).contains(&"system"));
+        assert!(active(None).contains(&"downloads"));
+        assert_eq!(active(Some("dev")), vec!["downloads", "logs"]);
+        assert_eq!(active(Some("media")), vec!["backups", "temp"]);
+        assert!(rules.active(Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_name_globs_match_only_files() {
+        let rules = JunkRules::new(CleanConfig::default()).unwrap();
+        let is_junk = |path: &str, is_dir: bool| {
+            rules
+                .all()
+                .iter()
+                .any(|rule| rule.matches_path(Path::new(path), is_dir))
+        };
+
+        assert!(is_junk("notes.txt~", false));
+        assert!(is_junk("app.log.old", false));
+        assert!(!is_junk("backup~", true));
+        assert!(!is_junk("app.log.old", true));
+        assert!(is_junk("__pycache__", true));
+    }
+
+    #[test]
+    fn test_profile_selects_retention_policies() {
+        let rules = JunkRules::new(user_config(
+            r#"
+            [retention.rotations]
+            patterns = ["*.log.*"]
+            keep_newest = 3
+
+            [profiles]
+            media = ["system"]
+            servers = ["logs", "rotations"]
+            "#,
+        ))
+        .unwrap();
+
+        let names = |profile| {
+            rules
+                .active_retention(profile)
+                .unwrap()
+                .iter()
+                .map(|policy| policy.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(None), vec!["rotations"]);
+        assert_eq!(names(Some("servers")), vec!["rotations"]);
+        assert!(names(Some("media")).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_user_config() {
+        assert!(JunkRules::new(user_config("[profiles]\nweb = [\"missing\"]")).is_err());
+        assert!(JunkRules::new(user_config("[rules.old]\nmin_age = \"soon\"")).is_err());
+        assert!(toml::from_str::<CleanConfig>("[rules.old]\nmax_age = \"7d\"").is_err());
+    }
+
+    #[test]
+    fn test_entry_has_merged_fields() {
+        let mut config = user_config("[rules.logs]\nnames = [\"*.log\"]\nmin_age = \"7d\"");
+        config.merge(user_config(
+            "[rules.logs]\nmin_age = \"14d\"\n[profiles]\nweb = [\"logs\"]",
+        ));
+
+        assert_eq!(
+            config.entry("rules.logs").unwrap().to_string(),
+            "{ min_age = \"14d\", names = [\"*.log\"] }"
+        );
+        assert_eq!(
+            config.entry("profiles.web").unwrap().to_string(),
+            "[\"logs\"]"
+        );
+        assert!(config.entry("rules.missing").is_none());
+    }
+}
@@ -0,0 +1,196 @@
+//! Interactive review of found junk before anything is removed
+
+use anstream::println;
+use owo_colors::OwoColorize;
+
+use super::format_bytes;
+use crate::{commands::plan::Operation, confirmation::Choice};
+
+const GROUP_CHOICES: &[Choice] = &[
+    Choice::new('y', "yes"),
+    Choice::new('n', "no"),
+    Choice::new('e', "each item"),
+    Choice::new('a', "all remaining"),
+    Choice::new('q', "quit"),
+];
+
+const ITEM_CHOICES: &[Choice] = &[
+    Choice::new('y', "yes"),
+    Choice::new('n', "no"),
+    Choice::new('r', "rest of group"),
+    Choice::new('s', "skip rest of group"),
+];
+
+/// Junk entry found by scan with the rule or retention policy that selected it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub source: String,
+    pub operation: Operation,
+}
+
+/// Operations of candidates grouped by source, in the order sources were first found
+pub fn group_by_source(candidates: Vec<Candidate>) -> Vec<(String, Vec<Operation>)> {
+    let mut groups: Vec<(String, Vec<Operation>)> = Vec::new();
+    for candidate in candidates {
+        match groups
+            .iter_mut()
+            .find(|(source, _)| *source == candidate.source)
+        {
+            Some((_, operations)) => operations.push(candidate.operation),
+            None => groups.push((candidate.source, vec![candidate.operation])),
+        }
+    }
+    groups
+}
+
+/// Show candidates grouped by source and let user accept or reject every group or item.
+/// `ask` shows prompt with choices and returns key of the answer.
+pub fn review(
+    candidates: Vec<Candidate>,
+    mut ask: impl FnMut(&str, &[Choice]) -> anyhow::Result<char>,
+) -> anyhow::Result<Vec<Operation>> {
+    let mut accepted = Vec::new();
+    let mut accept_remaining = false;
+
+    for (source, operations) in group_by_source(candidates) {
+        let size: u64 = operations.iter().filter_map(Operation::size).sum();
+        println!();
+        println!(
+            "{} {} item(s), {}",
+            format!("{source}:").bold().bright_green(),
+            operations.len().bright_purple(),
+            format_bytes(size).bright_yellow()
+        );
+        for operation in &operations {
+            println!("  {}", describe(operation));
+        }
+
+        let answer = if accept_remaining {
+            'y'
+        } else {
+            ask("Remove this group?", GROUP_CHOICES)?
+        };
+        match answer {
+            'y' => accepted.extend(operations),
+            'e' => accepted.extend(review_items(operations, &mut ask)?),
+            'a' => {
+                accept_remaining = true;
+                accepted.extend(operations);
+            }
+            'q' => break,
+            _ => {}
+        }
+    }
+
+    Ok(accepted)
+}
+
+fn review_items(
+    operations: Vec<Operation>,
+    ask: &mut impl FnMut(&str, &[Choice]) -> anyhow::Result<char>,
+) -> anyhow::Result<Vec<Operation>> {
+    let mut accepted = Vec::new();
+    let mut rest = None;
+
+    for operation in operations {
+        let answer = match rest {
+            Some(answer) => answer,
+            None => ask(&format!("  {}?", describe(&operation)), ITEM_CHOICES)?,
+        };
+        match answer {
+            'y' => accepted.push(operation),
+            'r' => {
+                rest = Some('y');
+                accepted.push(operation);
+            }
+            's' => rest = Some('n'),
+            _ => {}
+        }
+    }
+
+    Ok(accepted)
+}
+
+fn describe(operation: &Operation) -> String {
+    format!(
+        "{} {:>10} {}",
+        operation.colored_name(),
+        operation
+            .size()
+            .map(format_bytes)
+            .unwrap_or_default()
+            .bright_yellow(),
+        operation.target().display().bright_cyan()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn candidate(source: &str, name: &str) -> Candidate {
+        Candidate {
+            source: source.to_string(),
+            operation: Operation::Delete {
+                path: PathBuf::from(name),
+                size: 1,
+                is_dir: false,
+            },
+        }
+    }
+
+    fn candidates() -> Vec<Candidate> {
+        vec![
+            candidate("Rule 'logs'", "a.log"),
+            candidate("Rule 'temp'", "b.tmp"),
+            candidate("Rule 'logs'", "c.log"),
+            candidate("Rule 'logs'", "d.log"),
+            candidate("Rule 'backups'", "e.bak"),
+        ]
+    }
+
+    /// Review with scripted answers, returns names of accepted files
+    fn review_with(answers: &str) -> Vec<String> {
+        let mut answers = answers.chars();
+        review(candidates(), |_, _| {
+            Ok(answers.next().expect("more answers"))
+        })
+        .unwrap()
+        .iter()
+        .map(|operation| operation.target().display().to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_group_by_source_keeps_first_found_order() {
+        let groups = group_by_source(candidates());
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(source, operations)| (source.as_str(), operations.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Rule 'logs'", 3),
+                ("Rule 'temp'", 1),
+                ("Rule 'backups'", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_review_groups() {
+        assert_eq!(review_with("yny"), vec!["a.log", "c.log", "d.log", "e.bak"]);
+        assert_eq!(review_with("na"), vec!["b.tmp", "e.bak"]);
+        assert!(review_with("q").is_empty());
+    }
+
+    #[test]
+    fn test_review_items() {
+        // Second log, then rest of group; skip temp items; no backups
+        assert_eq!(review_with("enrnn"), vec!["c.log", "d.log"]);
+        assert_eq!(review_with("eysyn"), vec!["a.log", "b.tmp"]);
+    }
+}
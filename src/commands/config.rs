@@ -0,0 +1,144 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anstream::println;
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{debug, trace};
+use owo_colors::OwoColorize;
+
+use crate::{
+    cli::Cli,
+    config::{Config, Origin},
+};
+
+#[derive(Parser, Debug)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+enum ConfigAction {
+    #[command(about = "Print merged configuration with the origin of every value")]
+    Show {
+        #[arg(
+            help = "Directory whose '.fo.toml' files and their parents' are applied",
+            default_value = "."
+        )]
+        directory: PathBuf,
+    },
+}
+
+impl ConfigCommand {
+    /// Directory config layers are loaded for
+    pub fn directory(&self) -> &Path {
+        match &self.action {
+            ConfigAction::Show { directory } => directory,
+        }
+    }
+
+    pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
+        debug!("Executing 'config' command");
+        trace!("with configuration: {self}");
+
+        match &self.action {
+            ConfigAction::Show { .. } => print_config(&context.config),
+        }
+        Ok(())
+    }
+}
+
+impl Display for ConfigCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {:?}", "action".bright_cyan(), self.action)?;
+        Ok(())
+    }
+}
+
+/// Print config in the same format as config file, with origin of every value as comment
+fn print_config(config: &Config) {
+    print_line(
+        format!("sort_by = {}", quoted(&value_name(&config.sort_by.value))),
+        &config.sort_by.origin,
+    );
+    print_line(
+        format!(
+            "size_buckets = {}",
+            quoted(&config.size_buckets.value.to_string())
+        ),
+        &config.size_buckets.origin,
+    );
+    print_line(
+        format!("hidden = {}", config.hidden.value),
+        &config.hidden.origin,
+    );
+    print_line(
+        format!(
+            "datetime_format = {}",
+            quoted(&config.datetime_format.value)
+        ),
+        &config.datetime_format.origin,
+    );
+    print_line(
+        format!(
+            "plan_format = {}",
+            quoted(&value_name(&config.plan_format.value))
+        ),
+        &config.plan_format.origin,
+    );
+    print_line(
+        format!("color = {}", quoted(&value_name(&config.color.value))),
+        &config.color.origin,
+    );
+
+    let mut profiles_header = false;
+    for (key, origin) in &config.clean_origins {
+        match config.clean.entry(key) {
+            Some(toml::Value::Table(fields)) => {
+                println!();
+                print_line(format!("[clean.{key}]"), origin);
+                for (field, value) in fields {
+                    println!("{field} = {value}");
+                }
+            }
+            // Profile is a list, so profiles share one table
+            Some(value) => {
+                if !profiles_header {
+                    println!("\n[clean.profiles]");
+                    profiles_header = true;
+                }
+                let name = key.split_once('.').map_or(key.as_str(), |(_, name)| name);
+                print_line(format!("{name} = {value}"), origin);
+            }
+            None => {}
+        }
+    }
+
+    println!();
+    if config.files.is_empty() {
+        println!("{}", "No config files found".bright_green());
+    } else {
+        println!("{}", "Config files, lowest priority first:".bright_green());
+        for path in &config.files {
+            println!("  {}", path.display());
+        }
+    }
+}
+
+/// TOML string literal, escaped where needed
+fn quoted(value: &str) -> toml::Value {
+    toml::Value::String(value.to_string())
+}
+
+fn print_line(line: String, origin: &Origin) {
+    println!("{line:<50} {}", format!("# {origin}").bright_purple());
+}
+
+fn value_name(value: &impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,414 @@
+use std::{
+    cmp::Reverse,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anstream::println;
+use anyhow::bail;
+use clap::{Parser, ValueEnum};
+use log::{debug, info, trace};
+use owo_colors::OwoColorize;
+
+use crate::{
+    cli::Cli,
+    commands::find::FilesList,
+    config::Config,
+    confirmation::{confirm, select},
+    journal::Journal,
+    size::parse_size,
+};
+
+use super::{
+    clean::format_bytes,
+    plan::{Operation, Plan},
+};
+use grouping::{DuplicateGroup, find_duplicates};
+
+mod grouping;
+
+/// Which file of a duplicate group is kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum KeepPolicy {
+    /// File with the oldest modification time
+    Oldest,
+    /// File with the newest modification time
+    Newest,
+    /// File inside one of '--prefer' directories, groups without such file are skipped
+    Preferred,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct DupesCommand {
+    #[arg(help = "Directory to search for duplicates", default_value = ".")]
+    directory: PathBuf,
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = parse_size,
+        help = "Ignore files smaller than size, e.g. '1K'. Empty files are ignored by default"
+    )]
+    min_size: u64,
+    #[arg(
+        long,
+        value_enum,
+        help = "Resolve every group automatically, keeping one file and removing the others"
+    )]
+    keep: Option<KeepPolicy>,
+    #[arg(
+        long,
+        help = "Directory whose files are kept by '--keep preferred'. Can be repeated, earlier directories win"
+    )]
+    prefer: Vec<PathBuf>,
+    #[arg(
+        long,
+        help = "Replace removed duplicates with hardlinks to the kept file instead of deleting them"
+    )]
+    hardlink: bool,
+    #[arg(
+        short = 'i',
+        long,
+        conflicts_with = "keep",
+        help = "Choose the file to keep for every group"
+    )]
+    interactive: bool,
+    #[arg(
+        short = 'a',
+        long = "all",
+        overrides_with = "no_hidden",
+        help = "Include hidden files (dotfiles) in the search"
+    )]
+    search_hidden: bool,
+    #[arg(
+        long = "no-all",
+        overrides_with = "search_hidden",
+        help = "Leave out hidden files even when config includes them"
+    )]
+    no_hidden: bool,
+    #[arg(
+        short = 'r',
+        long = "recursive",
+        help = "Search directories recursively"
+    )]
+    search_recursive: bool,
+}
+
+impl DupesCommand {
+    pub fn configure(&mut self, config: &Config) {
+        self.search_hidden = config.hidden.value;
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
+        debug!("Executing 'dupes' command");
+        trace!("with configuration: {self}");
+
+        if self.keep == Some(KeepPolicy::Preferred) && self.prefer.is_empty() {
+            bail!("'--keep preferred' requires at least one '--prefer' directory");
+        }
+        let prefer = self
+            .prefer
+            .iter()
+            .map(|dir| {
+                fs::canonicalize(dir).map_err(|err| {
+                    anyhow::anyhow!("Invalid '--prefer' directory {}: {err}", dir.display())
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut files = Vec::new();
+        for entry in FilesList::new(&self.directory, self.search_recursive, self.search_hidden)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            // Symlinks and directories are never duplicates
+            if metadata.is_file() && metadata.len() >= self.min_size {
+                files.push((entry.path(), metadata.len()));
+            }
+        }
+
+        debug!("Searching duplicates among {} files", files.len());
+        let groups = find_duplicates(files);
+        print_groups(context, &groups)?;
+        if groups.is_empty() {
+            return Ok(());
+        }
+
+        let mut plan = Plan::new();
+        if let Some(policy) = self.keep {
+            if !confirm(&format!(
+                "This will {}, keeping the {} file of every group. Are you sure you want to proceed?",
+                self.action_name(),
+                format!("{policy:?}").to_lowercase()
+            ))? {
+                debug!("User declined to remove duplicates. Aborting command execution.");
+                return Ok(());
+            }
+
+            for group in &groups {
+                match choose_keeper(&group.files, policy, &prefer)? {
+                    Some(keeper) => self.plan_group(group, keeper, &mut plan),
+                    None => info!(
+                        "No preferred file in group, skipping: {}",
+                        group.files[0].display()
+                    ),
+                }
+            }
+        } else if self.interactive {
+            for (index, group) in groups.iter().enumerate() {
+                println!();
+                print_group(context, index, group)?;
+                if let Some(keeper) = select("Which file to keep?", group.files.len())? {
+                    self.plan_group(group, keeper, &mut plan);
+                }
+            }
+        } else {
+            return Ok(());
+        }
+
+        let summary = plan.execute(&mut Journal::open()?.start_run()?)?;
+        println!(
+            "\n{}\n  {} file(s) {}\n  {} freed",
+            "Dupes summary:".bold(),
+            (summary.deleted_files + summary.linked_files).bright_green(),
+            if self.hardlink {
+                "replaced with hardlinks"
+            } else {
+                "deleted"
+            },
+            format_bytes(summary.freed_bytes).bright_green(),
+        );
+
+        Ok(())
+    }
+
+    fn action_name(&self) -> &'static str {
+        if self.hardlink {
+            "replace duplicates with hardlinks"
+        } else {
+            "permanently delete duplicates"
+        }
+    }
+
+    /// Plan removal of every file of group except `keeper`
+    fn plan_group(&self, group: &DuplicateGroup, keeper: usize, plan: &mut Plan) {
+        let kept = &group.files[keeper];
+        for (index, path) in group.files.iter().enumerate() {
+            if index == keeper {
+                continue;
+            }
+
+            plan.push(if self.hardlink {
+                Operation::Hardlink {
+                    original: kept.clone(),
+                    path: path.clone(),
+                    size: group.size,
+                }
+            } else {
+                Operation::Delete {
+                    path: path.clone(),
+                    size: group.size,
+                    is_dir: false,
+                }
+            });
+        }
+    }
+}
+
+/// Index of file to keep, `None` when policy can't choose one
+fn choose_keeper(
+    files: &[PathBuf],
+    policy: KeepPolicy,
+    prefer: &[PathBuf],
+) -> anyhow::Result<Option<usize>> {
+    match policy {
+        KeepPolicy::Oldest | KeepPolicy::Newest => {
+            let modified = files
+                .iter()
+                .map(|path| Ok(fs::metadata(path)?.modified()?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            // On equal times the first file in path order wins
+            let keeper = if policy == KeepPolicy::Oldest {
+                modified
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(index, time)| (*time, *index))
+            } else {
+                modified
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(index, time)| (*time, Reverse(*index)))
+            };
+            Ok(keeper.map(|(index, _)| index))
+        }
+        KeepPolicy::Preferred => {
+            let canonical = files
+                .iter()
+                .map(fs::canonicalize)
+                .collect::<std::io::Result<Vec<_>>>()?;
+            Ok(prefer
+                .iter()
+                .find_map(|dir| canonical.iter().position(|path| path.starts_with(dir))))
+        }
+    }
+}
+
+fn print_groups(context: &Cli, groups: &[DuplicateGroup]) -> anyhow::Result<()> {
+    for (index, group) in groups.iter().enumerate() {
+        print_group(context, index, group)?;
+    }
+
+    let wasted: u64 = groups.iter().map(DuplicateGroup::wasted_bytes).sum();
+    println!();
+    println!(
+        "{} {}",
+        "Duplicate groups:".bright_green(),
+        groups.len().to_string().bright_purple()
+    );
+    println!(
+        "{} {}",
+        "Wasted space:".bright_green(),
+        format_bytes(wasted).bright_purple()
+    );
+    Ok(())
+}
+
+fn print_group(context: &Cli, index: usize, group: &DuplicateGroup) -> anyhow::Result<()> {
+    println!(
+        "{} {} file(s) of {}, {} wasted",
+        format!("Group {}:", index + 1).bold().bright_green(),
+        group.files.len(),
+        format_bytes(group.size).bright_yellow(),
+        format_bytes(group.wasted_bytes()).bright_red()
+    );
+    for (number, path) in group.files.iter().enumerate() {
+        let modified = context
+            .local_time(fs::metadata(path)?.modified()?)
+            .format(&context.datetime_format)?;
+        println!(
+            "  {:>3}. {} {}",
+            number + 1,
+            path.display().bright_cyan(),
+            modified.bright_purple()
+        );
+    }
+    Ok(())
+}
+
+impl Display for DupesCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: {}",
+            "directory".bright_cyan(),
+            self.directory.display()
+        )?;
+        writeln!(f, "{}: {}", "min_size".bright_cyan(), self.min_size)?;
+        writeln!(f, "{}: {:?}", "keep".bright_cyan(), self.keep)?;
+        writeln!(f, "{}: {:?}", "prefer".bright_cyan(), self.prefer)?;
+        writeln!(f, "{}: {}", "hardlink".bright_cyan(), self.hardlink)?;
+        writeln!(f, "{}: {}", "interactive".bright_cyan(), self.interactive)?;
+        writeln!(
+            f,
+            "{}: {}",
+            "search_hidden".bright_cyan(),
+            self.search_hidden
+        )?;
+        writeln!(
+            f,
+            "{}: {}",
+            "search_recursive".bright_cyan(),
+            self.search_recursive
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use test_case::test_case;
+
+    fn create_group(name: &str) -> (PathBuf, Vec<PathBuf>) {
+        let temp_dir = std::env::temp_dir().join(name);
+        let preferred_dir = temp_dir.join("preferred");
+        fs::create_dir_all(&preferred_dir).unwrap();
+
+        let files = vec![
+            temp_dir.join("a.txt"),
+            temp_dir.join("b.txt"),
+            preferred_dir.join("c.txt"),
+        ];
+        let now = SystemTime::now();
+        for (index, path) in files.iter().enumerate() {
+            fs::write(path, "content").unwrap();
+            // a.txt is the newest, c.txt is the oldest
+            let file = fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(now - Duration::from_secs(3600 * index as u64))
+                .unwrap();
+        }
+        (temp_dir, files)
+    }
+
+    #[test_case(KeepPolicy::Oldest, Some(2) ; "oldest")]
+    #[test_case(KeepPolicy::Newest, Some(0) ; "newest")]
+    #[test_case(KeepPolicy::Preferred, Some(2) ; "preferred")]
+    fn test_choose_keeper(policy: KeepPolicy, expected: Option<usize>) {
+        let (temp_dir, files) = create_group(&format!("fo_test_choose_keeper_{policy:?}"));
+        let prefer = vec![fs::canonicalize(temp_dir.join("preferred")).unwrap()];
+
+        let keeper = choose_keeper(&files, policy, &prefer).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(keeper, expected);
+    }
+
+    #[test]
+    fn test_choose_keeper_without_preferred_file() {
+        let (temp_dir, files) = create_group("fo_test_choose_keeper_no_preferred");
+        let prefer = vec![
+            fs::canonicalize(std::env::temp_dir())
+                .unwrap()
+                .join("elsewhere"),
+        ];
+
+        let keeper = choose_keeper(&files, KeepPolicy::Preferred, &prefer).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(keeper, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_with_hardlinks() {
+        use std::os::unix::fs::MetadataExt;
+
+        let (temp_dir, files) = create_group("fo_test_resolve_with_hardlinks");
+        let command = DupesCommand {
+            hardlink: true,
+            ..Default::default()
+        };
+        let group = DuplicateGroup { size: 7, files };
+
+        let mut plan = Plan::new();
+        command.plan_group(&group, 0, &mut plan);
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+        let summary = plan.execute(&mut journal.start_run().unwrap()).unwrap();
+        let inodes = group
+            .files
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().ino())
+            .collect::<Vec<_>>();
+        let content = fs::read_to_string(&group.files[2]).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(summary.linked_files, 2);
+        assert_eq!(summary.freed_bytes, 14);
+        assert!(inodes.iter().all(|inode| *inode == inodes[0]));
+        assert_eq!(content, "content");
+    }
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, fs::File, hash::Hash, io::Read, path::PathBuf};
+
+use log::{trace, warn};
+
+/// How many bytes from the start of file are hashed before hashing whole content
+const PARTIAL_HASH_BYTES: u64 = 4 * 1024;
+
+/// Files with identical content
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    /// Size of every file in the group
+    pub size: u64,
+    pub files: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Space that would be freed by keeping only one file
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Group files by size, then by hash of the first bytes, then by hash of whole content.
+/// Every step only hashes files that still have a possible duplicate.
+pub fn find_duplicates(files: Vec<(PathBuf, u64)>) -> Vec<DuplicateGroup> {
+    let files = skip_hardlinks(files);
+    trace!("Grouping {} files by size", files.len());
+
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in files {
+        by_size.entry(size).or_default().push(path);
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size {
+        if paths.len() < 2 {
+            continue;
+        }
+
+        for candidates in split_by(paths, |path| hash_file(path, Some(PARTIAL_HASH_BYTES))) {
+            // Partial hash already covers whole content of small files
+            let duplicates = if size <= PARTIAL_HASH_BYTES {
+                vec![candidates]
+            } else {
+                split_by(candidates, |path| hash_file(path, None))
+            };
+
+            for mut files in duplicates {
+                files.sort();
+                groups.push(DuplicateGroup { size, files });
+            }
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.files.cmp(&b.files))
+    });
+    groups
+}
+
+/// Split paths by key, dropping unreadable files and keys with a single path
+fn split_by<K: Hash + Eq>(
+    paths: Vec<PathBuf>,
+    key: impl Fn(&PathBuf) -> std::io::Result<K>,
+) -> Vec<Vec<PathBuf>> {
+    let mut buckets: HashMap<K, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        match key(&path) {
+            Ok(key) => buckets.entry(key).or_default().push(path),
+            Err(err) => warn!("Failed to read {}: {}", path.display(), err),
+        }
+    }
+
+    buckets
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect()
+}
+
+/// Hash first `limit` bytes of file, or whole file when there is no limit
+fn hash_file(path: &PathBuf, limit: Option<u64>) -> std::io::Result<blake3::Hash> {
+    let file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    match limit {
+        Some(limit) => hasher.update_reader(file.take(limit))?,
+        None => hasher.update_reader(file)?,
+    };
+    Ok(hasher.finalize())
+}
+
+/// Hardlinks share the same data, so only the first link of every inode is kept
+#[cfg(unix)]
+fn skip_hardlinks(files: Vec<(PathBuf, u64)>) -> Vec<(PathBuf, u64)> {
+    use std::{collections::HashSet, os::unix::fs::MetadataExt};
+
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .filter(|(path, _)| match std::fs::metadata(path) {
+            Ok(metadata) => seen.insert((metadata.dev(), metadata.ino())),
+            Err(_) => true,
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn skip_hardlinks(files: Vec<(PathBuf, u64)>) -> Vec<(PathBuf, u64)> {
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(dir: &PathBuf, files: &[(&str, &[u8])]) -> Vec<(PathBuf, u64)> {
+        std::fs::create_dir_all(dir).unwrap();
+        files
+            .iter()
+            .map(|(name, content)| {
+                let path = dir.join(name);
+                std::fs::write(&path, content).unwrap();
+                (path, content.len() as u64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let temp_dir = std::env::temp_dir().join("fo_test_find_duplicates");
+        let large_a = vec![b'a'; 10_000];
+        let mut large_b = large_a.clone();
+        // Same size and same first bytes, differs only after the partial hash
+        large_b[9_999] = b'b';
+
+        let files = write_files(
+            &temp_dir,
+            &[
+                ("one.txt", b"same content"),
+                ("two.txt", b"same content"),
+                ("other.txt", b"diff content"),
+                ("large_1.bin", &large_a),
+                ("large_2.bin", &large_a),
+                ("large_3.bin", &large_b),
+            ],
+        );
+
+        let groups = find_duplicates(files);
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            groups,
+            vec![
+                DuplicateGroup {
+                    size: 10_000,
+                    files: vec![temp_dir.join("large_1.bin"), temp_dir.join("large_2.bin")],
+                },
+                DuplicateGroup {
+                    size: 12,
+                    files: vec![temp_dir.join("one.txt"), temp_dir.join("two.txt")],
+                },
+            ]
+        );
+        assert_eq!(groups[0].wasted_bytes(), 10_000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_duplicates_skips_hardlinks() {
+        let temp_dir = std::env::temp_dir().join("fo_test_find_duplicates_hardlinks");
+        let mut files = write_files(&temp_dir, &[("original.txt", b"content")]);
+        let link = temp_dir.join("link.txt");
+        std::fs::hard_link(&files[0].0, &link).unwrap();
+        files.push((link, 7));
+
+        let groups = find_duplicates(files);
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(groups.is_empty());
+    }
+}
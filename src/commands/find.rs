@@ -1,20 +1,24 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
-use log::{debug, trace};
+use clap::{ArgGroup, Parser};
+use log::{debug, trace, warn};
 use owo_colors::OwoColorize;
 
-use crate::cli::Cli;
+use crate::{cli::Cli, config::Config, size::parse_size};
 
 mod file_list;
 mod filtering;
 mod printer;
 
-pub(super) use file_list::FilesList;
+pub(super) use file_list::{Exclusions, FilesList};
 pub(super) use filtering::*;
 pub(super) use printer::*;
 
 #[derive(Parser, Debug, Default)]
+#[command(group(ArgGroup::new("filter").multiple(true)))]
 pub struct FindCommand {
     #[arg(help = "Directory to search", default_value = ".", requires = "filter")]
     directory: PathBuf,
@@ -23,38 +27,167 @@ pub struct FindCommand {
         long,
         name = "name",
         group = "filter",
-        help = "Filter by file name"
+        help = "Filter by file name. Glob patterns (*, ?, [a-z], {a,b}) are used when name contains them. Can be repeated"
     )]
-    file_name: Option<String>,
-    #[arg(short, long, group = "filter", help = "Filter by file size in bytes")]
-    size: Option<String>,
-    #[arg(short, long, group = "filter", help = "Filter by file extension")]
-    ext: Option<String>,
+    file_name: Vec<String>,
     #[arg(
         short,
         long,
         group = "filter",
-        help = "Filter by file creation datetime in format 'YYYY-MM-DD HH:MM'"
+        help = "Filter by file path relative to the searched directory, e.g. 'config/.env' or '**/config/*.env'. Can be repeated"
     )]
-    datetime: Option<String>,
+    path: Vec<String>,
     #[arg(
         short,
         long,
         group = "filter",
-        help = "Filter by file modification datetime in format 'YYYY-MM-DD HH:MM'"
+        help = "Filter by file content containing text, or matching regex with --regex. Binary files are skipped. Can be repeated"
     )]
-    modified: Option<String>,
+    contains: Vec<String>,
+    #[arg(
+        short,
+        long,
+        group = "filter",
+        allow_hyphen_values = true,
+        help = "Filter by file size: '1M' (exact), '+10M' (larger), '-1K' (smaller) or '1K..5M' (range). K/M/G/T and KiB/MiB/.. are binary units, KB/MB/.. are decimal. Can be repeated"
+    )]
+    size: Vec<String>,
+    #[arg(
+        short,
+        long,
+        group = "filter",
+        help = "Filter by file extension. Can be repeated"
+    )]
+    ext: Vec<String>,
+    #[arg(
+        short = 't',
+        long = "type",
+        group = "filter",
+        help = "Filter by MIME type detected from file content, e.g. 'image/*' or 'application/pdf'. Can be repeated"
+    )]
+    mime_type: Vec<String>,
+    #[arg(
+        short,
+        long,
+        visible_alias = "created",
+        group = "filter",
+        allow_hyphen_values = true,
+        help = "Filter by file creation datetime: '2025-06', '2025-06-14 10:30', 'yesterday', '-7d' (within), '+7d' (older than) or 'A..B' (range). Can be repeated"
+    )]
+    datetime: Vec<String>,
+    #[arg(
+        short,
+        long,
+        group = "filter",
+        allow_hyphen_values = true,
+        help = "Filter by file modification datetime, same formats as --datetime. Can be repeated"
+    )]
+    modified: Vec<String>,
+    #[arg(
+        long,
+        group = "filter",
+        allow_hyphen_values = true,
+        help = "Filter by file access datetime (atime), same formats as --datetime. Can be repeated"
+    )]
+    accessed: Vec<String>,
+    #[arg(
+        long,
+        group = "filter",
+        allow_hyphen_values = true,
+        help = "Filter by inode change datetime (ctime, Unix only), same formats as --datetime. Can be repeated"
+    )]
+    changed: Vec<String>,
+    #[arg(
+        long,
+        value_name = "DATE",
+        group = "filter",
+        help = "Filter by date photos and videos were taken, from EXIF or MP4/MOV metadata, on or after DATE. Other files use creation datetime. Same formats as --datetime"
+    )]
+    taken_after: Option<String>,
+    #[arg(
+        long,
+        value_name = "DATE",
+        group = "filter",
+        help = "Filter by date photos and videos were taken on or before DATE, see --taken-after"
+    )]
+    taken_before: Option<String>,
+    #[arg(
+        short = 'w',
+        long = "where",
+        group = "filter",
+        help = "Filter by expression, e.g. 'ext = \"rs\" and size > 1M'. Can be repeated"
+    )]
+    where_query: Vec<String>,
     #[arg(
         long = "regex",
         help = "Combine this with other filters to use regex for filtering"
     )]
     is_regex: bool,
+    #[arg(
+        short = 'i',
+        long = "ignore-case",
+        help = "Match names, extensions and patterns case-insensitively"
+    )]
+    ignore_case: bool,
+    #[arg(
+        long = "case-sensitive",
+        conflicts_with = "ignore_case",
+        help = "Match case-sensitively. By default matching ignores case unless the pattern contains uppercase letters"
+    )]
+    case_sensitive: bool,
+    #[arg(
+        long = "or",
+        help = "Match files that satisfy any of the filters instead of all of them"
+    )]
+    is_or: bool,
+    #[arg(
+        long = "not",
+        help = "Invert the result of all filters combined, not of a single one. To negate one filter use --where, e.g. --where 'not ext = \"rs\"'"
+    )]
+    is_not: bool,
+    #[arg(
+        long,
+        value_parser = parse_size,
+        default_value = "10M",
+        help = "Maximum bytes read from every file by --contains, e.g. '64K' or '1M'"
+    )]
+    max_scan_bytes: Option<u64>,
+    #[arg(
+        long,
+        requires = "contains",
+        help = "Print numbers and text of lines matched by --contains"
+    )]
+    line_numbers: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = DEFAULT_COLUMNS.to_vec(),
+        help = "Comma separated columns to print for matched files"
+    )]
+    columns: Vec<Column>,
+    #[arg(
+        long,
+        help = "Skip files and directories matching glob, e.g. 'node_modules' or 'build/**'. Patterns without '/' match the name. Can be repeated"
+    )]
+    exclude: Vec<String>,
+    #[arg(
+        long,
+        help = "Skip directories matching glob without reading them, same patterns as --exclude. Can be repeated"
+    )]
+    exclude_dir: Vec<String>,
     #[arg(
         short = 'a',
         long = "all",
+        overrides_with = "no_hidden",
         help = "Include hidden files (dotfiles) in the search"
     )]
     search_hidden: bool,
+    #[arg(
+        long = "no-all",
+        overrides_with = "search_hidden",
+        help = "Leave out hidden files even when config includes them"
+    )]
+    no_hidden: bool,
     #[arg(
         short = 'r',
         long = "recursive",
@@ -64,10 +197,19 @@ pub struct FindCommand {
 }
 
 impl FindCommand {
+    pub fn configure(&mut self, config: &Config) {
+        self.search_hidden = config.hidden.value;
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
     pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
         debug!("Executing 'find' command");
         trace!("with configuration: {self}");
-        let files = FilesList::new(&self.directory, self.search_recursive, self.search_hidden)?;
+        let files = FilesList::new(&self.directory, self.search_recursive, self.search_hidden)?
+            .with_exclusions(self.exclusions()?);
 
         debug!("Filtering files based on provided criteria");
         let file_matcher = create_matcher_from_config(self, context)?;
@@ -83,52 +225,115 @@ impl FindCommand {
             }
         }
 
-        print_files(context, &matched_files, total_files, total_matched_files)
+        let matched_lines = if self.line_numbers {
+            let patterns = create_content_patterns(self)?;
+            matched_files
+                .iter()
+                .map(|file| {
+                    let lines = FileContent::open(&file.path(), self.max_scan_bytes()).and_then(
+                        |content| match content {
+                            FileContent::Text(reader) => matching_lines(reader, &patterns),
+                            FileContent::Skipped => Ok(Vec::new()),
+                        },
+                    );
+                    // Same as content filter, one unreadable file doesn't stop the search
+                    lines.unwrap_or_else(|err| {
+                        warn!("Failed to read {}: {}", file.path().display(), err);
+                        Vec::new()
+                    })
+                })
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        print_files(
+            context,
+            &matched_files,
+            &matched_lines,
+            &self.columns,
+            total_files,
+            total_matched_files,
+        )
+    }
+
+    fn exclusions(&self) -> anyhow::Result<Exclusions> {
+        let case = self.case_sensitivity();
+        let to_globs = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| PathGlob::new(pattern, case.ignores_case(pattern)))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+
+        Ok(Exclusions {
+            entries: to_globs(&self.exclude)?,
+            dirs: to_globs(&self.exclude_dir)?,
+        })
+    }
+
+    /// Commands built without clap, e.g. in tests, have no default of --max-scan-bytes
+    fn max_scan_bytes(&self) -> u64 {
+        self.max_scan_bytes.unwrap_or(DEFAULT_MAX_SCAN_BYTES)
+    }
+
+    fn case_sensitivity(&self) -> CaseSensitivity {
+        if self.ignore_case {
+            CaseSensitivity::Insensitive
+        } else if self.case_sensitive {
+            CaseSensitivity::Sensitive
+        } else {
+            CaseSensitivity::Smart
+        }
     }
 }
 
 impl Display for FindCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "{}: {}",
-            "filename".bright_cyan(),
-            self.file_name.as_ref().unwrap_or(&String::from("None"))
-        )?;
+        writeln!(f, "{}: {:?}", "filename".bright_cyan(), self.file_name)?;
         writeln!(
             f,
             "{}: {}",
             "directory".bright_cyan(),
             self.directory.to_string_lossy()
         )?;
+        writeln!(f, "{}: {:?}", "path".bright_cyan(), self.path)?;
+        writeln!(f, "{}: {:?}", "contains".bright_cyan(), self.contains)?;
         writeln!(
             f,
-            "{}: {}",
-            "size".bright_cyan(),
-            self.size
-                .as_ref()
-                .map(|s| s.to_string())
-                .unwrap_or(String::from("None"))
+            "{}: {:?}",
+            "max_scan_bytes".bright_cyan(),
+            self.max_scan_bytes
         )?;
+        writeln!(f, "{}: {}", "line_numbers".bright_cyan(), self.line_numbers)?;
+        writeln!(f, "{}: {:?}", "size".bright_cyan(), self.size)?;
+        writeln!(f, "{}: {:?}", "ext".bright_cyan(), self.ext)?;
+        writeln!(f, "{}: {:?}", "type".bright_cyan(), self.mime_type)?;
+        writeln!(f, "{}: {:?}", "datetime".bright_cyan(), self.datetime)?;
+        writeln!(f, "{}: {:?}", "modified".bright_cyan(), self.modified)?;
+        writeln!(f, "{}: {:?}", "accessed".bright_cyan(), self.accessed)?;
+        writeln!(f, "{}: {:?}", "changed".bright_cyan(), self.changed)?;
+        writeln!(f, "{}: {:?}", "taken_after".bright_cyan(), self.taken_after)?;
         writeln!(
             f,
-            "{}: {}",
-            "ext".bright_cyan(),
-            self.ext.as_ref().unwrap_or(&String::from("None"))
-        )?;
-        writeln!(
-            f,
-            "{}: {}",
-            "datetime".bright_cyan(),
-            self.datetime.as_ref().unwrap_or(&String::from("None"))
+            "{}: {:?}",
+            "taken_before".bright_cyan(),
+            self.taken_before
         )?;
+        writeln!(f, "{}: {:?}", "where".bright_cyan(), self.where_query)?;
+        writeln!(f, "{}: {}", "regex".bright_cyan(), self.is_regex)?;
+        writeln!(f, "{}: {}", "ignore_case".bright_cyan(), self.ignore_case)?;
         writeln!(
             f,
             "{}: {}",
-            "modified".bright_cyan(),
-            self.modified.as_ref().unwrap_or(&String::from("None"))
+            "case_sensitive".bright_cyan(),
+            self.case_sensitive
         )?;
-        writeln!(f, "{}: {}", "regex".bright_cyan(), self.is_regex)?;
+        writeln!(f, "{}: {}", "or".bright_cyan(), self.is_or)?;
+        writeln!(f, "{}: {}", "not".bright_cyan(), self.is_not)?;
+        writeln!(f, "{}: {:?}", "columns".bright_cyan(), self.columns)?;
+        writeln!(f, "{}: {:?}", "exclude".bright_cyan(), self.exclude)?;
+        writeln!(f, "{}: {:?}", "exclude_dir".bright_cyan(), self.exclude_dir)?;
         writeln!(
             f,
             "{}: {}",
@@ -1,18 +1,57 @@
 use std::{
     fs::{DirEntry, ReadDir, read_dir},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 
-use log::debug;
+use log::{debug, trace};
+
+use crate::commands::find::filtering::PathGlob;
 
 /// Iterator over files in directory with extra specific iterating rules
 pub struct FilesList {
     is_recursive: bool,
     search_hidden: bool,
+    root: PathBuf,
+    exclusions: Exclusions,
     // Represent directory stack from start directory, that dynamically add dirs by DFS algorithm
     dir_stack: Vec<ReadDir>,
 }
 
+/// Patterns of entries skipped while iterating. Excluded directories are never read.
+#[derive(Default)]
+pub struct Exclusions {
+    /// Skip any matched file or directory
+    pub entries: Vec<PathGlob>,
+    /// Skip only matched directories
+    pub dirs: Vec<PathGlob>,
+}
+
+impl Exclusions {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.dirs.is_empty()
+    }
+
+    fn is_excluded(&self, relative_path: &str, is_dir: bool) -> bool {
+        self.entries
+            .iter()
+            .any(|glob| glob.is_matched(relative_path))
+            || (is_dir && self.dirs.iter().any(|glob| glob.is_matched(relative_path)))
+    }
+}
+
+/// Path relative to root joined with `/`, e.g. `config/app/.env`
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 impl FilesList {
     pub fn new(
         start_directory: &PathBuf,
@@ -26,9 +65,17 @@ impl FilesList {
         Ok(FilesList {
             is_recursive,
             search_hidden,
+            root: start_directory.clone(),
+            exclusions: Exclusions::default(),
             dir_stack: Vec::from([root_dir]),
         })
     }
+
+    /// Skip entries matched by exclusion patterns
+    pub fn with_exclusions(mut self, exclusions: Exclusions) -> Self {
+        self.exclusions = exclusions;
+        self
+    }
 }
 
 impl Iterator for FilesList {
@@ -56,6 +103,17 @@ impl Iterator for FilesList {
                             continue;
                         }
 
+                        // Skip excluded entries before descending, so pruned directories are never read
+                        if !self.exclusions.is_empty()
+                            && self.exclusions.is_excluded(
+                                &relative_path(&self.root, &file.path()),
+                                metadata.is_dir(),
+                            )
+                        {
+                            trace!("Excluded: {}", file.path().to_string_lossy());
+                            continue;
+                        }
+
                         // If recursive flag enabled, add new directory to stack to follow DFS algorithm
                         if self.is_recursive && metadata.is_dir() {
                             let dir = match read_dir(file.path()) {
@@ -83,7 +141,14 @@ impl Iterator for FilesList {
 mod tests {
     use std::collections::HashSet;
 
-    use crate::{commands::find::file_list::FilesList, volumes::get_path_under_tests};
+    use crate::{
+        commands::find::{
+            file_list::{Exclusions, FilesList, relative_path},
+            filtering::PathGlob,
+        },
+        volumes::get_path_under_tests,
+    };
+    use std::path::Path;
 
     #[test]
     fn test_iterate_file_list_without_recursion() {
@@ -174,4 +239,37 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_iterate_file_list_with_exclusions() {
+        let exclusions = Exclusions {
+            entries: vec![PathGlob::new("a", false).unwrap()],
+            dirs: vec![PathGlob::new("e", false).unwrap()],
+        };
+        let file_list = FilesList::new(&get_path_under_tests("file_list"), true, false)
+            .unwrap()
+            .with_exclusions(exclusions);
+
+        let mut result = HashSet::new();
+        for file in file_list {
+            result.insert(file.unwrap().file_name().to_string_lossy().into_owned());
+        }
+
+        // "a" and "e" are pruned together with their children
+        let expected = HashSet::from(["d".to_string(), "h".to_string(), "i".to_string()]);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("."), Path::new("./config/app/.env")),
+            "config/app/.env"
+        );
+        assert_eq!(
+            relative_path(Path::new("/tmp/root"), Path::new("/tmp/root/a")),
+            "a"
+        );
+    }
 }
@@ -1,12 +1,26 @@
+mod combinators;
+mod content;
+mod dates;
 mod extractors;
+mod filter_set;
 mod filters;
+mod query;
 
-use crate::{cli::Cli, commands::find::FindCommand};
+use crate::{cli::Cli, commands::find::FindCommand, size::parse_size, timestamps::FileTimestamp};
 use anyhow::bail;
+pub use combinators::*;
+pub use content::{
+    ContentPattern, DEFAULT_MAX_SCAN_BYTES, FileContent, MatchedLine, matching_lines,
+};
+use dates::parse_date_filter;
+pub use dates::parse_duration;
 pub use extractors::*;
+pub use filter_set::FilterSet;
 use filters::*;
+pub use filters::{CaseSensitivity, PathGlob};
 use log::trace;
-use std::fs::DirEntry;
+use query::compile_query;
+use std::{fs::DirEntry, ops::Bound, path::Path, time::SystemTime};
 use time::PrimitiveDateTime;
 
 /// Trait for matching files against filters
@@ -41,6 +55,11 @@ where
     FilterValue: 'static,
     E: Extractor<FilterValue>,
 {
+    /// Create a FileMatcher with any filter
+    pub fn new(filter: Box<dyn Filter<FilterValue>>, extractor: E) -> Self {
+        Self { filter, extractor }
+    }
+
     /// Create a FileMatcher with an exact match filter
     pub fn with_exact_match(compare_value: FilterValue, extractor: E) -> Self
     where
@@ -57,72 +76,305 @@ impl<E> FileMatcher<String, E>
 where
     E: Extractor<String>,
 {
+    /// Create a FileMatcher with an exact match filter that can ignore case (only for String extractors)
+    pub fn with_exact_text(compare_value: &str, ignore_case: bool, extractor: E) -> Self {
+        let filter: Box<dyn Filter<String>> = if ignore_case {
+            Box::new(LowercaseFilter::new(Box::new(ExactMatchFilter::new(
+                compare_value.to_lowercase(),
+            ))))
+        } else {
+            Box::new(ExactMatchFilter::new(compare_value.to_string()))
+        };
+        Self { filter, extractor }
+    }
+
     /// Create a FileMatcher with a regex filter (only for String extractors)
-    pub fn with_regex(regex_pattern: &str, extractor: E) -> anyhow::Result<Self> {
+    pub fn with_regex(
+        regex_pattern: &str,
+        ignore_case: bool,
+        extractor: E,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            filter: Box::new(RegexFilter::new(regex_pattern, ignore_case)?),
+            extractor,
+        })
+    }
+
+    /// Create a FileMatcher with a glob filter (only for String extractors)
+    pub fn with_glob(glob_pattern: &str, ignore_case: bool, extractor: E) -> anyhow::Result<Self> {
         Ok(Self {
-            filter: Box::new(RegexFilter::new(regex_pattern)?),
+            filter: Box::new(GlobFilter::new(glob_pattern, ignore_case)?),
             extractor,
         })
     }
 }
 
+/// Build a matcher from every filter provided in config.
+/// Filters are combined with AND, or with OR when `--or` is set, and `--not` inverts the combined
+/// result, so `--file-name "*.rs" --size +1M --not` matches files that are not both.
 pub fn create_matcher_from_config(
     config: &FindCommand,
     context: &Cli,
 ) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
     trace!("Creating file matcher from config");
-    if let Some(file_name) = &config.file_name {
-        if !config.is_regex {
-            let matcher = FileMatcher::with_exact_match(file_name.clone(), FileNameExtractor);
-            Ok(Box::new(matcher))
-        } else {
-            let matcher = FileMatcher::with_regex(file_name, FileNameExtractor)?;
-            Ok(Box::new(matcher))
-        }
-    } else if let Some(size) = &config.size {
-        if !config.is_regex {
-            let number = size.parse::<u64>().map_err(|_| {
-                anyhow::anyhow!("Size must be a valid number if regex is not enabled")
-            })?;
-            let matcher = FileMatcher::with_exact_match(number, FileSizeExtractor);
-            Ok(Box::new(matcher))
-        } else {
-            let matcher = FileMatcher::with_regex(&size.to_string(), FileSizeRegexExtractor)?;
-            Ok(Box::new(matcher))
-        }
-    } else if let Some(ext) = &config.ext {
-        if !config.is_regex {
-            let matcher = FileMatcher::with_exact_match(ext.clone(), FileExtensionExtractor);
-            Ok(Box::new(matcher))
-        } else {
-            let matcher = FileMatcher::with_regex(ext, FileExtensionExtractor)?;
-            Ok(Box::new(matcher))
-        }
-    } else if let Some(datetime) = &config.datetime {
-        if !config.is_regex {
-            let matcher = FileMatcher::with_exact_match(
-                PrimitiveDateTime::parse(datetime, &context.datetime_format)?,
-                FileDateExtractor,
-            );
-            Ok(Box::new(matcher))
-        } else {
-            let matcher = FileMatcher::with_regex(datetime, FileDateRegexExtractor)?;
-            Ok(Box::new(matcher))
-        }
-    } else if let Some(modified) = &config.modified {
-        if !config.is_regex {
-            let matcher = FileMatcher::with_exact_match(
-                PrimitiveDateTime::parse(modified, &context.datetime_format)?,
-                FileDateExtractor,
-            );
-            Ok(Box::new(matcher))
-        } else {
-            let matcher = FileMatcher::with_regex(modified, FileDateRegexExtractor)?;
-            Ok(Box::new(matcher))
-        }
-    } else {
+    let mut matchers: Vec<Box<dyn FileMatcherTrait>> = Vec::new();
+
+    let case = config.case_sensitivity();
+    for file_name in &config.file_name {
+        matchers.push(create_name_matcher(file_name, config.is_regex, case)?);
+    }
+    for path in &config.path {
+        matchers.push(create_path_matcher(
+            path,
+            config.is_regex,
+            case,
+            &config.directory,
+        )?);
+    }
+    for pattern in &config.contains {
+        matchers.push(create_content_matcher(pattern, config, case)?);
+    }
+    for size in &config.size {
+        matchers.push(create_size_matcher(size, config.is_regex)?);
+    }
+    for ext in &config.ext {
+        matchers.push(create_ext_matcher(ext, config.is_regex, case)?);
+    }
+    for mime_type in &config.mime_type {
+        matchers.push(create_type_matcher(mime_type, config.is_regex)?);
+    }
+    for datetime in &config.datetime {
+        matchers.push(create_date_matcher(
+            datetime,
+            config.is_regex,
+            FileTimestamp::Created,
+            context,
+        )?);
+    }
+    for modified in &config.modified {
+        matchers.push(create_date_matcher(
+            modified,
+            config.is_regex,
+            FileTimestamp::Modified,
+            context,
+        )?);
+    }
+    for accessed in &config.accessed {
+        matchers.push(create_date_matcher(
+            accessed,
+            config.is_regex,
+            FileTimestamp::Accessed,
+            context,
+        )?);
+    }
+    for changed in &config.changed {
+        matchers.push(create_date_matcher(
+            changed,
+            config.is_regex,
+            FileTimestamp::Changed,
+            context,
+        )?);
+    }
+    if let Some(after) = &config.taken_after {
+        matchers.push(create_taken_matcher(&format!("{after}.."), context)?);
+    }
+    if let Some(before) = &config.taken_before {
+        matchers.push(create_taken_matcher(&format!("..{before}"), context)?);
+    }
+
+    for query in &config.where_query {
+        matchers.push(compile_query(query, case, &config.directory, context)?);
+    }
+
+    if matchers.is_empty() {
         bail!("No valid filter configuration found");
     }
+
+    trace!("Combining {} filter(s)", matchers.len());
+    let combined: Box<dyn FileMatcherTrait> = if matchers.len() == 1 {
+        matchers.remove(0)
+    } else if config.is_or {
+        Box::new(AnyOf::new(matchers))
+    } else {
+        Box::new(AllOf::new(matchers))
+    };
+
+    if config.is_not {
+        Ok(Box::new(Not::new(combined)))
+    } else {
+        Ok(combined)
+    }
+}
+
+fn create_name_matcher(
+    file_name: &str,
+    is_regex: bool,
+    case: CaseSensitivity,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let ignore_case = case.ignores_case(file_name);
+    if !is_regex && is_glob_pattern(file_name) {
+        let matcher = FileMatcher::with_glob(file_name, ignore_case, FileNameExtractor)?;
+        Ok(Box::new(matcher))
+    } else if !is_regex {
+        let matcher = FileMatcher::with_exact_text(file_name, ignore_case, FileNameExtractor);
+        Ok(Box::new(matcher))
+    } else {
+        let matcher = FileMatcher::with_regex(file_name, ignore_case, FileNameExtractor)?;
+        Ok(Box::new(matcher))
+    }
+}
+
+fn create_path_matcher(
+    path: &str,
+    is_regex: bool,
+    case: CaseSensitivity,
+    root: &Path,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let ignore_case = case.ignores_case(path);
+    let extractor = FilePathExtractor::new(root);
+    if is_regex {
+        let matcher = FileMatcher::with_regex(path, ignore_case, extractor)?;
+        return Ok(Box::new(matcher));
+    }
+
+    let path = path.trim_start_matches("./");
+    if is_glob_pattern(path) {
+        let matcher = FileMatcher::with_glob(path, ignore_case, extractor)?;
+        Ok(Box::new(matcher))
+    } else {
+        let matcher = FileMatcher::with_exact_text(path, ignore_case, extractor);
+        Ok(Box::new(matcher))
+    }
+}
+
+fn create_content_matcher(
+    pattern: &str,
+    config: &FindCommand,
+    case: CaseSensitivity,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let pattern = ContentPattern::new(pattern, config.is_regex, case.ignores_case(pattern))?;
+    Ok(Box::new(FileMatcher::new(
+        Box::new(ContentFilter::new(pattern)),
+        FileContentExtractor::new(config.max_scan_bytes()),
+    )))
+}
+
+/// Patterns of all `--contains` filters, used to print matched lines
+pub fn create_content_patterns(config: &FindCommand) -> anyhow::Result<Vec<ContentPattern>> {
+    let case = config.case_sensitivity();
+    config
+        .contains
+        .iter()
+        .map(|pattern| ContentPattern::new(pattern, config.is_regex, case.ignores_case(pattern)))
+        .collect()
+}
+
+fn create_size_matcher(size: &str, is_regex: bool) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    if is_regex {
+        let matcher = FileMatcher::with_regex(size, false, FileSizeRegexExtractor)?;
+        return Ok(Box::new(matcher));
+    }
+
+    // Supported forms: `1M` (exact), `+1M` (greater), `-1M` (less), `1K..5M`, `1K..`, `..5M` (inclusive range)
+    let filter: Box<dyn Filter<u64>> = if let Some((start, end)) = size.split_once("..") {
+        let to_bound = |value: &str| -> anyhow::Result<Bound<u64>> {
+            if value.is_empty() {
+                Ok(Bound::Unbounded)
+            } else {
+                Ok(Bound::Included(parse_size(value)?))
+            }
+        };
+        Box::new(RangeFilter::new(to_bound(start)?, to_bound(end)?))
+    } else if let Some(value) = size.strip_prefix('+') {
+        Box::new(ComparisonFilter::new(
+            Comparison::Greater,
+            parse_size(value)?,
+        ))
+    } else if let Some(value) = size.strip_prefix('-') {
+        Box::new(ComparisonFilter::new(Comparison::Less, parse_size(value)?))
+    } else {
+        Box::new(ExactMatchFilter::new(parse_size(size)?))
+    };
+
+    Ok(Box::new(FileMatcher::new(filter, FileSizeExtractor)))
+}
+
+fn create_ext_matcher(
+    ext: &str,
+    is_regex: bool,
+    case: CaseSensitivity,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let ignore_case = case.ignores_case(ext);
+    if !is_regex {
+        let matcher = FileMatcher::with_exact_text(ext, ignore_case, FileExtensionExtractor);
+        Ok(Box::new(matcher))
+    } else {
+        let matcher = FileMatcher::with_regex(ext, ignore_case, FileExtensionExtractor)?;
+        Ok(Box::new(matcher))
+    }
+}
+
+/// MIME types are matched case-insensitively, e.g. `image/*` or `application/pdf`
+fn create_type_matcher(
+    mime_type: &str,
+    is_regex: bool,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    if is_regex {
+        let matcher = FileMatcher::with_regex(mime_type, true, MimeTypeExtractor)?;
+        Ok(Box::new(matcher))
+    } else if is_glob_pattern(mime_type) {
+        let matcher = FileMatcher::with_glob(mime_type, true, MimeTypeExtractor)?;
+        Ok(Box::new(matcher))
+    } else {
+        let matcher = FileMatcher::with_exact_text(mime_type, true, MimeTypeExtractor);
+        Ok(Box::new(matcher))
+    }
+}
+
+fn create_date_matcher(
+    value: &str,
+    is_regex: bool,
+    timestamp: FileTimestamp,
+    context: &Cli,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    if !is_regex {
+        let (start, end) = parse_date_filter(value, context, now(context))?;
+        Ok(date_matcher(
+            timestamp,
+            Box::new(RangeFilter::new(start, end)),
+        ))
+    } else {
+        let matcher = FileMatcher::with_regex(value, false, FileDateRegexExtractor(timestamp))?;
+        Ok(Box::new(matcher))
+    }
+}
+
+/// Date photo or video was taken, in the same forms as other date filters
+fn create_taken_matcher(value: &str, context: &Cli) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let (start, end) = parse_date_filter(value, context, now(context))?;
+    Ok(Box::new(FileMatcher::new(
+        Box::new(RangeFilter::new(start, end)),
+        FileTakenExtractor,
+    )))
+}
+
+/// Combine date filter with extractor of the selected timestamp
+pub fn date_matcher(
+    timestamp: FileTimestamp,
+    filter: Box<dyn Filter<PrimitiveDateTime>>,
+) -> Box<dyn FileMatcherTrait> {
+    match timestamp {
+        FileTimestamp::Created => Box::new(FileMatcher::new(filter, FileDateExtractor)),
+        FileTimestamp::Modified => Box::new(FileMatcher::new(filter, FileModifiedExtractor)),
+        FileTimestamp::Accessed => Box::new(FileMatcher::new(filter, FileAccessedExtractor)),
+        FileTimestamp::Changed => Box::new(FileMatcher::new(filter, FileChangedExtractor)),
+    }
+}
+
+/// Current time in local timezone, in the same form as date extractors produce
+pub fn now(context: &Cli) -> PrimitiveDateTime {
+    let now = context.local_time(SystemTime::now());
+    PrimitiveDateTime::new(now.date(), now.time())
 }
 
 #[cfg(test)]
@@ -135,6 +387,7 @@ mod tests {
         cli::Cli, commands::find::filtering::create_matcher_from_config,
         volumes::get_path_under_tests,
     };
+    use test_case::test_case;
     use time::PrimitiveDateTime;
 
     fn get_dir_entry_from_path(path_str: &str) -> std::fs::DirEntry {
@@ -145,7 +398,7 @@ mod tests {
     #[test]
     fn test_create_matcher_from_config_file_name_exact_match() {
         let find_command = crate::commands::find::FindCommand {
-            file_name: Some("matched.txt".to_string()),
+            file_name: vec!["matched.txt".to_string()],
             is_regex: false,
             ..Default::default()
         };
@@ -162,7 +415,7 @@ mod tests {
     #[test]
     fn test_create_matcher_from_config_file_name_regex_match() {
         let find_command = crate::commands::find::FindCommand {
-            file_name: Some(r"^matched\.txt$".to_string()),
+            file_name: vec![r"^matched\.txt$".to_string()],
             is_regex: true,
             ..Default::default()
         };
@@ -176,10 +429,64 @@ mod tests {
         assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
     }
 
+    #[test]
+    fn test_create_matcher_from_config_file_name_glob_match() {
+        let find_command = crate::commands::find::FindCommand {
+            file_name: vec!["match?d.*".to_string()],
+            is_regex: false,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matched_file = get_dir_entry_from_path("filtering/matched");
+        let not_matched_file = get_dir_entry_from_path("filtering/not_matched");
+
+        assert!(file_matcher.matches(&matched_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
+    }
+
+    #[test_case("matched/matched.txt", false, true ; "exact")]
+    #[test_case("./matched/matched.txt", false, true ; "exact with leading dot")]
+    #[test_case("matched.txt", false, false ; "exact is not basename")]
+    #[test_case("**/matched/*.txt", false, true ; "glob")]
+    #[test_case("^matched/", true, true ; "regex")]
+    fn test_create_matcher_from_config_path(path: &str, is_regex: bool, expected: bool) {
+        let find_command = crate::commands::find::FindCommand {
+            directory: get_path_under_tests("filtering"),
+            path: vec![path.to_string()],
+            is_regex,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let file = get_dir_entry_from_path("filtering/matched");
+
+        assert_eq!(file_matcher.matches(&file, &cli).unwrap(), expected);
+    }
+
+    #[test_case("extension testing", false, true ; "literal")]
+    #[test_case("Extension", false, false ; "smart case uppercase")]
+    #[test_case(r"^This is \w+ text", true, true ; "regex")]
+    fn test_create_matcher_from_config_contains(pattern: &str, is_regex: bool, expected: bool) {
+        let find_command = crate::commands::find::FindCommand {
+            contains: vec![pattern.to_string()],
+            is_regex,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let file = get_dir_entry_from_path("filtering/ext_txt");
+
+        assert_eq!(file_matcher.matches(&file, &cli).unwrap(), expected);
+    }
+
     #[test]
     fn test_create_matcher_from_config_size_exact_match() {
         let find_command = crate::commands::find::FindCommand {
-            size: Some("1024".to_string()),
+            size: vec!["1024".to_string()],
             is_regex: false,
             ..Default::default()
         };
@@ -196,7 +503,7 @@ mod tests {
     #[test]
     fn test_create_matcher_from_config_size_regex_match() {
         let find_command = crate::commands::find::FindCommand {
-            size: Some("10..".to_string()),
+            size: vec!["10..".to_string()],
             is_regex: true,
             ..Default::default()
         };
@@ -210,10 +517,72 @@ mod tests {
         assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
     }
 
+    #[test]
+    fn test_create_matcher_from_config_size_with_unit() {
+        let find_command = crate::commands::find::FindCommand {
+            size: vec!["1K".to_string()],
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matched_file = get_dir_entry_from_path("filtering/size_1024");
+        let not_matched_file = get_dir_entry_from_path("filtering/size_512");
+
+        assert!(file_matcher.matches(&matched_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
+    }
+
+    #[test]
+    fn test_create_matcher_from_config_size_comparison() {
+        let cli = Cli::default();
+        let larger_file = get_dir_entry_from_path("filtering/size_1024");
+        let smaller_file = get_dir_entry_from_path("filtering/size_512");
+
+        let find_command = crate::commands::find::FindCommand {
+            size: vec!["+512".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        assert!(file_matcher.matches(&larger_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&smaller_file, &cli).unwrap());
+
+        let find_command = crate::commands::find::FindCommand {
+            size: vec!["-1K".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        assert!(!file_matcher.matches(&larger_file, &cli).unwrap());
+        assert!(file_matcher.matches(&smaller_file, &cli).unwrap());
+    }
+
+    #[test]
+    fn test_create_matcher_from_config_size_range() {
+        let cli = Cli::default();
+        let larger_file = get_dir_entry_from_path("filtering/size_1024");
+        let smaller_file = get_dir_entry_from_path("filtering/size_512");
+
+        let find_command = crate::commands::find::FindCommand {
+            size: vec!["512..1000".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        assert!(!file_matcher.matches(&larger_file, &cli).unwrap());
+        assert!(file_matcher.matches(&smaller_file, &cli).unwrap());
+
+        let find_command = crate::commands::find::FindCommand {
+            size: vec!["1K..".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        assert!(file_matcher.matches(&larger_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&smaller_file, &cli).unwrap());
+    }
+
     #[test]
     fn test_create_matcher_from_config_ext_exact_match() {
         let find_command = crate::commands::find::FindCommand {
-            ext: Some("txt".to_string()),
+            ext: vec!["txt".to_string()],
             is_regex: false,
             ..Default::default()
         };
@@ -230,7 +599,7 @@ mod tests {
     #[test]
     fn test_create_matcher_from_config_ext_regex_match() {
         let find_command = crate::commands::find::FindCommand {
-            ext: Some(r"^txt$".to_string()),
+            ext: vec![r"^txt$".to_string()],
             is_regex: true,
             ..Default::default()
         };
@@ -244,10 +613,46 @@ mod tests {
         assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
     }
 
+    #[test_case("jpg", false, false, true ; "smart case lowercase pattern")]
+    #[test_case("JPG", false, false, true ; "smart case uppercase pattern")]
+    #[test_case("Jpg", false, false, false ; "smart case mixed pattern")]
+    #[test_case("Jpg", true, false, true ; "ignore case")]
+    #[test_case("jpg", false, true, false ; "case sensitive")]
+    fn test_create_matcher_from_config_ext_case(
+        ext: &str,
+        ignore_case: bool,
+        case_sensitive: bool,
+        expected: bool,
+    ) {
+        let temp_dir = std::env::temp_dir().join(format!(
+            "fo_test_ext_case_{ext}_{ignore_case}_{case_sensitive}"
+        ));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("photo.JPG"), "").unwrap();
+        let file = std::fs::read_dir(&temp_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+
+        let find_command = crate::commands::find::FindCommand {
+            ext: vec![ext.to_string()],
+            ignore_case,
+            case_sensitive,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matched = file_matcher.matches(&file, &cli).unwrap();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(matched, expected);
+    }
+
     #[test]
     fn test_create_matcher_from_config_ext_empty_match() {
         let find_command = crate::commands::find::FindCommand {
-            ext: Some("".to_string()),
+            ext: vec!["".to_string()],
             is_regex: false,
             ..Default::default()
         };
@@ -261,6 +666,77 @@ mod tests {
         assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
     }
 
+    #[test]
+    fn test_create_matcher_from_config_combined_and_match() {
+        let find_command = crate::commands::find::FindCommand {
+            file_name: vec!["matched.txt".to_string()],
+            ext: vec!["txt".to_string()],
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matched_file = get_dir_entry_from_path("filtering/matched");
+        let not_matched_file = get_dir_entry_from_path("filtering/not_matched");
+
+        assert!(file_matcher.matches(&matched_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
+    }
+
+    #[test]
+    fn test_create_matcher_from_config_combined_or_match() {
+        let find_command = crate::commands::find::FindCommand {
+            ext: vec!["txt".to_string(), "rs".to_string()],
+            is_or: true,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let txt_file = get_dir_entry_from_path("filtering/ext_txt");
+        let rs_file = get_dir_entry_from_path("filtering/ext_rs");
+        let not_matched_file = get_dir_entry_from_path("filtering/no_ext");
+
+        assert!(file_matcher.matches(&txt_file, &cli).unwrap());
+        assert!(file_matcher.matches(&rs_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
+    }
+
+    #[test]
+    fn test_create_matcher_from_config_combined_not_match() {
+        let find_command = crate::commands::find::FindCommand {
+            ext: vec!["txt".to_string()],
+            is_not: true,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matched_file = get_dir_entry_from_path("filtering/ext_rs");
+        let not_matched_file = get_dir_entry_from_path("filtering/ext_txt");
+
+        assert!(file_matcher.matches(&matched_file, &cli).unwrap());
+        assert!(!file_matcher.matches(&not_matched_file, &cli).unwrap());
+    }
+
+    #[test]
+    fn test_create_matcher_from_config_not_inverts_combined_filters() {
+        let find_command = crate::commands::find::FindCommand {
+            file_name: vec!["matched.txt".to_string()],
+            ext: vec!["txt".to_string()],
+            is_not: true,
+            ..Default::default()
+        };
+        let cli = Cli::default();
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let both_matched = get_dir_entry_from_path("filtering/matched");
+        let only_ext_matched = get_dir_entry_from_path("filtering/not_matched");
+
+        assert!(!file_matcher.matches(&both_matched, &cli).unwrap());
+        assert!(file_matcher.matches(&only_ext_matched, &cli).unwrap());
+    }
+
     #[test]
     fn test_create_matcher_from_config_datetime_exact_match() {
         let cli = Cli::default();
@@ -280,7 +756,7 @@ mod tests {
         let datetime_str = primitive_datetime.format(&cli.datetime_format).unwrap();
 
         let find_command = crate::commands::find::FindCommand {
-            datetime: Some(datetime_str.clone()),
+            datetime: vec![datetime_str.clone()],
             is_regex: false,
             ..Default::default()
         };
@@ -327,7 +803,7 @@ mod tests {
         let regex_pattern = format!(r"^{}.*", regex::escape(date_part));
 
         let find_command = crate::commands::find::FindCommand {
-            datetime: Some(regex_pattern),
+            datetime: vec![regex_pattern],
             is_regex: true,
             ..Default::default()
         };
@@ -373,7 +849,7 @@ mod tests {
         let datetime_str = primitive_datetime.format(&cli.datetime_format).unwrap();
 
         let find_command = crate::commands::find::FindCommand {
-            modified: Some(datetime_str.clone()),
+            modified: vec![datetime_str.clone()],
             is_regex: false,
             ..Default::default()
         };
@@ -398,6 +874,38 @@ mod tests {
         std::fs::remove_file(&test_file_path).ok();
     }
 
+    #[test_case("2025-06-13", true ; "local date")]
+    #[test_case("2025-06-14", false ; "utc date")]
+    fn test_create_matcher_from_config_modified_in_local_timezone(date: &str, expected: bool) {
+        let temp_dir = std::env::temp_dir().join(format!("fo_test_find_local_date_{date}"));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("late.txt");
+        // 2025-06-14 02:00 UTC is still 2025-06-13 in UTC-5
+        std::fs::File::create(&path)
+            .unwrap()
+            .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_749_866_400))
+            .unwrap();
+        let cli = Cli {
+            local_offset: time::UtcOffset::from_hms(-5, 0, 0).unwrap(),
+            ..Default::default()
+        };
+        let find_command = crate::commands::find::FindCommand {
+            modified: vec![date.to_string()],
+            ..Default::default()
+        };
+
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let entry = std::fs::read_dir(&temp_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let matched = file_matcher.matches(&entry, &cli).unwrap();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(matched, expected);
+    }
+
     #[test]
     fn test_create_matcher_from_config_modified_regex_match() {
         let cli = Cli::default();
@@ -420,7 +928,7 @@ mod tests {
         let regex_pattern = format!(r"^{}.*", regex::escape(date_part));
 
         let find_command = crate::commands::find::FindCommand {
-            modified: Some(regex_pattern),
+            modified: vec![regex_pattern],
             is_regex: true,
             ..Default::default()
         };
@@ -444,4 +952,80 @@ mod tests {
         // Cleanup
         std::fs::remove_file(&test_file_path).ok();
     }
+
+    #[test]
+    fn test_create_matcher_from_config_modified_uses_mtime() {
+        let cli = Cli::default();
+
+        let temp_dir = std::env::temp_dir().join("fo_test_modified_uses_mtime");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let file = std::fs::File::create(temp_dir.join("old.txt")).unwrap();
+        let modified =
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_577_934_000); // 2020-01-02 03:00 UTC
+        file.set_modified(modified).unwrap();
+
+        let find_command = crate::commands::find::FindCommand {
+            modified: vec!["2020-01-02".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let test_entry = std::fs::read_dir(&temp_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(file_matcher.matches(&test_entry, &cli).unwrap());
+
+        let find_command = crate::commands::find::FindCommand {
+            accessed: vec!["2020-01-02".to_string()],
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        assert!(!file_matcher.matches(&test_entry, &cli).unwrap());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test_case(Some("2021-07"), None, true, true ; "after")]
+    #[test_case(None, Some("2022"), true, false ; "before")]
+    #[test_case(Some("2021-07-05"), None, false, true ; "after taken date")]
+    fn test_create_matcher_from_config_taken(
+        after: Option<&str>,
+        before: Option<&str>,
+        photo_matches: bool,
+        text_matches: bool,
+    ) {
+        let cli = Cli::default();
+        let temp_dir = std::env::temp_dir().join(format!(
+            "fo_test_taken_{}_{}",
+            after.unwrap_or_default(),
+            before.unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(
+            temp_dir.join("photo.jpg"),
+            crate::media::sample_jpeg("2021:07:04 15:30:00"),
+        )
+        .unwrap();
+        std::fs::write(temp_dir.join("notes.txt"), "2021:07:04 15:30:00").unwrap();
+
+        let find_command = crate::commands::find::FindCommand {
+            taken_after: after.map(str::to_string),
+            taken_before: before.map(str::to_string),
+            ..Default::default()
+        };
+        let file_matcher = create_matcher_from_config(&find_command, &cli).unwrap();
+        let matches = |name: &str| {
+            let entry = std::fs::read_dir(&temp_dir)
+                .unwrap()
+                .map(|entry| entry.unwrap())
+                .find(|entry| entry.file_name() == name)
+                .unwrap();
+            file_matcher.matches(&entry, &cli).unwrap()
+        };
+        let (photo, text) = (matches("photo.jpg"), matches("notes.txt"));
+        std::fs::remove_dir_all(&temp_dir).ok();
+
+        assert_eq!((photo, text), (photo_matches, text_matches));
+    }
 }
@@ -0,0 +1,64 @@
+use std::fs::DirEntry;
+
+use crate::{cli::Cli, commands::find::filtering::FileMatcherTrait};
+
+/// Matches when every inner matcher matches. Empty list always matches.
+pub struct AllOf {
+    matchers: Vec<Box<dyn FileMatcherTrait>>,
+}
+
+impl AllOf {
+    pub fn new(matchers: Vec<Box<dyn FileMatcherTrait>>) -> Self {
+        Self { matchers }
+    }
+}
+
+impl FileMatcherTrait for AllOf {
+    fn matches(&self, file: &DirEntry, cli: &Cli) -> anyhow::Result<bool> {
+        for matcher in &self.matchers {
+            if !matcher.matches(file, cli)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Matches when at least one inner matcher matches. Empty list never matches.
+pub struct AnyOf {
+    matchers: Vec<Box<dyn FileMatcherTrait>>,
+}
+
+impl AnyOf {
+    pub fn new(matchers: Vec<Box<dyn FileMatcherTrait>>) -> Self {
+        Self { matchers }
+    }
+}
+
+impl FileMatcherTrait for AnyOf {
+    fn matches(&self, file: &DirEntry, cli: &Cli) -> anyhow::Result<bool> {
+        for matcher in &self.matchers {
+            if matcher.matches(file, cli)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Inverts the result of the inner matcher
+pub struct Not {
+    matcher: Box<dyn FileMatcherTrait>,
+}
+
+impl Not {
+    pub fn new(matcher: Box<dyn FileMatcherTrait>) -> Self {
+        Self { matcher }
+    }
+}
+
+impl FileMatcherTrait for Not {
+    fn matches(&self, file: &DirEntry, cli: &Cli) -> anyhow::Result<bool> {
+        Ok(!self.matcher.matches(file, cli)?)
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Take},
+    path::Path,
+};
+
+use regex::{Regex, RegexBuilder};
+
+/// How many bytes from the start of file are checked for NUL to detect binary files
+pub const BINARY_CHECK_BYTES: usize = 8 * 1024;
+
+/// Default limit of bytes read from every file by content search
+pub const DEFAULT_MAX_SCAN_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Contents of a file that is streamed line by line, limited to max scanned bytes
+pub enum FileContent {
+    Text(BufReader<Take<File>>),
+    /// Directories and binary files are never matched by content
+    Skipped,
+}
+
+impl FileContent {
+    pub fn open(path: &Path, max_bytes: u64) -> std::io::Result<Self> {
+        if !path.is_file() {
+            return Ok(FileContent::Skipped);
+        }
+
+        let mut reader =
+            BufReader::with_capacity(BINARY_CHECK_BYTES, File::open(path)?.take(max_bytes));
+        if reader.fill_buf()?.contains(&0) {
+            return Ok(FileContent::Skipped);
+        }
+
+        Ok(FileContent::Text(reader))
+    }
+}
+
+/// Line of a file that matched content pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchedLine {
+    /// 1-based line number
+    pub number: usize,
+    pub text: String,
+}
+
+/// Literal or regex pattern searched in file lines
+pub struct ContentPattern {
+    regex: Regex,
+}
+
+impl ContentPattern {
+    pub fn new(pattern: &str, is_regex: bool, ignore_case: bool) -> anyhow::Result<Self> {
+        let pattern = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(ContentPattern { regex })
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+}
+
+/// Read lines until the first one that matches pattern
+pub fn contains_match(reader: impl BufRead, pattern: &ContentPattern) -> std::io::Result<bool> {
+    let mut found = false;
+    for_each_line(reader, |_, line| {
+        found = pattern.is_match(line);
+        !found
+    })?;
+    Ok(found)
+}
+
+/// Collect all lines that match any of patterns
+pub fn matching_lines(
+    reader: impl BufRead,
+    patterns: &[ContentPattern],
+) -> std::io::Result<Vec<MatchedLine>> {
+    let mut lines = Vec::new();
+    for_each_line(reader, |number, line| {
+        if patterns.iter().any(|pattern| pattern.is_match(line)) {
+            lines.push(MatchedLine {
+                number,
+                text: line.to_string(),
+            });
+        }
+        true
+    })?;
+    Ok(lines)
+}
+
+/// Call `visit` with line number and line without line ending until it returns false.
+/// Invalid UTF-8 is replaced, so files in other encodings can still be searched for ASCII text.
+fn for_each_line(
+    mut reader: impl BufRead,
+    mut visit: impl FnMut(usize, &str) -> bool,
+) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut number = 0;
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        let line = String::from_utf8_lossy(&buffer);
+        if !visit(number, line.trim_end_matches(['\n', '\r'])) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("db_host", false, false, true ; "literal")]
+    #[test_case("db.host", false, false, false ; "literal dot is not wildcard")]
+    #[test_case(r"db_\w+ =", true, false, true ; "regex")]
+    #[test_case("DB_HOST", false, true, true ; "ignore case")]
+    #[test_case("DB_HOST", false, false, false ; "case sensitive")]
+    fn test_contains_match(pattern: &str, is_regex: bool, ignore_case: bool, expected: bool) {
+        let pattern = ContentPattern::new(pattern, is_regex, ignore_case).unwrap();
+        let content = "name = app\ndb_host = localhost\n";
+        assert_eq!(
+            contains_match(content.as_bytes(), &pattern).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_matching_lines() {
+        let patterns = [
+            ContentPattern::new("host", false, false).unwrap(),
+            ContentPattern::new("^port", true, false).unwrap(),
+        ];
+        let content = "db_host = a\r\nname = b\nport = 1\nredis_host = c";
+
+        let lines = matching_lines(content.as_bytes(), &patterns).unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                MatchedLine {
+                    number: 1,
+                    text: "db_host = a".to_string()
+                },
+                MatchedLine {
+                    number: 3,
+                    text: "port = 1".to_string()
+                },
+                MatchedLine {
+                    number: 4,
+                    text: "redis_host = c".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_skips_binary_and_limits_bytes() {
+        let temp_dir = std::env::temp_dir().join("fo_test_content_open");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let binary = temp_dir.join("binary.bin");
+        let text = temp_dir.join("text.txt");
+        std::fs::write(&binary, b"db_host\0\x01\x02").unwrap();
+        std::fs::write(&text, "first line\ndb_host = localhost\n").unwrap();
+        let pattern = ContentPattern::new("db_host", false, false).unwrap();
+
+        let binary_content = FileContent::open(&binary, DEFAULT_MAX_SCAN_BYTES).unwrap();
+        let dir_content = FileContent::open(&temp_dir, DEFAULT_MAX_SCAN_BYTES).unwrap();
+        let FileContent::Text(full) = FileContent::open(&text, DEFAULT_MAX_SCAN_BYTES).unwrap()
+        else {
+            panic!("text file must be readable");
+        };
+        let FileContent::Text(limited) = FileContent::open(&text, 11).unwrap() else {
+            panic!("text file must be readable");
+        };
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(matches!(binary_content, FileContent::Skipped));
+        assert!(matches!(dir_content, FileContent::Skipped));
+        assert!(contains_match(full, &pattern).unwrap());
+        assert!(!contains_match(limited, &pattern).unwrap());
+    }
+}
@@ -0,0 +1,348 @@
+use std::{ops::Bound, sync::LazyLock};
+
+use anyhow::{anyhow, bail};
+use regex::{Captures, Regex};
+use time::{Date, Duration, Month, PrimitiveDateTime, Time};
+
+use crate::cli::Cli;
+
+/// Calendar date with optional month, day and time, e.g. `2025`, `2025-06`, `2025-06-14`, `2025-06-14 10:30`
+static CALENDAR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4})(?:-(\d{1,2})(?:-(\d{1,2})(?:[ T](\d{1,2}):(\d{2}))?)?)?$")
+        .expect("Should never fail since the regex is hardcoded and correct")
+});
+
+/// Relative duration, e.g. `30m`, `3h`, `2w`
+static DURATION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d+)\s*(m|min|h|d|w|y)$")
+        .expect("Should never fail since the regex is hardcoded and correct")
+});
+
+/// Half-open period of time [start, end) that a date expression covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateInterval {
+    pub start: PrimitiveDateTime,
+    pub end: PrimitiveDateTime,
+}
+
+/// Parse date filter into range bounds.
+///
+/// Supported forms:
+/// - `2025`, `2025-06`, `2025-06-14`, `2025-06-14 10:30` - whole year, month, day or minute
+/// - `today`, `yesterday`
+/// - `-7d` or `7d` - within the last 7 days, `+7d` - older than 7 days (units: m, h, d, w, y)
+/// - `A..B`, `A..`, `..B` - between, after or before any of the above, both ends included
+pub fn parse_date_filter(
+    value: &str,
+    context: &Cli,
+    now: PrimitiveDateTime,
+) -> anyhow::Result<(Bound<PrimitiveDateTime>, Bound<PrimitiveDateTime>)> {
+    let value = value.trim();
+
+    if let Some((start, end)) = value.split_once("..") {
+        let start = match start.trim() {
+            "" => Bound::Unbounded,
+            start => Bound::Included(parse_date_interval(start, context, now)?.start),
+        };
+        let end = match end.trim() {
+            "" => Bound::Unbounded,
+            end => Bound::Excluded(parse_date_interval(end, context, now)?.end),
+        };
+        return Ok((start, end));
+    }
+
+    if let Some(duration) = value.strip_prefix('+') {
+        let point = before_now(now, duration)?;
+        return Ok((Bound::Unbounded, Bound::Excluded(point)));
+    }
+
+    if let Some(duration) = value.strip_prefix('-') {
+        let point = before_now(now, duration)?;
+        return Ok((Bound::Included(point), Bound::Unbounded));
+    }
+
+    if DURATION_REGEX.is_match(value) {
+        let point = before_now(now, value)?;
+        return Ok((Bound::Included(point), Bound::Unbounded));
+    }
+
+    let interval = parse_date_interval(value, context, now)?;
+    Ok((
+        Bound::Included(interval.start),
+        Bound::Excluded(interval.end),
+    ))
+}
+
+/// Parse single date expression into the period it covers.
+/// Relative durations (`3h`) produce an instant `now - duration`.
+pub fn parse_date_interval(
+    value: &str,
+    context: &Cli,
+    now: PrimitiveDateTime,
+) -> anyhow::Result<DateInterval> {
+    let value = value.trim();
+
+    match value {
+        "today" => return day_interval(now.date()),
+        "yesterday" => {
+            let date = now
+                .date()
+                .previous_day()
+                .ok_or_else(|| anyhow!("Date is out of range"))?;
+            return day_interval(date);
+        }
+        _ => {}
+    }
+
+    if DURATION_REGEX.is_match(value) {
+        let point = before_now(now, value)?;
+        return Ok(DateInterval {
+            start: point,
+            end: point,
+        });
+    }
+
+    if let Some(captures) = CALENDAR_REGEX.captures(value) {
+        return parse_calendar(&captures).map_err(|err| anyhow!("Invalid date '{value}': {err}"));
+    }
+
+    // Fallback to configured datetime format, matching the whole minute
+    if let Ok(start) = PrimitiveDateTime::parse(value, &context.datetime_format) {
+        return minute_interval(start);
+    }
+
+    bail!(
+        "Invalid date '{value}', expected 'YYYY', 'YYYY-MM', 'YYYY-MM-DD', 'YYYY-MM-DD HH:MM', 'today', 'yesterday' or duration like '3h', '2w'"
+    )
+}
+
+/// Build interval from calendar regex captures, granularity depends on which parts are present
+fn parse_calendar(captures: &Captures) -> anyhow::Result<DateInterval> {
+    let number = |index: usize| captures.get(index).map(|m| m.as_str().parse::<u8>());
+    let year: i32 = captures[1].parse()?;
+
+    match (number(2), number(3), number(4), number(5)) {
+        (None, ..) => {
+            let start = date_at(year, Month::January, 1)?;
+            let end = date_at(year + 1, Month::January, 1)?;
+            Ok(DateInterval {
+                start: PrimitiveDateTime::new(start, Time::MIDNIGHT),
+                end: PrimitiveDateTime::new(end, Time::MIDNIGHT),
+            })
+        }
+        (Some(month), None, ..) => {
+            let month = Month::try_from(month?)?;
+            let start = date_at(year, month, 1)?;
+            let end = if month == Month::December {
+                date_at(year + 1, Month::January, 1)?
+            } else {
+                date_at(year, month.next(), 1)?
+            };
+            Ok(DateInterval {
+                start: PrimitiveDateTime::new(start, Time::MIDNIGHT),
+                end: PrimitiveDateTime::new(end, Time::MIDNIGHT),
+            })
+        }
+        (Some(month), Some(day), None, _) => {
+            day_interval(date_at(year, Month::try_from(month?)?, day?)?)
+        }
+        (Some(month), Some(day), Some(hour), Some(minute)) => {
+            let date = date_at(year, Month::try_from(month?)?, day?)?;
+            minute_interval(PrimitiveDateTime::new(
+                date,
+                Time::from_hms(hour?, minute?, 0)?,
+            ))
+        }
+        _ => bail!("Incomplete time"),
+    }
+}
+
+/// Parse duration like `30m`, `3h`, `7d`, `2w`, `1y`
+pub fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let captures = DURATION_REGEX.captures(value.trim()).ok_or_else(|| {
+        anyhow!("Invalid duration '{value}', expected number with unit m, h, d, w or y, e.g. '7d'")
+    })?;
+    let amount: i64 = captures[1]
+        .parse()
+        .map_err(|_| anyhow!("Duration '{value}' is out of range"))?;
+
+    let unit_seconds = match &captures[2] {
+        "m" | "min" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => 365 * 24 * 60 * 60,
+    };
+    let seconds = amount
+        .checked_mul(unit_seconds)
+        .ok_or_else(|| anyhow!("Duration '{value}' is out of range"))?;
+
+    Ok(Duration::seconds(seconds))
+}
+
+/// Instant `duration` before `now`
+fn before_now(now: PrimitiveDateTime, duration: &str) -> anyhow::Result<PrimitiveDateTime> {
+    now.checked_sub(parse_duration(duration)?)
+        .ok_or_else(|| anyhow!("Duration '{duration}' is out of range"))
+}
+
+fn date_at(year: i32, month: Month, day: u8) -> anyhow::Result<Date> {
+    Ok(Date::from_calendar_date(year, month, day)?)
+}
+
+fn day_interval(date: Date) -> anyhow::Result<DateInterval> {
+    let start = PrimitiveDateTime::new(date, Time::MIDNIGHT);
+    interval_of(start, Duration::days(1))
+}
+
+fn minute_interval(start: PrimitiveDateTime) -> anyhow::Result<DateInterval> {
+    interval_of(start, Duration::minutes(1))
+}
+
+/// Interval of `length` from `start`, error when it ends after the last representable date
+fn interval_of(start: PrimitiveDateTime, length: Duration) -> anyhow::Result<DateInterval> {
+    let end = start
+        .checked_add(length)
+        .ok_or_else(|| anyhow!("Date is out of range"))?;
+    Ok(DateInterval { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn now() -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            date_at(2025, Month::June, 14).unwrap(),
+            Time::from_hms(12, 0, 0).unwrap(),
+        )
+    }
+
+    fn datetime(year: i32, month: Month, day: u8, hour: u8, minute: u8) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            date_at(year, month, day).unwrap(),
+            Time::from_hms(hour, minute, 0).unwrap(),
+        )
+    }
+
+    #[test_case(
+        "2025",
+        datetime(2025, Month::January, 1, 0, 0),
+        datetime(2026, Month::January, 1, 0, 0)
+    )]
+    #[test_case(
+        "2025-06",
+        datetime(2025, Month::June, 1, 0, 0),
+        datetime(2025, Month::July, 1, 0, 0)
+    )]
+    #[test_case(
+        "2025-12",
+        datetime(2025, Month::December, 1, 0, 0),
+        datetime(2026, Month::January, 1, 0, 0)
+    )]
+    #[test_case(
+        "2025-06-14",
+        datetime(2025, Month::June, 14, 0, 0),
+        datetime(2025, Month::June, 15, 0, 0)
+    )]
+    #[test_case(
+        "2025-06-14 10:30",
+        datetime(2025, Month::June, 14, 10, 30),
+        datetime(2025, Month::June, 14, 10, 31)
+    )]
+    #[test_case(
+        "14-06-2025 10:30",
+        datetime(2025, Month::June, 14, 10, 30),
+        datetime(2025, Month::June, 14, 10, 31)
+    )]
+    #[test_case(
+        "today",
+        datetime(2025, Month::June, 14, 0, 0),
+        datetime(2025, Month::June, 15, 0, 0)
+    )]
+    #[test_case(
+        "yesterday",
+        datetime(2025, Month::June, 13, 0, 0),
+        datetime(2025, Month::June, 14, 0, 0)
+    )]
+    #[test_case(
+        "3h",
+        datetime(2025, Month::June, 14, 9, 0),
+        datetime(2025, Month::June, 14, 9, 0)
+    )]
+    fn test_parse_date_interval(value: &str, start: PrimitiveDateTime, end: PrimitiveDateTime) {
+        let interval = parse_date_interval(value, &Cli::default(), now()).unwrap();
+        assert_eq!(interval, DateInterval { start, end });
+    }
+
+    #[test_case("2025-13")]
+    #[test_case("2025-02-30")]
+    #[test_case("last week")]
+    #[test_case("3x")]
+    fn test_parse_date_interval_invalid(value: &str) {
+        assert!(parse_date_interval(value, &Cli::default(), now()).is_err());
+    }
+
+    #[test_case("-99999999999d" ; "before first date")]
+    #[test_case("100000000y" ; "years before first date")]
+    #[test_case("+99999999999999y" ; "duration overflow")]
+    #[test_case("99999999999999999999d" ; "amount overflow")]
+    #[test_case("9999-12-31" ; "day after last date")]
+    #[test_case("9999-12-31 23:59" ; "minute after last date")]
+    fn test_parse_date_filter_out_of_range(value: &str) {
+        assert!(parse_date_filter(value, &Cli::default(), now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_filter_relative() {
+        let cli = Cli::default();
+        assert_eq!(
+            parse_date_filter("-7d", &cli, now()).unwrap(),
+            (
+                Bound::Included(datetime(2025, Month::June, 7, 12, 0)),
+                Bound::Unbounded
+            )
+        );
+        assert_eq!(
+            parse_date_filter("2w", &cli, now()).unwrap(),
+            (
+                Bound::Included(datetime(2025, Month::May, 31, 12, 0)),
+                Bound::Unbounded
+            )
+        );
+        assert_eq!(
+            parse_date_filter("+30m", &cli, now()).unwrap(),
+            (
+                Bound::Unbounded,
+                Bound::Excluded(datetime(2025, Month::June, 14, 11, 30))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_date_filter_range() {
+        let cli = Cli::default();
+        assert_eq!(
+            parse_date_filter("2025-01-01..2025-03-31", &cli, now()).unwrap(),
+            (
+                Bound::Included(datetime(2025, Month::January, 1, 0, 0)),
+                Bound::Excluded(datetime(2025, Month::April, 1, 0, 0))
+            )
+        );
+        assert_eq!(
+            parse_date_filter("..2024", &cli, now()).unwrap(),
+            (
+                Bound::Unbounded,
+                Bound::Excluded(datetime(2025, Month::January, 1, 0, 0))
+            )
+        );
+        assert_eq!(
+            parse_date_filter("yesterday..", &cli, now()).unwrap(),
+            (
+                Bound::Included(datetime(2025, Month::June, 13, 0, 0)),
+                Bound::Unbounded
+            )
+        );
+    }
+}
@@ -1,9 +1,19 @@
-use std::fs::DirEntry;
+use std::{
+    fs::DirEntry,
+    path::{Path, PathBuf},
+};
 
 use anyhow::bail;
-use time::{OffsetDateTime, PrimitiveDateTime};
+use log::warn;
+use time::PrimitiveDateTime;
 
-use crate::cli::Cli;
+use crate::{
+    cli::Cli,
+    commands::find::{file_list::relative_path, filtering::content::FileContent},
+    media,
+    mime::MimeType,
+    timestamps::FileTimestamp,
+};
 
 /// Trait for extracting values from DirEntry
 pub trait Extractor<FilterValue> {
@@ -19,6 +29,79 @@ impl Extractor<String> for FileNameExtractor {
     }
 }
 
+/// File path extractor, returns path relative to the search root with `/` separators
+pub struct FilePathExtractor {
+    root: PathBuf,
+}
+
+impl FilePathExtractor {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl Extractor<String> for FilePathExtractor {
+    fn extract(&self, file: &DirEntry, _context: &Cli) -> anyhow::Result<String> {
+        Ok(relative_path(&self.root, &file.path()))
+    }
+}
+
+/// File type extractor, returns one of "file", "dir" or "symlink"
+pub struct FileTypeExtractor;
+
+impl Extractor<String> for FileTypeExtractor {
+    fn extract(&self, file: &DirEntry, _context: &Cli) -> anyhow::Result<String> {
+        match file.file_type() {
+            Ok(file_type) if file_type.is_symlink() => Ok("symlink".to_string()),
+            Ok(file_type) if file_type.is_dir() => Ok("dir".to_string()),
+            Ok(_) => Ok("file".to_string()),
+            Err(err) => bail!("Failed to get file type: {}", err),
+        }
+    }
+}
+
+/// MIME type extractor, detects type from the first bytes of file content
+pub struct MimeTypeExtractor;
+
+impl Extractor<String> for MimeTypeExtractor {
+    fn extract(&self, file: &DirEntry, _context: &Cli) -> anyhow::Result<String> {
+        match MimeType::detect(&file.path()) {
+            Ok(mime) => Ok(mime.name.to_string()),
+            Err(err) => {
+                // Unreadable file should not stop the whole search
+                warn!("Failed to read {}: {}", file.path().display(), err);
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// File content extractor, opens text files for streaming up to `max_bytes`
+pub struct FileContentExtractor {
+    max_bytes: u64,
+}
+
+impl FileContentExtractor {
+    pub fn new(max_bytes: u64) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl Extractor<FileContent> for FileContentExtractor {
+    fn extract(&self, file: &DirEntry, _context: &Cli) -> anyhow::Result<FileContent> {
+        match FileContent::open(&file.path(), self.max_bytes) {
+            Ok(content) => Ok(content),
+            Err(err) => {
+                // Unreadable file should not stop the whole search
+                warn!("Failed to read {}: {}", file.path().display(), err);
+                Ok(FileContent::Skipped)
+            }
+        }
+    }
+}
+
 /// File size extractor
 pub struct FileSizeExtractor;
 
@@ -55,48 +138,105 @@ impl Extractor<String> for FileExtensionExtractor {
     }
 }
 
-/// File datetime extractor
+/// File creation datetime extractor
 pub struct FileDateExtractor;
 
 impl Extractor<PrimitiveDateTime> for FileDateExtractor {
-    fn extract(&self, file: &DirEntry, _context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
-        match file.metadata() {
-            Ok(metadata) => match metadata.created() {
-                Ok(system_time) => {
-                    let offset_datetime = OffsetDateTime::from(system_time)
-                        .replace_second(0)?
-                        .replace_millisecond(0)?;
-
-                    Ok(PrimitiveDateTime::new(
-                        offset_datetime.date(),
-                        offset_datetime.time(),
-                    ))
-                }
-                Err(err) => bail!("Failed to get file creation date: {}", err),
-            },
-            Err(err) => bail!("Failed to get file metadata: {}", err),
+    fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
+        extract_minute_datetime(file, FileTimestamp::Created, context)
+    }
+}
+
+/// File modification datetime (mtime) extractor
+pub struct FileModifiedExtractor;
+
+impl Extractor<PrimitiveDateTime> for FileModifiedExtractor {
+    fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
+        extract_minute_datetime(file, FileTimestamp::Modified, context)
+    }
+}
+
+/// File access datetime (atime) extractor
+pub struct FileAccessedExtractor;
+
+impl Extractor<PrimitiveDateTime> for FileAccessedExtractor {
+    fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
+        extract_minute_datetime(file, FileTimestamp::Accessed, context)
+    }
+}
+
+/// Inode change datetime (ctime) extractor
+pub struct FileChangedExtractor;
+
+impl Extractor<PrimitiveDateTime> for FileChangedExtractor {
+    fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
+        extract_minute_datetime(file, FileTimestamp::Changed, context)
+    }
+}
+
+/// Date photo or video was taken from its metadata, creation datetime for other files
+pub struct FileTakenExtractor;
+
+impl Extractor<PrimitiveDateTime> for FileTakenExtractor {
+    fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PrimitiveDateTime> {
+        let taken = match media::taken_date(&file.path(), context.local_offset) {
+            Ok(taken) => taken,
+            Err(err) => {
+                warn!("Failed to read {}: {}", file.path().display(), err);
+                None
+            }
+        };
+        match taken {
+            Some(taken) => {
+                let local = taken
+                    .to_offset(context.local_offset)
+                    .replace_second(0)?
+                    .replace_millisecond(0)?;
+                Ok(PrimitiveDateTime::new(local.date(), local.time()))
+            }
+            None => extract_minute_datetime(file, FileTimestamp::Created, context),
         }
     }
 }
 
-/// File datetime regex extractor
-pub struct FileDateRegexExtractor;
+/// Read timestamp and truncate it to minutes, so it can be compared with user input
+fn extract_minute_datetime(
+    file: &DirEntry,
+    timestamp: FileTimestamp,
+    context: &Cli,
+) -> anyhow::Result<PrimitiveDateTime> {
+    match file.metadata() {
+        Ok(metadata) => {
+            let offset_datetime = context
+                .local_time(timestamp.read(&metadata, context.time_source)?)
+                .replace_second(0)?
+                .replace_millisecond(0)?;
+
+            Ok(PrimitiveDateTime::new(
+                offset_datetime.date(),
+                offset_datetime.time(),
+            ))
+        }
+        Err(err) => bail!("Failed to get file metadata: {}", err),
+    }
+}
+
+/// File datetime regex extractor, formats selected timestamp with configured datetime format
+pub struct FileDateRegexExtractor(pub FileTimestamp);
 
 impl Extractor<String> for FileDateRegexExtractor {
     fn extract(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<String> {
         match file.metadata() {
-            Ok(metadata) => match metadata.created() {
-                Ok(system_time) => {
-                    let offset_datetime = OffsetDateTime::from(system_time);
-                    let primitive_datetime =
-                        PrimitiveDateTime::new(offset_datetime.date(), offset_datetime.time());
-
-                    let formatted = primitive_datetime.format(&context.datetime_format)?;
-
-                    Ok(formatted)
-                }
-                Err(err) => bail!("Failed to get file creation date: {}", err),
-            },
+            Ok(metadata) => {
+                let offset_datetime =
+                    context.local_time(self.0.read(&metadata, context.time_source)?);
+                let primitive_datetime =
+                    PrimitiveDateTime::new(offset_datetime.date(), offset_datetime.time());
+
+                let formatted = primitive_datetime.format(&context.datetime_format)?;
+
+                Ok(formatted)
+            }
             Err(err) => bail!("Failed to get file metadata: {}", err),
         }
     }
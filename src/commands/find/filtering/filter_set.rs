@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::{
+    AllOf, AnyOf, CaseSensitivity, ContentFilter, ContentPattern, DEFAULT_MAX_SCAN_BYTES,
+    FileContentExtractor, FileMatcher, FileMatcherTrait, compile_query, create_date_matcher,
+    create_ext_matcher, create_name_matcher, create_path_matcher, create_size_matcher,
+    create_taken_matcher, create_type_matcher,
+};
+use crate::{cli::Cli, timestamps::FileTimestamp};
+
+/// Filters of `fo find` written in a file, e.g. as matcher of a sort rule.
+/// Values of one filter are combined with OR, different filters with AND.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterSet {
+    #[serde(default)]
+    pub name: Vec<String>,
+    #[serde(default)]
+    pub path: Vec<String>,
+    #[serde(default)]
+    pub contains: Vec<String>,
+    #[serde(default)]
+    pub size: Vec<String>,
+    #[serde(default)]
+    pub ext: Vec<String>,
+    #[serde(default, rename = "type")]
+    pub mime_type: Vec<String>,
+    #[serde(default)]
+    pub created: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+    #[serde(default)]
+    pub accessed: Vec<String>,
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// Date photos and videos were taken, e.g. `2024..2025`
+    #[serde(default)]
+    pub taken: Vec<String>,
+    #[serde(default, rename = "where")]
+    pub where_query: Vec<String>,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+impl FilterSet {
+    /// Matcher of all filters, matches every file when no filter is set.
+    /// Path filters are relative to `root`.
+    pub fn matcher(&self, root: &Path, context: &Cli) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+        let case = if self.ignore_case {
+            CaseSensitivity::Insensitive
+        } else {
+            CaseSensitivity::Smart
+        };
+        let dates = [
+            (&self.created, FileTimestamp::Created),
+            (&self.modified, FileTimestamp::Modified),
+            (&self.accessed, FileTimestamp::Accessed),
+            (&self.changed, FileTimestamp::Changed),
+        ];
+
+        let mut groups: Vec<Vec<Box<dyn FileMatcherTrait>>> = vec![
+            collect(&self.name, |name| {
+                create_name_matcher(name, self.regex, case)
+            })?,
+            collect(&self.path, |path| {
+                create_path_matcher(path, self.regex, case, root)
+            })?,
+            collect(&self.contains, |pattern| {
+                let pattern = ContentPattern::new(pattern, self.regex, case.ignores_case(pattern))?;
+                Ok(Box::new(FileMatcher::new(
+                    Box::new(ContentFilter::new(pattern)),
+                    FileContentExtractor::new(DEFAULT_MAX_SCAN_BYTES),
+                )))
+            })?,
+            collect(&self.size, |size| create_size_matcher(size, self.regex))?,
+            collect(&self.ext, |ext| create_ext_matcher(ext, self.regex, case))?,
+            collect(&self.mime_type, |mime_type| {
+                create_type_matcher(mime_type, self.regex)
+            })?,
+            collect(&self.taken, |taken| create_taken_matcher(taken, context))?,
+            collect(&self.where_query, |query| {
+                compile_query(query, case, root, context)
+            })?,
+        ];
+        for (values, timestamp) in dates {
+            groups.push(collect(values, |value| {
+                create_date_matcher(value, self.regex, timestamp, context)
+            })?);
+        }
+
+        let matchers = groups
+            .into_iter()
+            .filter(|group| !group.is_empty())
+            .map(|group| Box::new(AnyOf::new(group)) as Box<dyn FileMatcherTrait>)
+            .collect();
+        Ok(Box::new(AllOf::new(matchers)))
+    }
+}
+
+fn collect(
+    values: &[String],
+    create: impl Fn(&str) -> anyhow::Result<Box<dyn FileMatcherTrait>>,
+) -> anyhow::Result<Vec<Box<dyn FileMatcherTrait>>> {
+    values.iter().map(|value| create(value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_values_are_or_filters_are_and() {
+        let temp_dir = std::env::temp_dir().join("fo_test_filter_set");
+        fs::create_dir_all(&temp_dir).unwrap();
+        for name in [
+            "invoice_01.pdf",
+            "invoice_02.PNG",
+            "manual.pdf",
+            "notes.txt",
+        ] {
+            fs::write(temp_dir.join(name), name).unwrap();
+        }
+        let filters: FilterSet =
+            toml::from_str("name = [\"invoice*\"]\next = [\"pdf\", \"png\"]").unwrap();
+        let context = Cli::default();
+
+        let matcher = filters.matcher(&temp_dir, &context).unwrap();
+        let mut matched = fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| matcher.matches(entry, &context).unwrap())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        matched.sort();
+        let everything = FilterSet::default().matcher(&temp_dir, &context).unwrap();
+        let matches_everything = fs::read_dir(&temp_dir)
+            .unwrap()
+            .all(|entry| everything.matches(&entry.unwrap(), &context).unwrap());
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(matched, vec!["invoice_01.pdf", "invoice_02.PNG"]);
+        assert!(matches_everything);
+    }
+
+    #[test]
+    fn test_unknown_filter_is_rejected() {
+        assert!(toml::from_str::<FilterSet>("extension = [\"pdf\"]").is_err());
+    }
+}
@@ -1,4 +1,9 @@
-use regex::Regex;
+use std::ops::{Bound, RangeBounds};
+
+use log::warn;
+use regex::{Regex, RegexBuilder};
+
+use crate::commands::find::filtering::content::{ContentPattern, FileContent, contains_match};
 
 /// Filter trait that defines matching behavior
 pub trait Filter<FilterValue> {
@@ -22,14 +27,67 @@ impl<Value: PartialEq> Filter<Value> for ExactMatchFilter<Value> {
     }
 }
 
+/// Filter that lowercases value before passing it to the inner filter.
+/// Inner filter is expected to be built from lowercased pattern.
+pub struct LowercaseFilter {
+    inner: Box<dyn Filter<String>>,
+}
+
+impl LowercaseFilter {
+    pub fn new(inner: Box<dyn Filter<String>>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Filter<String> for LowercaseFilter {
+    fn is_matched(&self, filter_value: String) -> bool {
+        self.inner.is_matched(filter_value.to_lowercase())
+    }
+}
+
+/// How letter case is treated by text filters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    Sensitive,
+    Insensitive,
+    /// Case-insensitive unless pattern contains uppercase letters
+    #[default]
+    Smart,
+}
+
+impl CaseSensitivity {
+    pub fn ignores_case(&self, pattern: &str) -> bool {
+        match self {
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+            CaseSensitivity::Smart => !has_uppercase_literal(pattern),
+        }
+    }
+}
+
+/// Check for uppercase letters, ignoring escape sequences like `\D` or `\W`
+fn has_uppercase_literal(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            chars.next();
+        } else if ch.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
 /// Filter that check if regex matched
 pub struct RegexFilter {
     regex: Regex,
 }
 
 impl RegexFilter {
-    pub fn new(regex_pattern: &str) -> anyhow::Result<Self> {
-        let regex = Regex::new(regex_pattern)?;
+    pub fn new(regex_pattern: &str, ignore_case: bool) -> anyhow::Result<Self> {
+        let regex = RegexBuilder::new(regex_pattern)
+            .case_insensitive(ignore_case)
+            .build()?;
         Ok(RegexFilter { regex })
     }
 }
@@ -39,3 +97,354 @@ impl Filter<String> for RegexFilter {
         self.regex.is_match(&filter_value)
     }
 }
+
+/// Filter that checks if glob pattern matched.
+/// Supports `*`, `?`, `**`, character classes (`[a-z]`, `[!0-9]`) and alternation (`{a,b}`).
+pub struct GlobFilter {
+    regex: Regex,
+}
+
+impl GlobFilter {
+    pub fn new(glob_pattern: &str, ignore_case: bool) -> anyhow::Result<Self> {
+        let regex = RegexBuilder::new(&glob_to_regex(glob_pattern)?)
+            .case_insensitive(ignore_case)
+            .build()?;
+        Ok(GlobFilter { regex })
+    }
+}
+
+impl Filter<String> for GlobFilter {
+    fn is_matched(&self, filter_value: String) -> bool {
+        self.regex.is_match(&filter_value)
+    }
+}
+
+/// Glob pattern for paths relative to search root.
+/// Pattern without `/` is matched against the base name only, like in `.gitignore`.
+pub struct PathGlob {
+    glob: GlobFilter,
+    is_basename: bool,
+}
+
+impl PathGlob {
+    pub fn new(pattern: &str, ignore_case: bool) -> anyhow::Result<Self> {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        Ok(PathGlob {
+            glob: GlobFilter::new(pattern, ignore_case)?,
+            is_basename: !pattern.contains('/'),
+        })
+    }
+
+    pub fn is_matched(&self, relative_path: &str) -> bool {
+        let value = if self.is_basename {
+            relative_path.rsplit('/').next().unwrap_or(relative_path)
+        } else {
+            relative_path
+        };
+        self.glob.is_matched(value.to_string())
+    }
+}
+
+/// Filter that streams file content until a line matches pattern
+pub struct ContentFilter {
+    pattern: ContentPattern,
+}
+
+impl ContentFilter {
+    pub fn new(pattern: ContentPattern) -> Self {
+        ContentFilter { pattern }
+    }
+}
+
+impl Filter<FileContent> for ContentFilter {
+    fn is_matched(&self, filter_value: FileContent) -> bool {
+        match filter_value {
+            FileContent::Text(reader) => match contains_match(reader, &self.pattern) {
+                Ok(found) => found,
+                Err(err) => {
+                    warn!("Failed to read file content: {err}");
+                    false
+                }
+            },
+            FileContent::Skipped => false,
+        }
+    }
+}
+
+/// Check if pattern contains glob metacharacters
+pub fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Translate glob pattern into anchored regex pattern
+fn glob_to_regex(glob_pattern: &str) -> anyhow::Result<String> {
+    let chars: Vec<char> = glob_pattern.chars().collect();
+    let mut regex = String::from("^");
+    let mut alternation_depth = 0;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            '*' if chars.get(index + 1) == Some(&'*') => {
+                index += 1;
+                if chars.get(index + 1) == Some(&'/') {
+                    // `**/` matches zero or more directories
+                    index += 1;
+                    regex.push_str("(?:.*/)?");
+                } else if regex.ends_with('/') && index + 1 == chars.len() {
+                    // `dir/**` matches the directory itself and everything inside it
+                    regex.pop();
+                    regex.push_str("(?:/.*)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let close = chars[index + 1..]
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .find(|(_, c)| **c == ']')
+                    .map(|(offset, _)| index + 1 + offset)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Unclosed character class in glob '{glob_pattern}'")
+                    })?;
+
+                regex.push('[');
+                let mut class_start = index + 1;
+                if matches!(chars[class_start], '!' | '^') {
+                    regex.push('^');
+                    class_start += 1;
+                }
+                for class_char in &chars[class_start..close] {
+                    if matches!(class_char, '\\' | '[' | ']' | '^' | '&' | '~') {
+                        regex.push('\\');
+                    }
+                    regex.push(*class_char);
+                }
+                regex.push(']');
+                index = close;
+            }
+            '{' => {
+                alternation_depth += 1;
+                regex.push_str("(?:");
+            }
+            ',' if alternation_depth > 0 => regex.push('|'),
+            '}' if alternation_depth > 0 => {
+                alternation_depth -= 1;
+                regex.push(')');
+            }
+            ch => regex.push_str(&regex::escape(&ch.to_string())),
+        }
+        index += 1;
+    }
+
+    if alternation_depth > 0 {
+        anyhow::bail!("Unclosed alternation in glob '{glob_pattern}'");
+    }
+
+    regex.push('$');
+    Ok(regex)
+}
+
+/// Ordering that ComparisonFilter checks the filter value against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// Filter that compares values by order, e.g. `value > compare_value`
+pub struct ComparisonFilter<CompareValue: PartialOrd> {
+    compare_value: CompareValue,
+    comparison: Comparison,
+}
+
+impl<CompareValue: PartialOrd> ComparisonFilter<CompareValue> {
+    pub fn new(comparison: Comparison, compare_value: CompareValue) -> Self {
+        Self {
+            compare_value,
+            comparison,
+        }
+    }
+}
+
+impl<Value: PartialOrd> Filter<Value> for ComparisonFilter<Value> {
+    fn is_matched(&self, filter_value: Value) -> bool {
+        match self.comparison {
+            Comparison::Less => filter_value < self.compare_value,
+            Comparison::LessOrEqual => filter_value <= self.compare_value,
+            Comparison::Greater => filter_value > self.compare_value,
+            Comparison::GreaterOrEqual => filter_value >= self.compare_value,
+        }
+    }
+}
+
+/// Filter that checks if value equals any of the listed values
+pub struct OneOfFilter<CompareValue: PartialEq> {
+    compare_values: Vec<CompareValue>,
+}
+
+impl<CompareValue: PartialEq> OneOfFilter<CompareValue> {
+    pub fn new(compare_values: Vec<CompareValue>) -> Self {
+        Self { compare_values }
+    }
+}
+
+impl<Value: PartialEq> Filter<Value> for OneOfFilter<Value> {
+    fn is_matched(&self, filter_value: Value) -> bool {
+        self.compare_values.contains(&filter_value)
+    }
+}
+
+/// Filter that checks if value lies within range. Each bound can be inclusive, exclusive or open.
+pub struct RangeFilter<CompareValue: PartialOrd> {
+    start: Bound<CompareValue>,
+    end: Bound<CompareValue>,
+}
+
+impl<CompareValue: PartialOrd> RangeFilter<CompareValue> {
+    pub fn new(start: Bound<CompareValue>, end: Bound<CompareValue>) -> Self {
+        Self { start, end }
+    }
+}
+
+impl<Value: PartialOrd> Filter<Value> for RangeFilter<Value> {
+    fn is_matched(&self, filter_value: Value) -> bool {
+        (self.start.as_ref(), self.end.as_ref()).contains(&filter_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("node_modules", "node_modules", true ; "basename at root")]
+    #[test_case("node_modules", "web/node_modules", true ; "basename nested")]
+    #[test_case("*.log", "logs/app.log", true ; "basename glob nested")]
+    #[test_case("node_modules/**", "node_modules/pkg/index.js", true ; "path glob inside")]
+    #[test_case("node_modules/**", "web/node_modules/pkg", false ; "path glob anchored at root")]
+    #[test_case("**/config/*.env", "app/config/prod.env", true ; "path glob any depth")]
+    #[test_case("./build/", "build", true ; "leading dot and trailing slash")]
+    fn test_path_glob(pattern: &str, path: &str, expected: bool) {
+        let glob = PathGlob::new(pattern, false).unwrap();
+        assert_eq!(glob.is_matched(path), expected);
+    }
+
+    #[test_case("*.tar.gz", "backup.tar.gz", true)]
+    #[test_case("*.tar.gz", "backup.tar", false)]
+    #[test_case("*.rs", "src/main.rs", false ; "star does not cross directories")]
+    #[test_case("IMG_20??_*.jpg", "IMG_2024_beach.jpg", true)]
+    #[test_case("IMG_20??_*.jpg", "IMG_202_beach.jpg", false)]
+    #[test_case("report-[0-9]*", "report-7-final", true)]
+    #[test_case("report-[0-9]*", "report-x", false)]
+    #[test_case("[!a]*", "b.txt", true)]
+    #[test_case("[!a]*", "a.txt", false)]
+    #[test_case("*.{jpg,png}", "photo.png", true)]
+    #[test_case("*.{jpg,png}", "photo.gif", false)]
+    #[test_case("{a,b{c,d}}.txt", "bd.txt", true)]
+    #[test_case("**/*.rs", "main.rs", true)]
+    #[test_case("**/*.rs", "src/commands/find.rs", true)]
+    #[test_case("config/**/.env", "app/config/.env", false)]
+    #[test_case("config/**/.env", "config/prod/.env", true)]
+    #[test_case("node_modules/**", "node_modules", true)]
+    #[test_case("node_modules/**", "node_modules/pkg/index.js", true)]
+    #[test_case("a+b(1).txt", "a+b(1).txt", true ; "regex characters are literal")]
+    fn test_glob_filter(pattern: &str, value: &str, expected: bool) {
+        let filter = GlobFilter::new(pattern, false).unwrap();
+        assert_eq!(filter.is_matched(value.to_string()), expected);
+    }
+
+    #[test]
+    fn test_glob_filter_ignore_case() {
+        let filter = GlobFilter::new("*.jpg", true).unwrap();
+        assert!(filter.is_matched("PHOTO.JPG".to_string()));
+
+        let filter = GlobFilter::new("*.jpg", false).unwrap();
+        assert!(!filter.is_matched("PHOTO.JPG".to_string()));
+    }
+
+    #[test]
+    fn test_regex_filter_ignore_case() {
+        let filter = RegexFilter::new("^jpe?g$", true).unwrap();
+        assert!(filter.is_matched("JPG".to_string()));
+
+        let filter = RegexFilter::new("^jpe?g$", false).unwrap();
+        assert!(!filter.is_matched("JPG".to_string()));
+    }
+
+    #[test]
+    fn test_lowercase_filter() {
+        let filter = LowercaseFilter::new(Box::new(ExactMatchFilter::new("jpg".to_string())));
+        assert!(filter.is_matched("JPG".to_string()));
+        assert!(filter.is_matched("jpg".to_string()));
+        assert!(!filter.is_matched("png".to_string()));
+    }
+
+    #[test_case(CaseSensitivity::Smart, "photo", true)]
+    #[test_case(CaseSensitivity::Smart, "Photo", false)]
+    #[test_case(CaseSensitivity::Smart, r"\d+\.jpg", true ; "escape sequences are ignored")]
+    #[test_case(CaseSensitivity::Smart, r"\D+", true ; "uppercase escape is ignored")]
+    #[test_case(CaseSensitivity::Sensitive, "photo", false)]
+    #[test_case(CaseSensitivity::Insensitive, "Photo", true)]
+    fn test_case_sensitivity(case: CaseSensitivity, pattern: &str, expected: bool) {
+        assert_eq!(case.ignores_case(pattern), expected);
+    }
+
+    #[test_case("file[abc")]
+    #[test_case("{a,b")]
+    fn test_glob_filter_invalid(pattern: &str) {
+        assert!(GlobFilter::new(pattern, false).is_err());
+    }
+
+    #[test_case("*.txt", true)]
+    #[test_case("file?.txt", true)]
+    #[test_case("[ab].txt", true)]
+    #[test_case("{a,b}.txt", true)]
+    #[test_case("file.txt", false)]
+    fn test_is_glob_pattern(pattern: &str, expected: bool) {
+        assert_eq!(is_glob_pattern(pattern), expected);
+    }
+
+    #[test]
+    fn test_comparison_filter() {
+        assert!(ComparisonFilter::new(Comparison::Greater, 10).is_matched(11));
+        assert!(!ComparisonFilter::new(Comparison::Greater, 10).is_matched(10));
+        assert!(ComparisonFilter::new(Comparison::GreaterOrEqual, 10).is_matched(10));
+        assert!(ComparisonFilter::new(Comparison::Less, 10).is_matched(9));
+        assert!(!ComparisonFilter::new(Comparison::LessOrEqual, 10).is_matched(11));
+    }
+
+    #[test]
+    fn test_range_filter_inclusive() {
+        let filter = RangeFilter::new(Bound::Included(10), Bound::Included(20));
+        assert!(filter.is_matched(10));
+        assert!(filter.is_matched(20));
+        assert!(!filter.is_matched(9));
+        assert!(!filter.is_matched(21));
+    }
+
+    #[test]
+    fn test_range_filter_half_open() {
+        let filter = RangeFilter::new(Bound::Included(10), Bound::Excluded(20));
+        assert!(filter.is_matched(19));
+        assert!(!filter.is_matched(20));
+    }
+
+    #[test]
+    fn test_range_filter_unbounded() {
+        let filter = RangeFilter::new(Bound::Unbounded, Bound::Included(20));
+        assert!(filter.is_matched(0));
+        assert!(!filter.is_matched(21));
+
+        let filter = RangeFilter::new(Bound::Included(10), Bound::Unbounded);
+        assert!(filter.is_matched(u64::MAX));
+        assert!(!filter.is_matched(9));
+    }
+}
@@ -0,0 +1,341 @@
+//! Small filter expression language used by `fo find --where`.
+//!
+//! Example: `ext in ["jpg", "png"] and (size > 1M or modified >= "2025-01-01")`
+
+mod ast;
+mod compiler;
+mod lexer;
+mod parser;
+
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    cli::Cli,
+    commands::find::filtering::{FileMatcherTrait, filters::CaseSensitivity},
+};
+use compiler::Compiler;
+use parser::Parser;
+
+/// Byte range in the query source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Smallest span that covers both spans
+    pub fn join(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// Error produced by lexer, parser or type checker, pointing at the problematic part of query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl QueryError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Render error with the source line and carets under the span
+    pub fn render(&self, source: &str) -> String {
+        let padding = source[..self.span.start.min(source.len())].chars().count();
+        let width = source
+            .get(self.span.start..self.span.end)
+            .map(|s| s.chars().count())
+            .unwrap_or(0)
+            .max(1);
+
+        format!(
+            "{}\n  {}\n  {}{}",
+            self,
+            source,
+            " ".repeat(padding),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (at {}..{})",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+/// Parse, type check and compile query into a file matcher
+/// `path` field is matched relative to `root`
+pub fn compile_query(
+    source: &str,
+    case: CaseSensitivity,
+    root: &Path,
+    context: &Cli,
+) -> anyhow::Result<Box<dyn FileMatcherTrait>> {
+    let compile = || -> Result<Box<dyn FileMatcherTrait>, QueryError> {
+        let tokens = lexer::tokenize(source)?;
+        let expr = Parser::new(tokens).parse()?;
+        Compiler::new(case, root, context).compile(&expr)
+    };
+
+    compile().map_err(|err| anyhow::anyhow!("Invalid filter expression: {}", err.render(source)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::volumes::get_path_under_tests;
+
+    fn get_dir_entry_from_path(path_str: &str) -> std::fs::DirEntry {
+        let path = get_path_under_tests(path_str);
+        std::fs::read_dir(path).unwrap().next().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_render_query_error() {
+        let err = QueryError::new("unknown field", Span::new(4, 9));
+        assert_eq!(
+            err.render("ext colour"),
+            "unknown field (at 4..9)\n  ext colour\n      ^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_compile_query_text_and_size() {
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"ext in ["txt", "md"] and size >= 1K"#,
+            CaseSensitivity::Sensitive,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/size_1024"), &cli)
+                .unwrap()
+        );
+        assert!(
+            !matcher
+                .matches(&get_dir_entry_from_path("filtering/size_512"), &cli)
+                .unwrap()
+        );
+        assert!(
+            !matcher
+                .matches(&get_dir_entry_from_path("filtering/ext_rs"), &cli)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compile_query_matches_and_not() {
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"name matches "^\w+_\w+\.txt$" and not name = "matched.txt""#,
+            CaseSensitivity::Sensitive,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/not_matched"), &cli)
+                .unwrap()
+        );
+        assert!(
+            !matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compile_query_matches_keeps_regex_escapes() {
+        let temp_dir = std::env::temp_dir().join("fo_test_query_regex_escapes");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        for name in ["file1.txt", "filed.txt", "file1_txt"] {
+            std::fs::write(temp_dir.join(name), "").unwrap();
+        }
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"name matches "^file\d\.txt$""#,
+            CaseSensitivity::Sensitive,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+
+        let mut matched = std::fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|entry| matcher.matches(entry, &cli).unwrap())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        matched.sort();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(matched, vec!["file1.txt"]);
+    }
+
+    #[test]
+    fn test_compile_query_date_range() {
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"modified > "2000-01-01" and created < "9999-01-01""#,
+            CaseSensitivity::Sensitive,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compile_query_smart_case() {
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"name in ["MATCHED.TXT"] or name = "not_matched.TXT""#,
+            CaseSensitivity::Smart,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+        assert!(
+            !matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+
+        let matcher = compile_query(
+            r#"name = "matched.txt""#,
+            CaseSensitivity::Smart,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+
+        let matcher = compile_query(
+            r#"ext matches "^TXT$""#,
+            CaseSensitivity::Insensitive,
+            Path::new(""),
+            &cli,
+        )
+        .unwrap();
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compile_query_path_is_relative_to_root() {
+        let cli = Cli::default();
+        let matcher = compile_query(
+            r#"path = "matched/matched.txt""#,
+            CaseSensitivity::Sensitive,
+            &get_path_under_tests("filtering"),
+            &cli,
+        )
+        .unwrap();
+
+        assert!(
+            matcher
+                .matches(&get_dir_entry_from_path("filtering/matched"), &cli)
+                .unwrap()
+        );
+        assert!(
+            !matcher
+                .matches(&get_dir_entry_from_path("filtering/not_matched"), &cli)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compile_query_type_errors() {
+        let cli = Cli::default();
+        assert!(
+            compile_query(
+                r#"size matches "1""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+        assert!(
+            compile_query(
+                r#"name > "a""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+        assert!(
+            compile_query("ext = 10", CaseSensitivity::Sensitive, Path::new(""), &cli).is_err()
+        );
+        assert!(
+            compile_query(
+                r#"size = "10""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+        assert!(
+            compile_query(
+                r#"created = "last week""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+        assert!(
+            compile_query(
+                r#"type = "socket""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+        assert!(
+            compile_query(
+                r#"name matches "(""#,
+                CaseSensitivity::Sensitive,
+                Path::new(""),
+                &cli
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    commands::find::filtering::query::{Span, lexer::CompareOp},
+    timestamps::FileTimestamp,
+};
+
+/// File property that can be queried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Ext,
+    Path,
+    Type,
+    Size,
+    Created,
+    Modified,
+    Accessed,
+    Changed,
+}
+
+/// Type of values that a field produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Text,
+    Size,
+    Date,
+}
+
+impl Field {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Field::Name),
+            "ext" => Some(Field::Ext),
+            "path" => Some(Field::Path),
+            "type" => Some(Field::Type),
+            "size" => Some(Field::Size),
+            "created" => Some(Field::Created),
+            "modified" => Some(Field::Modified),
+            "accessed" => Some(Field::Accessed),
+            "changed" => Some(Field::Changed),
+            _ => None,
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Field::Name | Field::Ext | Field::Path | Field::Type => ValueType::Text,
+            Field::Size => ValueType::Size,
+            Field::Created | Field::Modified | Field::Accessed | Field::Changed => ValueType::Date,
+        }
+    }
+
+    /// Timestamp that date field reads
+    pub fn timestamp(&self) -> Option<FileTimestamp> {
+        match self {
+            Field::Created => Some(FileTimestamp::Created),
+            Field::Modified => Some(FileTimestamp::Modified),
+            Field::Accessed => Some(FileTimestamp::Accessed),
+            Field::Changed => Some(FileTimestamp::Changed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralKind {
+    Str(String),
+    Number(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Literal {
+    pub kind: LiteralKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: Field,
+        field_span: Span,
+        op: CompareOp,
+        value: Literal,
+    },
+    Matches {
+        field: Field,
+        field_span: Span,
+        pattern: Literal,
+    },
+    In {
+        field: Field,
+        field_span: Span,
+        values: Vec<Literal>,
+    },
+}
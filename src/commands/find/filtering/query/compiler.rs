@@ -0,0 +1,306 @@
+use std::{ops::Bound, path::Path};
+
+use time::PrimitiveDateTime;
+
+use crate::{
+    cli::Cli,
+    commands::find::filtering::{
+        AllOf, AnyOf, FileExtensionExtractor, FileMatcher, FileMatcherTrait, FileNameExtractor,
+        FilePathExtractor, FileSizeExtractor, FileTypeExtractor, Not, date_matcher,
+        dates::parse_date_interval,
+        filters::{
+            CaseSensitivity, Comparison, ComparisonFilter, ExactMatchFilter, Filter,
+            LowercaseFilter, OneOfFilter, RangeFilter, RegexFilter,
+        },
+        now,
+        query::{
+            QueryError, Span,
+            ast::{Expr, Field, Literal, LiteralKind, ValueType},
+            lexer::CompareOp,
+        },
+    },
+    size::parse_size,
+    timestamps::FileTimestamp,
+};
+
+const FILE_TYPES: &[&str] = &["file", "dir", "symlink"];
+
+/// Type check expression and build matcher tree from it
+pub struct Compiler<'a> {
+    /// Sensitivity of text comparisons
+    case: CaseSensitivity,
+    /// Search root that `path` field is relative to
+    root: &'a Path,
+    context: &'a Cli,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn new(case: CaseSensitivity, root: &'a Path, context: &'a Cli) -> Self {
+        Self {
+            case,
+            root,
+            context,
+        }
+    }
+
+    pub fn compile(&self, expr: &Expr) -> Result<Box<dyn FileMatcherTrait>, QueryError> {
+        match expr {
+            Expr::And(left, right) => Ok(Box::new(AllOf::new(vec![
+                self.compile(left)?,
+                self.compile(right)?,
+            ]))),
+            Expr::Or(left, right) => Ok(Box::new(AnyOf::new(vec![
+                self.compile(left)?,
+                self.compile(right)?,
+            ]))),
+            Expr::Not(inner) => Ok(Box::new(Not::new(self.compile(inner)?))),
+            Expr::Compare {
+                field,
+                field_span,
+                op,
+                value,
+            } => self.compile_compare(*field, *field_span, *op, value),
+            Expr::Matches {
+                field,
+                field_span,
+                pattern,
+            } => self.compile_matches(*field, *field_span, pattern),
+            Expr::In { field, values, .. } => self.compile_in(*field, values),
+        }
+    }
+
+    fn compile_compare(
+        &self,
+        field: Field,
+        field_span: Span,
+        op: CompareOp,
+        value: &Literal,
+    ) -> Result<Box<dyn FileMatcherTrait>, QueryError> {
+        match field.value_type() {
+            ValueType::Text => {
+                let text = expect_text(field, value)?;
+                let filter: Box<dyn Filter<String>> = if self.case.ignores_case(&text) {
+                    Box::new(LowercaseFilter::new(Box::new(ExactMatchFilter::new(
+                        text.to_lowercase(),
+                    ))))
+                } else {
+                    Box::new(ExactMatchFilter::new(text))
+                };
+                let equals = self.text_matcher(field, filter);
+                match op {
+                    CompareOp::Eq => Ok(equals),
+                    CompareOp::NotEq => Ok(Box::new(Not::new(equals))),
+                    _ => Err(QueryError::new(
+                        format!(
+                            "operator '{}' is not supported for text fields, use '=', '!=', 'matches' or 'in'",
+                            op.as_str()
+                        ),
+                        field_span,
+                    )),
+                }
+            }
+            ValueType::Size => {
+                let size = expect_size(value)?;
+                let matcher: Box<dyn FileMatcherTrait> = match to_comparison(op) {
+                    Some(comparison) => Box::new(FileMatcher::new(
+                        Box::new(ComparisonFilter::new(comparison, size)),
+                        FileSizeExtractor,
+                    )),
+                    None => {
+                        let equals =
+                            Box::new(FileMatcher::with_exact_match(size, FileSizeExtractor));
+                        if op == CompareOp::Eq {
+                            equals
+                        } else {
+                            Box::new(Not::new(equals))
+                        }
+                    }
+                };
+                Ok(matcher)
+            }
+            ValueType::Date => {
+                let (start, end) = expect_date(value, self.context)?;
+                let matcher = match op {
+                    CompareOp::Eq => date_within(field, start, end),
+                    CompareOp::NotEq => Box::new(Not::new(date_within(field, start, end))),
+                    CompareOp::Less => date_comparison(field, Comparison::Less, start),
+                    CompareOp::LessOrEqual => date_comparison(field, Comparison::Less, end),
+                    CompareOp::Greater => date_comparison(field, Comparison::GreaterOrEqual, end),
+                    CompareOp::GreaterOrEqual => {
+                        date_comparison(field, Comparison::GreaterOrEqual, start)
+                    }
+                };
+                Ok(matcher)
+            }
+        }
+    }
+
+    fn compile_matches(
+        &self,
+        field: Field,
+        field_span: Span,
+        pattern: &Literal,
+    ) -> Result<Box<dyn FileMatcherTrait>, QueryError> {
+        if field.value_type() != ValueType::Text {
+            return Err(QueryError::new(
+                "'matches' can only be used with text fields: name, ext, path, type",
+                field_span,
+            ));
+        }
+
+        let LiteralKind::Str(pattern_str) = &pattern.kind else {
+            return Err(QueryError::new(
+                "expected a quoted regex pattern",
+                pattern.span,
+            ));
+        };
+
+        let filter = RegexFilter::new(pattern_str, self.case.ignores_case(pattern_str))
+            .map_err(|err| QueryError::new(format!("invalid regex: {err}"), pattern.span))?;
+        Ok(self.text_matcher(field, Box::new(filter)))
+    }
+
+    fn compile_in(
+        &self,
+        field: Field,
+        values: &[Literal],
+    ) -> Result<Box<dyn FileMatcherTrait>, QueryError> {
+        match field.value_type() {
+            ValueType::Text => {
+                let texts = values
+                    .iter()
+                    .map(|value| expect_text(field, value))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if texts.iter().all(|text| self.case.ignores_case(text)) {
+                    let texts = texts.iter().map(|text| text.to_lowercase()).collect();
+                    let filter = LowercaseFilter::new(Box::new(OneOfFilter::new(texts)));
+                    Ok(self.text_matcher(field, Box::new(filter)))
+                } else {
+                    Ok(self.text_matcher(field, Box::new(OneOfFilter::new(texts))))
+                }
+            }
+            ValueType::Size => {
+                let sizes = values
+                    .iter()
+                    .map(expect_size)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Box::new(FileMatcher::new(
+                    Box::new(OneOfFilter::new(sizes)),
+                    FileSizeExtractor,
+                )))
+            }
+            ValueType::Date => {
+                let matchers = values
+                    .iter()
+                    .map(|value| {
+                        let (start, end) = expect_date(value, self.context)?;
+                        Ok(date_within(field, start, end))
+                    })
+                    .collect::<Result<Vec<_>, QueryError>>()?;
+                Ok(Box::new(AnyOf::new(matchers)))
+            }
+        }
+    }
+
+    fn text_matcher(
+        &self,
+        field: Field,
+        filter: Box<dyn Filter<String>>,
+    ) -> Box<dyn FileMatcherTrait> {
+        match field {
+            Field::Name => Box::new(FileMatcher::new(filter, FileNameExtractor)),
+            Field::Ext => Box::new(FileMatcher::new(filter, FileExtensionExtractor)),
+            Field::Path => Box::new(FileMatcher::new(filter, FilePathExtractor::new(self.root))),
+            Field::Type => Box::new(FileMatcher::new(filter, FileTypeExtractor)),
+            _ => unreachable!("Only text fields are passed here"),
+        }
+    }
+}
+
+fn date_comparison(
+    field: Field,
+    comparison: Comparison,
+    value: PrimitiveDateTime,
+) -> Box<dyn FileMatcherTrait> {
+    date_matcher(
+        date_timestamp(field),
+        Box::new(ComparisonFilter::new(comparison, value)),
+    )
+}
+
+/// Matches dates in half-open interval [start, end)
+fn date_within(
+    field: Field,
+    start: PrimitiveDateTime,
+    end: PrimitiveDateTime,
+) -> Box<dyn FileMatcherTrait> {
+    date_matcher(
+        date_timestamp(field),
+        Box::new(RangeFilter::new(
+            Bound::Included(start),
+            Bound::Excluded(end),
+        )),
+    )
+}
+
+fn date_timestamp(field: Field) -> FileTimestamp {
+    field.timestamp().expect("Only date fields are passed here")
+}
+
+fn to_comparison(op: CompareOp) -> Option<Comparison> {
+    match op {
+        CompareOp::Less => Some(Comparison::Less),
+        CompareOp::LessOrEqual => Some(Comparison::LessOrEqual),
+        CompareOp::Greater => Some(Comparison::Greater),
+        CompareOp::GreaterOrEqual => Some(Comparison::GreaterOrEqual),
+        CompareOp::Eq | CompareOp::NotEq => None,
+    }
+}
+
+fn expect_text(field: Field, value: &Literal) -> Result<String, QueryError> {
+    let LiteralKind::Str(text) = &value.kind else {
+        return Err(QueryError::new(
+            "expected a quoted string for text field",
+            value.span,
+        ));
+    };
+
+    if field == Field::Type && !FILE_TYPES.contains(&text.as_str()) {
+        return Err(QueryError::new(
+            format!("unknown file type '{text}', expected one of: file, dir, symlink"),
+            value.span,
+        ));
+    }
+
+    Ok(text.clone())
+}
+
+/// Parse size literal like `512`, `10K` or `1.5MB`
+fn expect_size(value: &Literal) -> Result<u64, QueryError> {
+    let LiteralKind::Number(raw) = &value.kind else {
+        return Err(QueryError::new(
+            "expected a size, e.g. 512, 10K or 1M",
+            value.span,
+        ));
+    };
+
+    parse_size(raw).map_err(|err| QueryError::new(err.to_string(), value.span))
+}
+
+/// Parse date literal into the interval it covers, e.g. a whole day for `"2025-06-14"`
+fn expect_date(
+    value: &Literal,
+    context: &Cli,
+) -> Result<(PrimitiveDateTime, PrimitiveDateTime), QueryError> {
+    let LiteralKind::Str(text) = &value.kind else {
+        return Err(QueryError::new(
+            "expected a quoted date, e.g. \"2025-06-14\", \"2025-06\" or \"yesterday\"",
+            value.span,
+        ));
+    };
+
+    parse_date_interval(text, context, now(context))
+        .map(|interval| (interval.start, interval.end))
+        .map_err(|err| QueryError::new(err.to_string(), value.span))
+}
@@ -0,0 +1,274 @@
+use crate::commands::find::filtering::query::{QueryError, Span};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Str(String),
+    /// Number with optional unit suffix, e.g. `10`, `1M`, `512KiB`
+    Number(String),
+    Op(CompareOp),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl CompareOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "!=",
+            CompareOp::Less => "<",
+            CompareOp::LessOrEqual => "<=",
+            CompareOp::Greater => ">",
+            CompareOp::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Split query into tokens. Spans are byte offsets into the source.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let single = |kind: TokenKind| Token {
+            kind,
+            span: Span::new(start, start + ch.len_utf8()),
+        };
+
+        match ch {
+            '(' => {
+                chars.next();
+                tokens.push(single(TokenKind::LParen));
+            }
+            ')' => {
+                chars.next();
+                tokens.push(single(TokenKind::RParen));
+            }
+            '[' => {
+                chars.next();
+                tokens.push(single(TokenKind::LBracket));
+            }
+            ']' => {
+                chars.next();
+                tokens.push(single(TokenKind::RBracket));
+            }
+            ',' => {
+                chars.next();
+                tokens.push(single(TokenKind::Comma));
+            }
+            '=' => {
+                chars.next();
+                // Accept `==` as an alias for `=`
+                let end = if chars.next_if(|&(_, c)| c == '=').is_some() {
+                    start + 2
+                } else {
+                    start + 1
+                };
+                tokens.push(Token {
+                    kind: TokenKind::Op(CompareOp::Eq),
+                    span: Span::new(start, end),
+                });
+            }
+            '!' => {
+                chars.next();
+                if chars.next_if(|&(_, c)| c == '=').is_none() {
+                    return Err(QueryError::new(
+                        "expected '=' after '!'",
+                        Span::new(start, start + 1),
+                    ));
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Op(CompareOp::NotEq),
+                    span: Span::new(start, start + 2),
+                });
+            }
+            '<' | '>' => {
+                chars.next();
+                let with_eq = chars.next_if(|&(_, c)| c == '=').is_some();
+                let op = match (ch, with_eq) {
+                    ('<', false) => CompareOp::Less,
+                    ('<', true) => CompareOp::LessOrEqual,
+                    ('>', false) => CompareOp::Greater,
+                    _ => CompareOp::GreaterOrEqual,
+                };
+                let end = if with_eq { start + 2 } else { start + 1 };
+                tokens.push(Token {
+                    kind: TokenKind::Op(op),
+                    span: Span::new(start, end),
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let quote = ch;
+                let mut value = String::new();
+                let mut end = None;
+                while let Some((index, c)) = chars.next() {
+                    match c {
+                        // Only quotes and backslash are unescaped, so regex patterns like `\d` reach `matches` intact
+                        '\\' => match chars.next() {
+                            Some((_, escaped)) if matches!(escaped, '"' | '\'' | '\\') => {
+                                value.push(escaped)
+                            }
+                            Some((_, escaped)) => {
+                                value.push('\\');
+                                value.push(escaped);
+                            }
+                            None => break,
+                        },
+                        c if c == quote => {
+                            end = Some(index + 1);
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                let Some(end) = end else {
+                    return Err(QueryError::new(
+                        "unterminated string literal",
+                        Span::new(start, source.len()),
+                    ));
+                };
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    span: Span::new(start, end),
+                });
+            }
+            c if c.is_ascii_digit() => {
+                let end = consume_while(&mut chars, source.len(), |c| {
+                    c.is_ascii_alphanumeric() || c == '.' || c == '_'
+                });
+                tokens.push(Token {
+                    kind: TokenKind::Number(source[start..end].to_string()),
+                    span: Span::new(start, end),
+                });
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let end = consume_while(&mut chars, source.len(), |c| {
+                    c.is_alphanumeric() || c == '_'
+                });
+                tokens.push(Token {
+                    kind: TokenKind::Ident(source[start..end].to_string()),
+                    span: Span::new(start, end),
+                });
+            }
+            c => {
+                return Err(QueryError::new(
+                    format!("unexpected character '{c}'"),
+                    Span::new(start, start + c.len_utf8()),
+                ));
+            }
+        }
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(source.len(), source.len()),
+    });
+
+    Ok(tokens)
+}
+
+/// Consume characters while predicate holds, returning the end offset
+fn consume_while(
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    source_len: usize,
+    predicate: impl Fn(char) -> bool,
+) -> usize {
+    while chars.next_if(|&(_, c)| predicate(c)).is_some() {}
+    chars.peek().map(|&(index, _)| index).unwrap_or(source_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_comparison() {
+        assert_eq!(
+            kinds(r#"ext = "rs" and size >= 1M"#),
+            vec![
+                TokenKind::Ident("ext".to_string()),
+                TokenKind::Op(CompareOp::Eq),
+                TokenKind::Str("rs".to_string()),
+                TokenKind::Ident("and".to_string()),
+                TokenKind::Ident("size".to_string()),
+                TokenKind::Op(CompareOp::GreaterOrEqual),
+                TokenKind::Number("1M".to_string()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_list_and_escapes() {
+        assert_eq!(
+            kinds(r#"name in ['a\'b', "c"]"#),
+            vec![
+                TokenKind::Ident("name".to_string()),
+                TokenKind::Ident("in".to_string()),
+                TokenKind::LBracket,
+                TokenKind::Str("a'b".to_string()),
+                TokenKind::Comma,
+                TokenKind::Str("c".to_string()),
+                TokenKind::RBracket,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_keeps_regex_escapes() {
+        assert_eq!(
+            kinds(r#""\d\.txt \"a\" \\""#),
+            vec![
+                TokenKind::Str(r#"\d\.txt "a" \"#.to_string()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_string() {
+        let err = tokenize(r#"name = "abc"#).unwrap_err();
+        assert_eq!(err.span, Span::new(7, 11));
+    }
+
+    #[test]
+    fn test_tokenize_unexpected_character() {
+        let err = tokenize("size ~ 10").unwrap_err();
+        assert_eq!(err.span, Span::new(5, 6));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::commands::find::filtering::query::{
+    QueryError,
+    ast::{Expr, Field, Literal, LiteralKind},
+    lexer::{Token, TokenKind},
+};
+
+/// Recursive descent parser for filter expressions.
+///
+/// Grammar:
+/// ```text
+/// expr       := and_expr ("or" and_expr)*
+/// and_expr   := unary ("and" unary)*
+/// unary      := "not" unary | "(" expr ")" | comparison
+/// comparison := field op value
+///             | field "matches" string
+///             | field "not"? "in" "[" value ("," value)* "]"
+/// ```
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Expr, QueryError> {
+        let expr = self.parse_or()?;
+        let token = self.peek();
+        if token.kind != TokenKind::Eof {
+            return Err(QueryError::new(
+                "expected 'and', 'or' or end of expression",
+                token.span,
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_unary()?;
+        while self.eat_keyword("and") {
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        if self.eat_keyword("not") {
+            let inner = self.parse_unary()?;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+
+        if self.peek().kind == TokenKind::LParen {
+            let open = self.advance();
+            let inner = self.parse_or()?;
+            let token = self.peek();
+            if token.kind != TokenKind::RParen {
+                return Err(QueryError::new(
+                    "expected ')' to close this group",
+                    open.span.join(token.span),
+                ));
+            }
+            self.advance();
+            return Ok(inner);
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, QueryError> {
+        let token = self.advance();
+        let (field, field_span) = match &token.kind {
+            TokenKind::Ident(name) => match Field::from_name(name) {
+                Some(field) => (field, token.span),
+                None => {
+                    return Err(QueryError::new(
+                        format!(
+                            "unknown field '{name}', expected one of: name, ext, path, type, size, created, modified, accessed, changed"
+                        ),
+                        token.span,
+                    ));
+                }
+            },
+            _ => return Err(QueryError::new("expected a field name", token.span)),
+        };
+
+        let token = self.advance();
+        match &token.kind {
+            TokenKind::Op(op) => {
+                let value = self.parse_literal()?;
+                Ok(Expr::Compare {
+                    field,
+                    field_span,
+                    op: *op,
+                    value,
+                })
+            }
+            TokenKind::Ident(keyword) if keyword == "matches" => {
+                let pattern = self.parse_literal()?;
+                Ok(Expr::Matches {
+                    field,
+                    field_span,
+                    pattern,
+                })
+            }
+            TokenKind::Ident(keyword) if keyword == "in" => {
+                let values = self.parse_list()?;
+                Ok(Expr::In {
+                    field,
+                    field_span,
+                    values,
+                })
+            }
+            TokenKind::Ident(keyword) if keyword == "not" && self.eat_keyword("in") => {
+                let values = self.parse_list()?;
+                Ok(Expr::Not(Box::new(Expr::In {
+                    field,
+                    field_span,
+                    values,
+                })))
+            }
+            _ => Err(QueryError::new(
+                "expected an operator: =, !=, <, <=, >, >=, matches, in",
+                token.span,
+            )),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Literal>, QueryError> {
+        let open = self.advance();
+        if open.kind != TokenKind::LBracket {
+            return Err(QueryError::new("expected '[' to start a list", open.span));
+        }
+
+        let mut values = vec![self.parse_literal()?];
+        loop {
+            let token = self.advance();
+            match token.kind {
+                TokenKind::Comma => values.push(self.parse_literal()?),
+                TokenKind::RBracket => break,
+                _ => {
+                    return Err(QueryError::new("expected ',' or ']' in list", token.span));
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, QueryError> {
+        let token = self.advance();
+        let kind = match token.kind {
+            TokenKind::Str(value) => LiteralKind::Str(value),
+            TokenKind::Number(value) => LiteralKind::Number(value),
+            _ => {
+                return Err(QueryError::new(
+                    "expected a value (quoted string or number)",
+                    token.span,
+                ));
+            }
+        };
+        Ok(Literal {
+            kind,
+            span: token.span,
+        })
+    }
+
+    fn peek(&self) -> Token {
+        self.tokens[self.position].clone()
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek();
+        // Never step past Eof, so errors always point at the end of input
+        if token.kind != TokenKind::Eof {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match &self.peek().kind {
+            TokenKind::Ident(ident) if ident == keyword => {
+                self.advance();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::find::filtering::query::{
+        Span,
+        lexer::{CompareOp, tokenize},
+    };
+
+    fn parse(source: &str) -> Result<Expr, QueryError> {
+        Parser::new(tokenize(source).unwrap()).parse()
+    }
+
+    #[test]
+    fn test_parse_and_binds_tighter_than_or() {
+        let expr = parse(r#"ext = "rs" or ext = "py" and size > 1"#).unwrap();
+        match expr {
+            Expr::Or(left, right) => {
+                assert!(matches!(
+                    *left,
+                    Expr::Compare {
+                        field: Field::Ext,
+                        ..
+                    }
+                ));
+                assert!(matches!(*right, Expr::And(_, _)));
+            }
+            other => panic!("Unexpected expression: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_parentheses_and_not() {
+        let expr = parse(r#"not (name matches "^a" or size <= 10K)"#).unwrap();
+        match expr {
+            Expr::Not(inner) => assert!(matches!(*inner, Expr::Or(_, _))),
+            other => panic!("Unexpected expression: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_in_list() {
+        let expr = parse(r#"ext not in ["rs", "toml"]"#).unwrap();
+        match expr {
+            Expr::Not(inner) => match *inner {
+                Expr::In { values, .. } => assert_eq!(values.len(), 2),
+                other => panic!("Unexpected expression: {other:?}"),
+            },
+            other => panic!("Unexpected expression: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_comparison_operator() {
+        let expr = parse("size != 0").unwrap();
+        assert!(matches!(
+            expr,
+            Expr::Compare {
+                op: CompareOp::NotEq,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_unknown_field() {
+        let err = parse(r#"color = "red""#).unwrap_err();
+        assert_eq!(err.span, Span::new(0, 5));
+    }
+
+    #[test]
+    fn test_parse_missing_value() {
+        let err = parse("size >").unwrap_err();
+        assert_eq!(err.span, Span::new(6, 6));
+    }
+
+    #[test]
+    fn test_parse_unclosed_group() {
+        let err = parse(r#"(ext = "rs""#).unwrap_err();
+        assert_eq!(err.span, Span::new(0, 11));
+    }
+}
@@ -1,38 +1,100 @@
-use log::debug;
+use anstream::println;
+use clap::ValueEnum;
+use log::{debug, warn};
 use owo_colors::OwoColorize;
-use std::fs::DirEntry;
-use time::OffsetDateTime;
+use std::{fmt::Display, fs::DirEntry, path::Path};
 
-use crate::cli::Cli;
+use crate::{cli::Cli, commands::find::MatchedLine, mime::MimeType, timestamps::FileTimestamp};
 
+/// Column that can be shown for every matched file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Path,
+    Size,
+    Created,
+    Modified,
+    Accessed,
+    Changed,
+    /// MIME type detected from content, flagged when extension is of another type
+    Type,
+}
+
+pub const DEFAULT_COLUMNS: &[Column] = &[Column::Path, Column::Size, Column::Created];
+
+impl Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Column::Path => write!(f, "path"),
+            Column::Size => write!(f, "size"),
+            Column::Created => write!(f, "created"),
+            Column::Modified => write!(f, "modified"),
+            Column::Accessed => write!(f, "accessed"),
+            Column::Changed => write!(f, "changed"),
+            Column::Type => write!(f, "type"),
+        }
+    }
+}
+
+/// Longer matched lines are cut to keep output readable
+const MAX_LINE_WIDTH: usize = 200;
+
+/// `matched_lines` is either empty or has matched lines for every file
 pub fn print_files(
     context: &Cli,
     files: &[DirEntry],
+    matched_lines: &[Vec<MatchedLine>],
+    columns: &[Column],
     total_files: i32,
     total_matched_files: i32,
 ) -> anyhow::Result<()> {
     debug!("Printing matched files");
+    let columns = if columns.is_empty() {
+        DEFAULT_COLUMNS
+    } else {
+        columns
+    };
+
     if total_matched_files != 0 {
         println!("{}", "Matched files:".bold().bright_green());
 
-        for file in files {
+        for (index, file) in files.iter().enumerate() {
             let metadata = file.metadata()?;
-            let file_size = metadata.len();
-            let created_time = OffsetDateTime::from(metadata.created()?);
-
-            let path = file.path().to_string_lossy().to_string();
-            let size_str = file_size.to_string();
-            let date_str = created_time.format(&context.datetime_format)?.to_string();
-
-            println!(
-                "{:<path_width$} {:>size_width$} {} {}",
-                path.bright_cyan(),
-                size_str.bright_yellow(),
-                "bytes".bright_cyan(),
-                date_str.bright_purple(),
-                path_width = 100,
-                size_width = 10
-            );
+            let mut line = Vec::with_capacity(columns.len());
+
+            for column in columns {
+                let cell = match column {
+                    Column::Path => {
+                        let path = file.path().to_string_lossy().to_string();
+                        format!("{:<path_width$}", path.bright_cyan(), path_width = 100)
+                    }
+                    Column::Size => {
+                        let size_str = metadata.len().to_string();
+                        format!(
+                            "{:>size_width$} {}",
+                            size_str.bright_yellow(),
+                            "bytes".bright_cyan(),
+                            size_width = 10
+                        )
+                    }
+                    Column::Created => format_time(context, &metadata, FileTimestamp::Created)?,
+                    Column::Modified => format_time(context, &metadata, FileTimestamp::Modified)?,
+                    Column::Accessed => format_time(context, &metadata, FileTimestamp::Accessed)?,
+                    Column::Changed => format_time(context, &metadata, FileTimestamp::Changed)?,
+                    Column::Type => format_type(&file.path()),
+                };
+                line.push(cell);
+            }
+
+            println!("{}", line.join(" "));
+
+            for matched_line in matched_lines.get(index).into_iter().flatten() {
+                let text: String = matched_line.text.chars().take(MAX_LINE_WIDTH).collect();
+                println!(
+                    "  {:>6}: {}",
+                    matched_line.number.bright_yellow(),
+                    text.trim()
+                );
+            }
         }
     }
 
@@ -50,3 +112,29 @@ pub fn print_files(
 
     Ok(())
 }
+
+/// Type of unreadable file is printed as `unknown`, so one file doesn't stop the output
+fn format_type(path: &Path) -> String {
+    let mime = match MimeType::detect(path) {
+        Ok(mime) => mime,
+        Err(err) => {
+            warn!("Failed to read {}: {}", path.display(), err);
+            return format!("{:<24}", "unknown".bright_blue());
+        }
+    };
+    let mismatch = match mime.contradicted_extension(path) {
+        Some(extension) => format!(" (not .{extension})").bright_red().to_string(),
+        None => String::new(),
+    };
+    format!("{:<24}{mismatch}", mime.name.bright_blue())
+}
+
+fn format_time(
+    context: &Cli,
+    metadata: &std::fs::Metadata,
+    timestamp: FileTimestamp,
+) -> anyhow::Result<String> {
+    let time = context.local_time(timestamp.read(metadata, context.time_source)?);
+    let date_str = time.format(&context.datetime_format)?;
+    Ok(date_str.bright_purple().to_string())
+}
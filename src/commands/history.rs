@@ -0,0 +1,74 @@
+use std::fmt::Display;
+
+use anstream::println;
+use clap::Parser;
+use log::{debug, trace};
+use owo_colors::OwoColorize;
+
+use crate::{cli::Cli, journal::Journal};
+
+#[derive(Parser, Debug)]
+pub struct HistoryCommand {
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 20,
+        help = "How many of the latest runs to show"
+    )]
+    limit: usize,
+}
+
+impl HistoryCommand {
+    pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
+        debug!("Executing 'history' command");
+        trace!("with configuration: {self}");
+
+        let runs = Journal::open()?.runs()?;
+        if runs.is_empty() {
+            println!("{}", "No runs recorded yet".bright_green());
+            return Ok(());
+        }
+
+        for run in runs.iter().rev().take(self.limit) {
+            let undone = run.undoes.clone().or_else(|| {
+                runs.iter()
+                    .find(|other| other.undone_by.as_deref() == Some(run.id.as_str()))
+                    .map(|undone| undone.id.clone())
+            });
+            let status = match (&run.undone_by, undone) {
+                (Some(undo_run_id), _) => {
+                    format!("undone by {undo_run_id}").bright_red().to_string()
+                }
+                (None, Some(undone)) => format!("undo of {undone}").bright_yellow().to_string(),
+                (None, None) => String::new(),
+            };
+
+            println!(
+                "{} {} {:>5} op(s) {} {}",
+                run.id.bright_cyan(),
+                run.started_at
+                    .to_offset(context.local_offset)
+                    .format(&context.datetime_format)?
+                    .bright_purple(),
+                run.operations.len(),
+                run.command_line,
+                status
+            );
+        }
+
+        println!();
+        println!(
+            "{} {}",
+            "Total runs:".bright_green(),
+            runs.len().to_string().bright_purple()
+        );
+        Ok(())
+    }
+}
+
+impl Display for HistoryCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", "limit".bright_cyan(), self.limit)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,642 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anstream::println;
+use anyhow::Context;
+use clap::ValueEnum;
+use log::{info, trace, warn};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    journal::{RunRecorder, absolute},
+    trash::{self, Trash},
+};
+
+use super::clean::format_bytes;
+
+/// Single file system change. Commands first build a list of operations and only then execute it,
+/// so `--dry-run` prints exactly what the real run would do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Operation {
+    CreateDir {
+        path: PathBuf,
+    },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+        size: u64,
+    },
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+        size: u64,
+    },
+    /// Delete file, or directory with all its contents
+    Delete {
+        path: PathBuf,
+        size: u64,
+        is_dir: bool,
+    },
+    /// Remove source directory emptied by previous moves
+    RemoveDir {
+        path: PathBuf,
+    },
+    /// Move file or directory into trash of its volume
+    Trash {
+        path: PathBuf,
+        size: u64,
+        is_dir: bool,
+        /// Where it is stored in trash, known only after execution
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trashed: Option<PathBuf>,
+    },
+    /// Move trashed file or directory back to its original location
+    Restore {
+        from: PathBuf,
+        to: PathBuf,
+        size: u64,
+    },
+    /// Replace file with a hardlink to another file with the same content
+    Hardlink {
+        original: PathBuf,
+        path: PathBuf,
+        size: u64,
+    },
+}
+
+impl Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::CreateDir { .. } => "create",
+            Operation::Move { .. } => "move",
+            Operation::Copy { .. } => "copy",
+            Operation::Delete { .. } => "delete",
+            Operation::RemoveDir { .. } => "rmdir",
+            Operation::Trash { .. } => "trash",
+            Operation::Restore { .. } => "restore",
+            Operation::Hardlink { .. } => "link",
+        }
+    }
+
+    /// Path that is created or deleted by operation
+    pub(super) fn target(&self) -> &Path {
+        match self {
+            Operation::CreateDir { path }
+            | Operation::Delete { path, .. }
+            | Operation::RemoveDir { path }
+            | Operation::Trash { path, .. }
+            | Operation::Hardlink { path, .. } => path,
+            Operation::Move { to, .. }
+            | Operation::Copy { to, .. }
+            | Operation::Restore { to, .. } => to,
+        }
+    }
+
+    fn source(&self) -> Option<&Path> {
+        match self {
+            Operation::Move { from, .. }
+            | Operation::Copy { from, .. }
+            | Operation::Restore { from, .. } => Some(from),
+            Operation::Hardlink { original, .. } => Some(original),
+            _ => None,
+        }
+    }
+
+    pub(super) fn size(&self) -> Option<u64> {
+        match self {
+            Operation::Move { size, .. }
+            | Operation::Copy { size, .. }
+            | Operation::Delete { size, .. }
+            | Operation::Trash { size, .. }
+            | Operation::Restore { size, .. }
+            | Operation::Hardlink { size, .. } => Some(*size),
+            _ => None,
+        }
+    }
+
+    pub(super) fn colored_name(&self) -> String {
+        let name = format!("{:<7}", self.name());
+        match self {
+            Operation::CreateDir { .. } => name.bright_green().to_string(),
+            Operation::Move { .. }
+            | Operation::Copy { .. }
+            | Operation::Restore { .. }
+            | Operation::Hardlink { .. } => name.bright_yellow().to_string(),
+            Operation::Delete { .. } | Operation::RemoveDir { .. } | Operation::Trash { .. } => {
+                name.bright_red().to_string()
+            }
+        }
+    }
+
+    /// Execute operation, returns it as it should be recorded into journal
+    fn execute(&self) -> std::io::Result<Operation> {
+        if let Operation::Trash {
+            path, size, is_dir, ..
+        } = self
+        {
+            let trashed = Trash::for_path(path)?.put(path)?;
+            return Ok(Operation::Trash {
+                path: path.clone(),
+                size: *size,
+                is_dir: *is_dir,
+                trashed: Some(trashed),
+            });
+        }
+
+        match self {
+            Operation::CreateDir { path } => fs::create_dir(path),
+            Operation::Move { from, to, .. } => fs::rename(from, to),
+            Operation::Copy { from, to, .. } => fs::copy(from, to).map(|_| ()),
+            Operation::Delete { path, is_dir, .. } => {
+                if *is_dir {
+                    fs::remove_dir_all(path)
+                } else {
+                    fs::remove_file(path)
+                }
+            }
+            Operation::RemoveDir { path } => fs::remove_dir(path),
+            Operation::Restore { from, to, .. } => trash::restore(from, to),
+            Operation::Hardlink { original, path, .. } => replace_with_hardlink(original, path),
+            Operation::Trash { .. } => unreachable!("Trash is executed above"),
+        }
+        .map(|_| self.clone())
+    }
+
+    /// Same operation with absolute paths, as stored in journal
+    pub fn to_absolute(&self) -> anyhow::Result<Operation> {
+        Ok(match self {
+            Operation::CreateDir { path } => Operation::CreateDir {
+                path: absolute(path)?,
+            },
+            Operation::Move { from, to, size } => Operation::Move {
+                from: absolute(from)?,
+                to: absolute(to)?,
+                size: *size,
+            },
+            Operation::Copy { from, to, size } => Operation::Copy {
+                from: absolute(from)?,
+                to: absolute(to)?,
+                size: *size,
+            },
+            Operation::Delete { path, size, is_dir } => Operation::Delete {
+                path: absolute(path)?,
+                size: *size,
+                is_dir: *is_dir,
+            },
+            Operation::RemoveDir { path } => Operation::RemoveDir {
+                path: absolute(path)?,
+            },
+            Operation::Trash {
+                path,
+                size,
+                is_dir,
+                trashed,
+            } => Operation::Trash {
+                path: absolute(path)?,
+                size: *size,
+                is_dir: *is_dir,
+                trashed: trashed.as_deref().map(absolute).transpose()?,
+            },
+            Operation::Restore { from, to, size } => Operation::Restore {
+                from: absolute(from)?,
+                to: absolute(to)?,
+                size: *size,
+            },
+            Operation::Hardlink {
+                original,
+                path,
+                size,
+            } => Operation::Hardlink {
+                original: absolute(original)?,
+                path: absolute(path)?,
+                size: *size,
+            },
+        })
+    }
+}
+
+/// Atomically replace `duplicate` with a hardlink to `original`
+fn replace_with_hardlink(original: &Path, duplicate: &Path) -> std::io::Result<()> {
+    let file_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = duplicate.with_file_name(format!(".{file_name}.fo-link"));
+
+    fs::hard_link(original, &temp_path)?;
+    if let Err(err) = fs::rename(&temp_path, duplicate) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// How plan is printed by `--dry-run`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlanFormat {
+    /// Destination tree with operations next to every path
+    #[default]
+    Tree,
+    /// One operation per line
+    Table,
+}
+
+/// What was done by executed plan
+#[derive(Debug, Default, PartialEq)]
+pub struct PlanSummary {
+    pub created_dirs: u64,
+    pub transferred_files: u64,
+    pub deleted_files: u64,
+    pub deleted_dirs: u64,
+    pub trashed_files: u64,
+    pub trashed_dirs: u64,
+    pub linked_files: u64,
+    /// Size of deleted and hardlinked files
+    pub freed_bytes: u64,
+    /// Paths that failed to move to trash, nothing was put in their place
+    pub not_trashed: Vec<PathBuf>,
+}
+
+/// Ordered list of operations, every operation only depends on the ones before it
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    operations: Vec<Operation>,
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, operation: Operation) {
+        trace!("Planned: {operation:?}");
+        self.operations.push(operation);
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Execute operations in order, recording every successful one into journal.
+    /// Failed create, move or copy stops the run, failed deletions and hardlinks
+    /// are only logged, so one locked file doesn't stop cleaning.
+    pub fn execute(&self, recorder: &mut RunRecorder) -> anyhow::Result<PlanSummary> {
+        let mut summary = PlanSummary::default();
+
+        for operation in &self.operations {
+            // Trash makes room for an overwriting file, which must not replace the kept one
+            if matches!(
+                operation,
+                Operation::CreateDir { .. }
+                    | Operation::Move { .. }
+                    | Operation::Copy { .. }
+                    | Operation::Restore { .. }
+            ) && let Some(kept) = summary
+                .not_trashed
+                .iter()
+                .find(|path| operation.target().starts_with(path))
+            {
+                warn!(
+                    "Skipped {} of {}, {} was not moved to trash",
+                    operation.name(),
+                    operation.target().display(),
+                    kept.display()
+                );
+                continue;
+            }
+            info!(
+                "{} {}",
+                operation.colored_name(),
+                operation.target().display()
+            );
+            let result = operation.execute();
+            if let Ok(executed) = &result {
+                recorder.record(executed)?;
+            }
+            match (operation, result.map(|_| ())) {
+                (Operation::CreateDir { .. }, Ok(())) => summary.created_dirs += 1,
+                (
+                    Operation::Move { .. } | Operation::Copy { .. } | Operation::Restore { .. },
+                    Ok(()),
+                ) => summary.transferred_files += 1,
+                (Operation::Delete { size, is_dir, .. }, Ok(())) => {
+                    if *is_dir {
+                        summary.deleted_dirs += 1;
+                    } else {
+                        summary.deleted_files += 1;
+                    }
+                    summary.freed_bytes += size;
+                }
+                (Operation::Trash { is_dir, .. }, Ok(())) => {
+                    if *is_dir {
+                        summary.trashed_dirs += 1;
+                    } else {
+                        summary.trashed_files += 1;
+                    }
+                }
+                (Operation::RemoveDir { .. }, Ok(())) => {}
+                (Operation::Hardlink { size, .. }, Ok(())) => {
+                    summary.linked_files += 1;
+                    summary.freed_bytes += size;
+                }
+                (Operation::Delete { path, .. } | Operation::RemoveDir { path }, Err(e)) => {
+                    warn!("Failed to delete {}: {}", path.display(), e);
+                }
+                (Operation::Trash { path, .. }, Err(e)) => {
+                    warn!("Failed to move {} to trash: {}", path.display(), e);
+                    summary.not_trashed.push(path.clone());
+                }
+                (Operation::Hardlink { path, .. }, Err(e)) => {
+                    warn!("Failed to replace {} with hardlink: {}", path.display(), e);
+                }
+                (_, Err(e)) => {
+                    return Err(e).with_context(|| match operation.source() {
+                        Some(source) => format!(
+                            "Failed to {} {} to {}",
+                            operation.name(),
+                            source.display(),
+                            operation.target().display()
+                        ),
+                        None => format!(
+                            "Failed to {} {}",
+                            operation.name(),
+                            operation.target().display()
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    pub fn print(&self, format: PlanFormat) {
+        println!("{}", "Planned operations (dry run):".bold().bright_green());
+        match format {
+            PlanFormat::Table => self.print_table(),
+            PlanFormat::Tree => self.print_tree(),
+        }
+        self.print_totals();
+    }
+
+    fn print_table(&self) {
+        for operation in &self.operations {
+            let size = operation.size().map(format_bytes).unwrap_or_default();
+            match operation.source() {
+                Some(source) => println!(
+                    "{} {:>10} {} -> {}",
+                    operation.colored_name(),
+                    size.bright_yellow(),
+                    source.display(),
+                    operation.target().display().bright_cyan()
+                ),
+                None => println!(
+                    "{} {:>10} {}",
+                    operation.colored_name(),
+                    size.bright_yellow(),
+                    operation.target().display().bright_cyan()
+                ),
+            }
+        }
+    }
+
+    fn print_tree(&self) {
+        let mut root = TreeNode::default();
+        for operation in &self.operations {
+            let mut node = &mut root;
+            for component in operation.target().components() {
+                node = node
+                    .children
+                    .entry(component.as_os_str().to_string_lossy().into_owned())
+                    .or_default();
+            }
+            node.operations.push(operation);
+        }
+
+        // Collapse common prefix of all paths into a single root line
+        let mut prefix = PathBuf::new();
+        let mut node = &root;
+        while node.children.len() == 1 {
+            let (name, child) = node.children.iter().next().expect("Checked length above");
+            if !child.operations.is_empty() {
+                break;
+            }
+            prefix.push(name);
+            node = child;
+        }
+
+        println!("{}", prefix.display().bold());
+        node.print_children("");
+    }
+
+    fn print_totals(&self) {
+        let count = |name: &str| {
+            self.operations
+                .iter()
+                .filter(|operation| operation.name() == name)
+                .count()
+        };
+        let bytes: u64 = self.operations.iter().filter_map(Operation::size).sum();
+
+        println!();
+        println!(
+            "{} {} create, {} move, {} copy, {} delete, {} trash, {} restore, {} rmdir, {} link",
+            "Total operations:".bright_green(),
+            count("create").bright_purple(),
+            count("move").bright_purple(),
+            count("copy").bright_purple(),
+            count("delete").bright_purple(),
+            count("trash").bright_purple(),
+            count("restore").bright_purple(),
+            count("rmdir").bright_purple(),
+            count("link").bright_purple(),
+        );
+        println!(
+            "{} {}",
+            "Total size:".bright_green(),
+            format_bytes(bytes).bright_purple()
+        );
+    }
+}
+
+impl FromIterator<Operation> for Plan {
+    fn from_iter<T: IntoIterator<Item = Operation>>(operations: T) -> Self {
+        let mut plan = Plan::new();
+        for operation in operations {
+            plan.push(operation);
+        }
+        plan
+    }
+}
+
+#[derive(Default)]
+struct TreeNode<'a> {
+    operations: Vec<&'a Operation>,
+    children: BTreeMap<String, TreeNode<'a>>,
+}
+
+impl TreeNode<'_> {
+    fn print_children(&self, indent: &str) {
+        let last_index = self.children.len().saturating_sub(1);
+        for (index, (name, child)) in self.children.iter().enumerate() {
+            let (branch, child_indent) = if index == last_index {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            let labels = child
+                .operations
+                .iter()
+                .map(|operation| {
+                    let size = operation
+                        .size()
+                        .map(|size| format!(" {}", format_bytes(size).bright_yellow()))
+                        .unwrap_or_default();
+                    let source = operation
+                        .source()
+                        .map(|source| format!(" <- {}", source.display()))
+                        .unwrap_or_default();
+                    format!("[{}{size}]{source}", operation.name().trim_end())
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            println!("{indent}{branch}{} {labels}", name.bright_cyan());
+            child.print_children(&format!("{indent}{child_indent}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Journal;
+
+    #[test]
+    fn test_execute_plan() {
+        let temp_dir = std::env::temp_dir().join("fo_test_execute_plan");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("a.txt"), "a").unwrap();
+        fs::write(temp_dir.join("b.txt"), "bb").unwrap();
+        fs::write(temp_dir.join("junk.tmp"), "junk").unwrap();
+
+        let mut plan = Plan::new();
+        plan.push(Operation::CreateDir {
+            path: temp_dir.join("txt"),
+        });
+        plan.push(Operation::Move {
+            from: temp_dir.join("a.txt"),
+            to: temp_dir.join("txt/a.txt"),
+            size: 1,
+        });
+        plan.push(Operation::Copy {
+            from: temp_dir.join("b.txt"),
+            to: temp_dir.join("txt/b.txt"),
+            size: 2,
+        });
+        plan.push(Operation::Delete {
+            path: temp_dir.join("junk.tmp"),
+            size: 4,
+            is_dir: false,
+        });
+        // Failed deletion doesn't stop the plan
+        plan.push(Operation::Delete {
+            path: temp_dir.join("missing.tmp"),
+            size: 1,
+            is_dir: false,
+        });
+
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+        let summary = plan.execute(&mut journal.start_run().unwrap()).unwrap();
+        let recorded = journal.runs().unwrap().remove(0).operations;
+        let exists = |name: &str| temp_dir.join(name).exists();
+        let result = (
+            exists("a.txt"),
+            exists("txt/a.txt"),
+            exists("b.txt"),
+            exists("txt/b.txt"),
+            exists("junk.tmp"),
+        );
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(result, (false, true, true, true, false));
+        assert_eq!(
+            summary,
+            PlanSummary {
+                created_dirs: 1,
+                transferred_files: 2,
+                deleted_files: 1,
+                deleted_dirs: 0,
+                trashed_files: 0,
+                trashed_dirs: 0,
+                linked_files: 0,
+                freed_bytes: 4,
+                not_trashed: Vec::new(),
+            }
+        );
+        // Failed deletion is not recorded
+        assert_eq!(recorded, plan.operations()[..4]);
+    }
+
+    #[test]
+    fn test_execute_plan_stops_on_failed_transfer() {
+        let temp_dir = std::env::temp_dir().join("fo_test_execute_plan_stops");
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let mut plan = Plan::new();
+        plan.push(Operation::Move {
+            from: temp_dir.join("missing.txt"),
+            to: temp_dir.join("moved.txt"),
+            size: 1,
+        });
+        plan.push(Operation::CreateDir {
+            path: temp_dir.join("never_created"),
+        });
+
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+        let result = plan.execute(&mut journal.start_run().unwrap());
+        let created = temp_dir.join("never_created").exists();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(result.is_err());
+        assert!(!created);
+    }
+
+    #[test]
+    fn test_execute_plan_keeps_file_that_failed_to_move_to_trash() {
+        let temp_dir = std::env::temp_dir().join("fo_test_execute_plan_not_trashed");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("new.txt"), "new").unwrap();
+
+        // Missing file can't be moved to trash, same as one on a volume without trash
+        let mut plan = Plan::new();
+        plan.push(Operation::Trash {
+            path: temp_dir.join("kept.txt"),
+            size: 3,
+            is_dir: false,
+            trashed: None,
+        });
+        plan.push(Operation::Copy {
+            from: temp_dir.join("new.txt"),
+            to: temp_dir.join("kept.txt"),
+            size: 3,
+        });
+
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+        let summary = plan.execute(&mut journal.start_run().unwrap()).unwrap();
+        let copied = temp_dir.join("kept.txt").exists();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(!copied);
+        assert_eq!(summary.transferred_files, 0);
+        assert_eq!(summary.not_trashed, vec![temp_dir.join("kept.txt")]);
+    }
+}
@@ -1,26 +1,36 @@
 use std::{
     fmt::{Debug, Display},
-    fs,
     path::{Path, PathBuf},
 };
 
+use anstream::println;
 use clap::Parser;
 use log::{debug, trace};
 
-use crate::{cli::Cli, confirmation::confirm};
+use crate::{
+    cli::Cli, commands::plan::PlanFormat, config::Config, confirmation::confirm, journal::Journal,
+};
+use conflicts::print_conflicts;
 use owo_colors::OwoColorize;
-use sort_directory::sort_directory;
-use transfer_files::transfer_files;
+use plan_sort::{SortPlan, plan_sort};
 
+mod conflicts;
+mod date_layout;
 mod file_action;
+mod plan_sort;
+mod rules;
+mod size_buckets;
 mod sort_by;
 mod sort_directory;
 mod sorters;
-mod transfer_files;
 
 pub(super) use super::find::FilesList;
+pub(super) use conflicts::OnConflict;
+pub(super) use date_layout::DateLayout;
 pub(super) use file_action::FileAction;
-pub(super) use sort_by::SortBy;
+pub(crate) use size_buckets::SizeBuckets;
+pub(crate) use sort_by::SortBy;
+pub(super) use sort_by::{DateSource, Grouping};
 pub(super) use sorters::*;
 
 #[derive(Parser, Debug, Default)]
@@ -46,92 +56,137 @@ pub struct SortCommand {
     #[arg(
         short,
         long,
-        help = "Sort values by size, extension(ext) or creation_date(date)"
+        value_enum,
+        default_value = "date",
+        help = "Sort values by size, extension(ext), creation date(date), modification date(modified), access date(accessed), inode change date(changed), MIME type detected from content(type) or its category(category)"
     )]
     sort_by: SortBy,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "sort_by",
+        help = "Sort by rules of a TOML file instead of --sort-by. Every rule has filters of 'fo find' and a destination like 'Finance/{year}/{month}', the first matching rule wins"
+    )]
+    rules: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "LAYOUT",
+        value_parser = DateLayout::parse,
+        default_value = "day",
+        conflicts_with = "rules",
+        help = "Directories of date sorts: 'day' (2025-06-14), 'tree' (2025/06/14), 'month' (2025/2025-06), 'quarter' (2025/Q2), 'week' (2025/W24), 'year' or own layout of %Y %y %m %B %b %d %j %q (quarter) %V (ISO week) %G (ISO week year). Dates are in local timezone"
+    )]
+    date_format: DateLayout,
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "rules",
+        help = "Where date sorts take creation time from: birth time, modification time, birth time falling back to it, or date photos and videos were taken (media) falling back to filesystem time [default: --time-source]"
+    )]
+    date_source: Option<DateSource>,
+    #[arg(
+        long,
+        value_name = "BUCKETS",
+        value_parser = SizeBuckets::parse,
+        default_value = "hundreds",
+        conflicts_with = "rules",
+        help = "Directories of --sort-by size: 'hundreds' (100-199 KB), 'log' (<1KiB, 1KiB-1MiB, 1MiB-100MiB, >100MiB), 'labels' (tiny, small, medium, large, huge), own bounds like '1M,100M,1G' or own labels like 'small=1M,medium=1G,large'"
+    )]
+    size_buckets: SizeBuckets,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "rename",
+        help = "What to do when a file already exists at its destination. Files with the same content are always skipped, directories are never replaced"
+    )]
+    on_conflict: OnConflict,
     #[arg(
         short = 'a',
         long = "all",
+        overrides_with = "no_hidden",
         help = "Include hidden files (dotfiles) in sorting"
     )]
     search_hidden: bool,
+    #[arg(
+        long = "no-all",
+        overrides_with = "search_hidden",
+        help = "Leave out hidden files even when config includes them"
+    )]
+    no_hidden: bool,
     #[arg(
         short = 'r',
         long = "recursive",
         help = "Sort directories recursively. All inner files will be sorted as well"
     )]
     search_recursive: bool,
+    #[arg(long, help = "Print planned operations without touching any files")]
+    dry_run: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "tree",
+        help = "How planned operations are printed by --dry-run"
+    )]
+    plan_format: PlanFormat,
 }
 
-// Directory iterator
-//  -- Sort files in directory
-//  -- Return inner directories paths
-//  -- Repeat for each inner directory if recursive
-// Sorter command
-//  -- Collect all paths for directory
-//  -- Sort paths by criteria
-//  -- Move/copy files
-//  -- Return inner directories paths with new locations
 impl SortCommand {
-    pub fn execute(&self, _context: &Cli) -> anyhow::Result<()> {
+    pub fn configure(&mut self, config: &Config) {
+        self.sort_by = config.sort_by.value;
+        self.size_buckets = config.size_buckets.value.clone();
+        self.search_hidden = config.hidden.value;
+        self.plan_format = config.plan_format.value;
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn execute(&self, context: &Cli) -> anyhow::Result<()> {
         debug!("Executing 'SORT' command");
         trace!("with configuration: {self}");
 
+        debug!("Planning sort of directory: {:?}", self.directory);
+        let SortPlan { plan, mut resolver } = plan_sort(self, context)?;
+        if self.dry_run {
+            plan.print(self.plan_format);
+            print_conflicts(resolver.conflicts());
+            return Ok(());
+        }
+
         if self.move_arg
             && !confirm(
-                "You have chosen to move files. Are you sure you want to proceed? This action cannot be undone.",
+                "You have chosen to move files. Are you sure you want to proceed? The run can be reverted with 'fo undo'.",
             )?
         {
             debug!("User declined to move files. Aborting command execution.");
             return Ok(());
         }
 
-        let mut file_action = FileAction::from(self);
-        let mut paths = vec![self.directory.clone()];
-
-        self.create_target_root_directory(&self.directory)?;
-
-        while let Some(path) = paths.pop() {
-            let files_list = FilesList::new(&path, false, self.search_hidden)?;
-            let target_root_path = if path == self.directory {
-                &self.directory.join("..").join(format!(
-                    "{}_sorted",
-                    self.directory
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                ))
-            } else {
-                &path
-            };
-
-            debug!("Sorting directory: {path:?}");
-            let sorted_files_list = sort_directory(files_list, self.sort_by)?;
-
-            debug!("Transferring files from directory: {path:?}");
-            let inner_directories_paths =
-                transfer_files(sorted_files_list, target_root_path, &file_action)?;
-
-            if self.search_recursive {
-                paths.extend(inner_directories_paths);
-                file_action = FileAction::Move;
-            }
-        }
+        let mut recorder = Journal::open()?.start_run()?;
+        let summary = plan.execute(&mut recorder)?;
+        println!(
+            "\n{}\n  {} directory(ies) created\n  {} file(s) {}\n  run id: {}",
+            "Sort summary:".bold(),
+            summary.created_dirs.bright_green(),
+            summary.transferred_files.bright_green(),
+            if self.copy { "copied" } else { "moved" },
+            recorder.run_id().bright_purple(),
+        );
+        resolver.mark_not_trashed(&summary.not_trashed);
+        print_conflicts(resolver.conflicts());
 
         Ok(())
     }
 
-    fn create_target_root_directory(&self, current_directory_path: &Path) -> anyhow::Result<()> {
-        let source_directory_name = current_directory_path
-            .file_name()
-            .unwrap_or_else(|| std::ffi::OsStr::new(""));
-        let target_root_path = current_directory_path.join(format!(
-            "../{}_sorted",
-            source_directory_name.to_string_lossy()
-        ));
-        trace!("Creating target root directory: {target_root_path:?}");
-        fs::create_dir(&target_root_path)?;
-        Ok(())
+    /// Sorted files are placed next to the source directory, e.g. `photos` into `photos_sorted`
+    fn target_root_path(&self) -> PathBuf {
+        let source_directory_name = self.directory.file_name().unwrap_or_default();
+        let target_name = format!("{}_sorted", source_directory_name.to_string_lossy());
+        match self.directory.parent() {
+            Some(parent) if !source_directory_name.is_empty() => parent.join(target_name),
+            _ => self.directory.join("..").join(target_name),
+        }
     }
 }
 
@@ -146,6 +201,11 @@ impl Display for SortCommand {
         writeln!(f, "{}: {}", "copy".bright_cyan(), self.copy)?;
         writeln!(f, "{}: {}", "move".bright_cyan(), self.move_arg)?;
         writeln!(f, "{}: {:?}", "sort_by".bright_cyan(), self.sort_by)?;
+        writeln!(f, "{}: {:?}", "rules".bright_cyan(), self.rules)?;
+        writeln!(f, "{}: {}", "date_format".bright_cyan(), self.date_format)?;
+        writeln!(f, "{}: {:?}", "date_source".bright_cyan(), self.date_source)?;
+        writeln!(f, "{}: {}", "size_buckets".bright_cyan(), self.size_buckets)?;
+        writeln!(f, "{}: {:?}", "on_conflict".bright_cyan(), self.on_conflict)?;
         writeln!(
             f,
             "{}: {}",
@@ -158,6 +218,8 @@ impl Display for SortCommand {
             "recursive".bright_cyan(),
             self.search_recursive
         )?;
+        writeln!(f, "{}: {}", "dry_run".bright_cyan(), self.dry_run)?;
+        writeln!(f, "{}: {:?}", "plan_format".bright_cyan(), self.plan_format)?;
 
         Ok(())
     }
@@ -0,0 +1,388 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, Metadata},
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use anstream::println;
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+
+use crate::{
+    commands::plan::{Operation, Plan},
+    confirmation::{Choice, choose},
+};
+
+const ASK_CHOICES: &[Choice] = &[
+    Choice::new('s', "skip"),
+    Choice::new('o', "overwrite"),
+    Choice::new('r', "rename"),
+];
+
+/// What to do with a sorted file whose destination already exists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnConflict {
+    /// Leave sorted file where it is
+    Skip,
+    /// Move existing file to trash and put sorted file in its place
+    Overwrite,
+    /// Put sorted file next to existing one, e.g. `photo (1).jpg`
+    #[default]
+    Rename,
+    /// Overwrite only when sorted file was modified later than existing one
+    KeepNewer,
+    /// Overwrite only when sorted file is larger than existing one
+    KeepLarger,
+    /// Ask for every conflict
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Existing file has the same content, sorted file is left where it is
+    Identical,
+    Skipped,
+    Overwritten,
+    /// Existing file was to be overwritten, but it failed to move to trash, so it was kept
+    NotTrashed,
+    Renamed(PathBuf),
+    /// User is asked when the run executes, not during a dry run
+    Ask,
+}
+
+/// Sorted file whose destination was taken, and what was done about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub decision: Decision,
+}
+
+/// Picks destinations of transfers, so no file already at destination is replaced unnoticed
+#[derive(Debug, Default)]
+pub struct ConflictResolver {
+    policy: OnConflict,
+    dry_run: bool,
+    /// Destinations of this run, they are not on disk while planning
+    planned: HashSet<PathBuf>,
+    conflicts: Vec<Conflict>,
+}
+
+impl ConflictResolver {
+    pub fn new(policy: OnConflict, dry_run: bool) -> Self {
+        ConflictResolver {
+            policy,
+            dry_run,
+            ..Default::default()
+        }
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// Record that existing files of executed run were kept, because they failed to move to trash
+    pub fn mark_not_trashed(&mut self, not_trashed: &[PathBuf]) {
+        for conflict in &mut self.conflicts {
+            if conflict.decision == Decision::Overwritten && not_trashed.contains(&conflict.target)
+            {
+                conflict.decision = Decision::NotTrashed;
+            }
+        }
+    }
+
+    /// Destination of `source` planned for `target`, `None` when it is not transferred.
+    /// Existing file that is overwritten is moved to trash first, so the run can be undone.
+    pub fn resolve(
+        &mut self,
+        plan: &mut Plan,
+        source: &Path,
+        target: &Path,
+    ) -> anyhow::Result<Option<PathBuf>> {
+        let existing = fs::symlink_metadata(target).ok();
+        if existing.is_none() && !self.planned.contains(target) {
+            self.planned.insert(target.to_path_buf());
+            return Ok(Some(target.to_path_buf()));
+        }
+
+        let decision = match &existing {
+            Some(existing) => self.decide(source, target, existing)?,
+            // Taken by another file of this run, which can't be compared before it is transferred
+            None if self.policy == OnConflict::Skip => Decision::Skipped,
+            None => Decision::Renamed(self.free_name(target)),
+        };
+        let destination = match &decision {
+            Decision::Identical | Decision::Skipped | Decision::NotTrashed | Decision::Ask => None,
+            Decision::Overwritten => {
+                let existing = existing.expect("only files on disk are overwritten");
+                plan.push(Operation::Trash {
+                    path: target.to_path_buf(),
+                    size: existing.len(),
+                    is_dir: existing.is_dir(),
+                    trashed: None,
+                });
+                Some(target.to_path_buf())
+            }
+            Decision::Renamed(renamed) => Some(renamed.clone()),
+        };
+        if let Some(destination) = &destination {
+            self.planned.insert(destination.clone());
+        }
+
+        self.conflicts.push(Conflict {
+            source: source.to_path_buf(),
+            target: target.to_path_buf(),
+            decision,
+        });
+        Ok(destination)
+    }
+
+    fn decide(
+        &self,
+        source: &Path,
+        target: &Path,
+        existing: &Metadata,
+    ) -> anyhow::Result<Decision> {
+        let metadata = fs::symlink_metadata(source)?;
+        if metadata.is_file() && existing.is_file() && same_content(source, target)? {
+            return Ok(Decision::Identical);
+        }
+        // Directory is never replaced, and a file doesn't replace a directory
+        if metadata.is_dir() || existing.is_dir() {
+            return Ok(match self.policy {
+                OnConflict::Skip => Decision::Skipped,
+                _ => Decision::Renamed(self.free_name(target)),
+            });
+        }
+
+        let overwrite = match self.policy {
+            OnConflict::Skip => false,
+            OnConflict::Overwrite => true,
+            OnConflict::Rename => return Ok(Decision::Renamed(self.free_name(target))),
+            OnConflict::KeepNewer => metadata.modified()? > existing.modified()?,
+            OnConflict::KeepLarger => metadata.len() > existing.len(),
+            OnConflict::Ask if self.dry_run => return Ok(Decision::Ask),
+            OnConflict::Ask => {
+                let prompt = format!(
+                    "{} already exists, what to do with {}?",
+                    target.display(),
+                    source.display()
+                );
+                match choose(&prompt, ASK_CHOICES)? {
+                    'o' => true,
+                    'r' => return Ok(Decision::Renamed(self.free_name(target))),
+                    _ => false,
+                }
+            }
+        };
+        Ok(if overwrite {
+            Decision::Overwritten
+        } else {
+            Decision::Skipped
+        })
+    }
+
+    /// First free name of `name (1).ext`, `name (2).ext`, ...
+    fn free_name(&self, target: &Path) -> PathBuf {
+        let stem = target
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let extension = target
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+
+        (1..)
+            .map(|number| target.with_file_name(format!("{stem} ({number}){extension}")))
+            .find(|path| !self.planned.contains(path) && fs::symlink_metadata(path).is_err())
+            .expect("some name is free")
+    }
+}
+
+/// Whether both files have the same bytes
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    let (mut chunk_a, mut chunk_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let read = a.read(&mut chunk_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut chunk_b[..read])?;
+        if chunk_a[..read] != chunk_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Print decision of every conflict, nothing when there were none
+pub fn print_conflicts(conflicts: &[Conflict]) {
+    if conflicts.is_empty() {
+        return;
+    }
+
+    println!(
+        "\n{} {}",
+        "Files already at destination:".bold(),
+        conflicts.len().bright_purple()
+    );
+    for conflict in conflicts {
+        let source = conflict.source.display();
+        let target = conflict.target.display();
+        match &conflict.decision {
+            Decision::Identical => println!(
+                "  {} {source} (same content as {target})",
+                "skipped    ".bright_green()
+            ),
+            Decision::Skipped => println!(
+                "  {} {source} (kept {target})",
+                "skipped    ".bright_yellow()
+            ),
+            Decision::Overwritten => println!(
+                "  {} {target} with {source}, old file in trash",
+                "overwritten".bright_red()
+            ),
+            Decision::NotTrashed => println!(
+                "  {} {source} (kept {target}, it failed to move to trash)",
+                "skipped    ".bright_red()
+            ),
+            Decision::Renamed(renamed) => println!(
+                "  {} {source} -> {}",
+                "renamed    ".bright_cyan(),
+                renamed.display()
+            ),
+            Decision::Ask => println!(
+                "  {} {source} (already at {target})",
+                "ask        ".bright_yellow()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(OnConflict::Skip, "new", Decision::Skipped ; "skip")]
+    #[test_case(OnConflict::Overwrite, "new", Decision::Overwritten ; "overwrite")]
+    #[test_case(OnConflict::Rename, "new", Decision::Renamed(PathBuf::from("a (2).txt")) ; "rename")]
+    #[test_case(OnConflict::KeepNewer, "new", Decision::Overwritten ; "keep newer")]
+    #[test_case(OnConflict::KeepLarger, "new", Decision::Skipped ; "keep larger")]
+    #[test_case(OnConflict::Overwrite, "old file", Decision::Identical ; "identical")]
+    fn test_resolve(policy: OnConflict, content: &str, expected: Decision) {
+        let temp_dir = std::env::temp_dir().join(format!("fo_test_conflict_{policy:?}_{content}"));
+        fs::create_dir_all(temp_dir.join("sorted")).unwrap();
+        fs::write(temp_dir.join("sorted/a.txt"), "old file").unwrap();
+        fs::write(temp_dir.join("sorted/a (1).txt"), "").unwrap();
+        fs::write(temp_dir.join("a.txt"), content).unwrap();
+        File::options()
+            .write(true)
+            .open(temp_dir.join("sorted/a.txt"))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        let mut plan = Plan::new();
+        let mut resolver = ConflictResolver::new(policy, false);
+        let destination = resolver.resolve(
+            &mut plan,
+            &temp_dir.join("a.txt"),
+            &temp_dir.join("sorted/a.txt"),
+        );
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        let expected = match expected {
+            Decision::Renamed(name) => Decision::Renamed(temp_dir.join("sorted").join(name)),
+            decision => decision,
+        };
+        let expected_destination = match &expected {
+            Decision::Identical | Decision::Skipped | Decision::NotTrashed | Decision::Ask => None,
+            Decision::Overwritten => Some(temp_dir.join("sorted/a.txt")),
+            Decision::Renamed(renamed) => Some(renamed.clone()),
+        };
+        assert_eq!(destination.unwrap(), expected_destination);
+        assert_eq!(resolver.conflicts()[0].decision, expected);
+        // Only overwritten file is moved out of the way
+        assert_eq!(
+            plan.operations().len(),
+            usize::from(expected == Decision::Overwritten)
+        );
+    }
+
+    #[test]
+    fn test_resolve_destinations_planned_in_same_run() {
+        let target = std::env::temp_dir().join("fo_test_conflict_planned/notes");
+        let mut plan = Plan::new();
+        let mut resolver = ConflictResolver::new(OnConflict::Overwrite, false);
+
+        let first = resolver.resolve(&mut plan, Path::new("a/notes"), &target);
+        let second = resolver.resolve(&mut plan, Path::new("b/notes"), &target);
+
+        assert_eq!(first.unwrap(), Some(target.clone()));
+        assert_eq!(second.unwrap(), Some(target.with_file_name("notes (1)")));
+        assert!(plan.is_empty());
+    }
+
+    #[test_case(OnConflict::Overwrite, Decision::Renamed(PathBuf::from("a (1).txt")) ; "overwrite")]
+    #[test_case(OnConflict::KeepLarger, Decision::Renamed(PathBuf::from("a (1).txt")) ; "keep larger")]
+    #[test_case(OnConflict::Skip, Decision::Skipped ; "skip")]
+    fn test_resolve_never_replaces_directory(policy: OnConflict, expected: Decision) {
+        let temp_dir = std::env::temp_dir().join(format!("fo_test_conflict_directory_{policy:?}"));
+        fs::create_dir_all(temp_dir.join("sorted/a.txt")).unwrap();
+        fs::write(
+            temp_dir.join("a.txt"),
+            "larger than a directory entry".repeat(1000),
+        )
+        .unwrap();
+
+        let mut plan = Plan::new();
+        let mut resolver = ConflictResolver::new(policy, false);
+        let destination = resolver.resolve(
+            &mut plan,
+            &temp_dir.join("a.txt"),
+            &temp_dir.join("sorted/a.txt"),
+        );
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        let expected = match expected {
+            Decision::Renamed(name) => Decision::Renamed(temp_dir.join("sorted").join(name)),
+            decision => decision,
+        };
+        assert!(destination.is_ok());
+        assert_eq!(resolver.conflicts()[0].decision, expected);
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_ask_in_dry_run_doesnt_prompt() {
+        let temp_dir = std::env::temp_dir().join("fo_test_conflict_ask_dry_run");
+        fs::create_dir_all(temp_dir.join("sorted")).unwrap();
+        fs::write(temp_dir.join("sorted/a.txt"), "old").unwrap();
+        fs::write(temp_dir.join("a.txt"), "new").unwrap();
+
+        let mut plan = Plan::new();
+        let mut resolver = ConflictResolver::new(OnConflict::Ask, true);
+        let destination = resolver.resolve(
+            &mut plan,
+            &temp_dir.join("a.txt"),
+            &temp_dir.join("sorted/a.txt"),
+        );
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(destination.unwrap(), None);
+        assert_eq!(resolver.conflicts()[0].decision, Decision::Ask);
+        assert!(plan.is_empty());
+    }
+}
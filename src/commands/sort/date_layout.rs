@@ -0,0 +1,198 @@
+use std::fmt::Display;
+
+use anyhow::bail;
+use time::{Month, OffsetDateTime};
+
+/// Layout of `--date-format` used by default, one directory per day
+pub const DEFAULT_DATE_LAYOUT: &str = "%Y-%m-%d";
+
+/// Named layouts of `--date-format`
+const PRESETS: &[(&str, &str)] = &[
+    ("day", DEFAULT_DATE_LAYOUT),
+    ("tree", "%Y/%m/%d"),
+    ("month", "%Y/%Y-%m"),
+    ("quarter", "%Y/Q%q"),
+    ("week", "%G/W%V"),
+    ("year", "%Y"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Text(String),
+    /// Year, e.g. 2025
+    Year,
+    /// Last two digits of year
+    ShortYear,
+    Month,
+    MonthName,
+    ShortMonthName,
+    Day,
+    /// Day of year, 001-366
+    Ordinal,
+    /// Quarter of year, 1-4
+    Quarter,
+    /// ISO 8601 week, 01-53
+    IsoWeek,
+    /// Year the ISO week belongs to, differs from year around new year
+    IsoYear,
+    Hour,
+    Minute,
+    Second,
+}
+
+/// Strftime-like date layout, e.g. `%Y/%m/%d`. `/` separates nested directories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateLayout {
+    source: String,
+    items: Vec<Item>,
+}
+
+impl Default for DateLayout {
+    fn default() -> Self {
+        DateLayout::parse(DEFAULT_DATE_LAYOUT).expect("default layout is valid")
+    }
+}
+
+impl DateLayout {
+    /// Parse layout, or one of presets: day, tree, month, quarter, week or year
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let layout = PRESETS
+            .iter()
+            .find(|(name, _)| *name == source)
+            .map_or(source, |(_, preset)| preset);
+
+        let mut items = Vec::new();
+        let mut text = String::new();
+        let mut chars = layout.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                text.push(ch);
+                continue;
+            }
+            let item = match chars.next() {
+                Some('Y') => Item::Year,
+                Some('y') => Item::ShortYear,
+                Some('m') => Item::Month,
+                Some('B') => Item::MonthName,
+                Some('b') => Item::ShortMonthName,
+                Some('d') => Item::Day,
+                Some('j') => Item::Ordinal,
+                Some('q') => Item::Quarter,
+                Some('V') => Item::IsoWeek,
+                Some('G') => Item::IsoYear,
+                Some('H') => Item::Hour,
+                Some('M') => Item::Minute,
+                Some('S') => Item::Second,
+                Some('%') => {
+                    text.push('%');
+                    continue;
+                }
+                Some(other) => bail!(
+                    "Unsupported date format '%{other}' in '{layout}', expected one of %Y %y %m %B %b %d %j %q %V %G %H %M %S %%"
+                ),
+                None => bail!("Date format '{layout}' ends with '%'"),
+            };
+            if !text.is_empty() {
+                items.push(Item::Text(std::mem::take(&mut text)));
+            }
+            items.push(item);
+        }
+        if !text.is_empty() {
+            items.push(Item::Text(text));
+        }
+
+        if items.iter().all(|item| matches!(item, Item::Text(_))) {
+            bail!("Date format '{layout}' has no date parts, e.g. %Y or %m");
+        }
+        if layout.starts_with('/')
+            || layout
+                .split('/')
+                .any(|part| part.is_empty() || part == "..")
+        {
+            bail!("Date format '{layout}' must be relative path without empty or '..' parts");
+        }
+
+        Ok(DateLayout {
+            source: source.to_string(),
+            items,
+        })
+    }
+
+    pub fn format(&self, date: OffsetDateTime) -> String {
+        self.items
+            .iter()
+            .map(|item| match item {
+                Item::Text(text) => text.clone(),
+                Item::Year => format!("{:04}", date.year()),
+                Item::ShortYear => format!("{:02}", date.year().rem_euclid(100)),
+                Item::Month => format!("{:02}", date.month() as u8),
+                Item::MonthName => date.month().to_string(),
+                Item::ShortMonthName => date.month().to_string()[..3].to_string(),
+                Item::Day => format!("{:02}", date.day()),
+                Item::Ordinal => format!("{:03}", date.ordinal()),
+                Item::Quarter => quarter(date.month()).to_string(),
+                Item::IsoWeek => format!("{:02}", date.iso_week()),
+                Item::IsoYear => format!("{:04}", date.to_iso_week_date().0),
+                Item::Hour => format!("{:02}", date.hour()),
+                Item::Minute => format!("{:02}", date.minute()),
+                Item::Second => format!("{:02}", date.second()),
+            })
+            .collect()
+    }
+}
+
+impl Display for DateLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn quarter(month: Month) -> u8 {
+    (month as u8 - 1) / 3 + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+    use time::Date;
+
+    use super::*;
+
+    fn date(year: i32, month: Month, day: u8) -> OffsetDateTime {
+        Date::from_calendar_date(year, month, day)
+            .unwrap()
+            .with_hms(10, 30, 5)
+            .unwrap()
+            .assume_utc()
+    }
+
+    #[test_case("day", "2025-06-14" ; "day preset")]
+    #[test_case("tree", "2025/06/14" ; "tree preset")]
+    #[test_case("month", "2025/2025-06" ; "month preset")]
+    #[test_case("quarter", "2025/Q2" ; "quarter preset")]
+    #[test_case("week", "2025/W24" ; "week preset")]
+    #[test_case("%y %b %B %j %H:%M:%S 100%%", "25 Jun June 165 10:30:05 100%" ; "all parts")]
+    fn test_format(layout: &str, expected: &str) {
+        let date = date(2025, Month::June, 14);
+
+        assert_eq!(DateLayout::parse(layout).unwrap().format(date), expected);
+    }
+
+    #[test]
+    fn test_iso_week_year_differs_around_new_year() {
+        let layout = DateLayout::parse("week").unwrap();
+
+        assert_eq!(layout.format(date(2024, Month::December, 30)), "2025/W01");
+        assert_eq!(layout.format(date(2027, Month::January, 1)), "2026/W53");
+    }
+
+    #[test_case("%Q" ; "unsupported")]
+    #[test_case("%Y%" ; "trailing percent")]
+    #[test_case("photos" ; "no date parts")]
+    #[test_case("/%Y" ; "absolute")]
+    #[test_case("%Y//%m" ; "empty part")]
+    #[test_case("../%Y" ; "parent")]
+    fn test_parse_invalid(layout: &str) {
+        assert!(DateLayout::parse(layout).is_err());
+    }
+}
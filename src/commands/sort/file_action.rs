@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use crate::commands::sort::SortCommand;
+use crate::commands::{plan::Operation, sort::SortCommand};
 
 #[derive(Clone, Debug, Copy)]
 pub enum FileAction {
@@ -22,15 +22,13 @@ impl From<&SortCommand> for FileAction {
     }
 }
 
-pub type FileActionFn = Box<dyn Fn(&Path, &Path) -> std::io::Result<()>>;
-
 impl FileAction {
-    pub fn get_action_fn(&self) -> FileActionFn {
+    /// Operation that transfers file from `source` to `target`
+    pub fn operation(self, source: &Path, target: &Path, size: u64) -> Operation {
+        let (from, to) = (source.to_path_buf(), target.to_path_buf());
         match self {
-            FileAction::Move => Box::new(|src: &Path, dst: &Path| std::fs::rename(src, dst)),
-            FileAction::Copy => {
-                Box::new(|src: &Path, dst: &Path| std::fs::copy(src, dst).map(|_| ()))
-            }
+            FileAction::Move => Operation::Move { from, to, size },
+            FileAction::Copy => Operation::Copy { from, to, size },
         }
     }
 }
@@ -0,0 +1,446 @@
+use std::{
+    collections::HashSet,
+    fs::{self, DirEntry},
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use log::trace;
+
+use crate::{
+    cli::Cli,
+    commands::{
+        plan::{Operation, Plan},
+        sort::{
+            FileAction, FilesList, Grouping, SortCommand,
+            conflicts::ConflictResolver,
+            rules::SortRules,
+            sort_directory::{Sorter, sort_directory},
+        },
+    },
+};
+
+/// Operations of sort run, with decisions taken about files already at their destination
+#[derive(Debug)]
+pub struct SortPlan {
+    pub plan: Plan,
+    pub resolver: ConflictResolver,
+}
+
+/// Build every operation of sort run without touching disk.
+/// Existing sorted directories are merged into, existing files are resolved by `--on-conflict`.
+pub fn plan_sort(command: &SortCommand, context: &Cli) -> anyhow::Result<SortPlan> {
+    let sorter = match &command.rules {
+        Some(rules) => Sorter::Rules(SortRules::load(rules, &command.directory, context)?),
+        None => Sorter::By(
+            command.sort_by,
+            Grouping {
+                layout: command.date_format.clone(),
+                source: command
+                    .date_source
+                    .unwrap_or_else(|| context.time_source.into()),
+                size_buckets: command.size_buckets.clone(),
+            },
+        ),
+    };
+    let target_root_path = command.target_root_path();
+    let mut plan = SortPlan {
+        plan: Plan::new(),
+        resolver: ConflictResolver::new(command.on_conflict, command.dry_run),
+    };
+
+    if needs_create_dir(&target_root_path)? {
+        plan.plan.push(Operation::CreateDir {
+            path: target_root_path.clone(),
+        });
+    }
+    plan_directory(
+        &mut plan,
+        command,
+        &sorter,
+        &command.directory,
+        &target_root_path,
+        true,
+        context,
+    )?;
+
+    Ok(plan)
+}
+
+/// Sort files of `source` into directories under `target`.
+/// Inner directories are sorted the same way when recursive flag is set, otherwise transferred as is.
+fn plan_directory(
+    plan: &mut SortPlan,
+    command: &SortCommand,
+    sorter: &Sorter,
+    source: &Path,
+    target: &Path,
+    is_root: bool,
+    context: &Cli,
+) -> anyhow::Result<()> {
+    trace!("Planning directory: {source:?}");
+    let action = FileAction::from(command);
+
+    // Hidden files of inner directories are not sorted, but still travel with their directory
+    if !is_root && !command.search_hidden {
+        for entry in read_sorted_dir(source)? {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                plan_transfer(plan, action, &entry.path(), &target.join(entry.file_name()))?;
+            }
+        }
+    }
+
+    let files_list = FilesList::new(&source.to_path_buf(), false, command.search_hidden)?;
+    let mut sorted_files = sort_directory(files_list, sorter, context)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    sorted_files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut created_dirs = HashSet::new();
+    for (sorted_directory, mut files) in sorted_files {
+        let sorted_dir_path = target.join(&sorted_directory);
+        plan_create_dirs(
+            &mut plan.plan,
+            target,
+            Path::new(&sorted_directory),
+            &mut created_dirs,
+        )?;
+
+        files.sort_by_key(|file| file.file_name());
+        for file in files {
+            let target_file_path = sorted_dir_path.join(file.file_name());
+            if command.search_recursive && file.file_type()?.is_dir() {
+                let Some(target_file_path) =
+                    plan_target_dir(plan, &file.path(), &target_file_path)?
+                else {
+                    continue;
+                };
+                plan_directory(
+                    plan,
+                    command,
+                    sorter,
+                    &file.path(),
+                    &target_file_path,
+                    false,
+                    context,
+                )?;
+            } else {
+                plan_transfer(plan, action, &file.path(), &target_file_path)?;
+            }
+        }
+    }
+
+    if !is_root && matches!(action, FileAction::Move) {
+        plan.plan.push(Operation::RemoveDir {
+            path: source.to_path_buf(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Create sorted directory `relative` under `target` with every missing parent, parents first
+fn plan_create_dirs(
+    plan: &mut Plan,
+    target: &Path,
+    relative: &Path,
+    created_dirs: &mut HashSet<PathBuf>,
+) -> anyhow::Result<()> {
+    let mut path = target.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        if created_dirs.insert(path.clone()) && needs_create_dir(&path)? {
+            plan.push(Operation::CreateDir { path: path.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// Transfer file, or directory with all its contents keeping inner structure
+fn plan_transfer(
+    plan: &mut SortPlan,
+    action: FileAction,
+    source: &Path,
+    target: &Path,
+) -> anyhow::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    if !metadata.is_dir() {
+        if let Some(target) = plan.resolver.resolve(&mut plan.plan, source, target)? {
+            plan.plan
+                .push(action.operation(source, &target, metadata.len()));
+        }
+        return Ok(());
+    }
+
+    let Some(target) = plan_target_dir(plan, source, target)? else {
+        return Ok(());
+    };
+    for entry in read_sorted_dir(source)? {
+        plan_transfer(plan, action, &entry.path(), &target.join(entry.file_name()))?;
+    }
+    if matches!(action, FileAction::Move) {
+        plan.plan.push(Operation::RemoveDir {
+            path: source.to_path_buf(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Directory to put contents of directory `source` into. Existing directory is merged into,
+/// `None` when a file in place of `target` is kept.
+fn plan_target_dir(
+    plan: &mut SortPlan,
+    source: &Path,
+    target: &Path,
+) -> anyhow::Result<Option<PathBuf>> {
+    if is_existing_dir(target) {
+        return Ok(Some(target.to_path_buf()));
+    }
+
+    let target = plan.resolver.resolve(&mut plan.plan, source, target)?;
+    if let Some(target) = &target {
+        plan.plan.push(Operation::CreateDir {
+            path: target.clone(),
+        });
+    }
+    Ok(target)
+}
+
+/// Directory on disk, symlinks to directories are not merged into
+fn is_existing_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// Whether sorted directory is missing. A file in its place is an error before anything is touched.
+fn needs_create_dir(path: &Path) -> anyhow::Result<bool> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Ok(false),
+        Ok(_) => bail!(
+            "Cannot create sorted directory {}, a file with that name exists",
+            path.display()
+        ),
+        Err(_) => Ok(true),
+    }
+}
+
+fn read_sorted_dir(path: &Path) -> anyhow::Result<Vec<DirEntry>> {
+    let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::commands::sort::{SortBy, conflicts::Decision};
+    use crate::journal::Journal;
+
+    fn create_source(name: &str) -> PathBuf {
+        let source = std::env::temp_dir().join(name).join("source");
+        fs::create_dir_all(source.join("inner/deep")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("b.rs"), "bb").unwrap();
+        fs::write(source.join("inner/c.txt"), "ccc").unwrap();
+        fs::write(source.join("inner/.hidden"), "").unwrap();
+        fs::write(source.join("inner/deep/d.rs"), "dddd").unwrap();
+        source
+    }
+
+    #[test]
+    fn test_plan_sort_copy() {
+        let source = create_source("fo_test_plan_sort_copy");
+        let target = source.parent().unwrap().join("source_sorted");
+        let command = SortCommand {
+            directory: source.clone(),
+            copy: true,
+            sort_by: SortBy::Ext,
+            ..Default::default()
+        };
+
+        let plan = plan_sort(&command, &Cli::default()).unwrap().plan;
+        fs::remove_dir_all(source.parent().unwrap()).unwrap();
+
+        assert_eq!(
+            plan.operations(),
+            &[
+                Operation::CreateDir {
+                    path: target.clone()
+                },
+                Operation::CreateDir {
+                    path: target.join("no_extension")
+                },
+                Operation::CreateDir {
+                    path: target.join("no_extension/inner")
+                },
+                Operation::Copy {
+                    from: source.join("inner/.hidden"),
+                    to: target.join("no_extension/inner/.hidden"),
+                    size: 0
+                },
+                Operation::Copy {
+                    from: source.join("inner/c.txt"),
+                    to: target.join("no_extension/inner/c.txt"),
+                    size: 3
+                },
+                Operation::CreateDir {
+                    path: target.join("no_extension/inner/deep")
+                },
+                Operation::Copy {
+                    from: source.join("inner/deep/d.rs"),
+                    to: target.join("no_extension/inner/deep/d.rs"),
+                    size: 4
+                },
+                Operation::CreateDir {
+                    path: target.join("rs")
+                },
+                Operation::Copy {
+                    from: source.join("b.rs"),
+                    to: target.join("rs/b.rs"),
+                    size: 2
+                },
+                Operation::CreateDir {
+                    path: target.join("txt")
+                },
+                Operation::Copy {
+                    from: source.join("a.txt"),
+                    to: target.join("txt/a.txt"),
+                    size: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_sort_rules_creates_nested_dirs() {
+        let source = create_source("fo_test_plan_sort_rules");
+        let root = source.parent().unwrap().to_path_buf();
+        let target = root.join("source_sorted");
+        let rules = root.join("rules.toml");
+        fs::write(
+            &rules,
+            "fallback = \"other\"\n[[rule]]\next = [\"rs\", \"txt\"]\ndestination = \"code/{ext}\"",
+        )
+        .unwrap();
+        let command = SortCommand {
+            directory: source.clone(),
+            copy: true,
+            rules: Some(rules),
+            ..Default::default()
+        };
+
+        let plan = plan_sort(&command, &Cli::default());
+        fs::remove_dir_all(&root).unwrap();
+        let created = plan
+            .unwrap()
+            .plan
+            .operations()
+            .iter()
+            .filter_map(|operation| match operation {
+                Operation::CreateDir { path } => {
+                    Some(path.strip_prefix(&target).unwrap().to_owned())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            created,
+            [
+                "",
+                "code",
+                "code/rs",
+                "code/txt",
+                "other",
+                "other/inner",
+                "other/inner/deep"
+            ]
+            .map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn test_plan_sort_recursive_move_executes_as_planned() {
+        let source = create_source("fo_test_plan_sort_recursive_move");
+        let target = source.parent().unwrap().join("source_sorted");
+        let command = SortCommand {
+            directory: source.clone(),
+            move_arg: true,
+            sort_by: SortBy::Ext,
+            search_recursive: true,
+            ..Default::default()
+        };
+
+        let plan = plan_sort(&command, &Cli::default()).unwrap().plan;
+        let untouched = source.join("inner/deep/d.rs").exists() && !target.exists();
+        let journal = Journal::at(source.parent().unwrap().join("journal.jsonl"));
+        plan.execute(&mut journal.start_run().unwrap()).unwrap();
+        let sorted = [
+            "txt/a.txt",
+            "rs/b.rs",
+            "no_extension/inner/txt/c.txt",
+            "no_extension/inner/.hidden",
+            "no_extension/inner/no_extension/deep/rs/d.rs",
+        ]
+        .iter()
+        .all(|path| target.join(path).exists());
+        let inner_removed = !source.join("inner").exists();
+        fs::remove_dir_all(source.parent().unwrap()).unwrap();
+
+        assert!(untouched, "planning must not touch disk");
+        assert!(sorted);
+        assert!(inner_removed);
+    }
+
+    #[test]
+    fn test_plan_sort_into_existing_sorted_dir() {
+        let source = create_source("fo_test_plan_sort_existing");
+        let target = source.parent().unwrap().join("source_sorted");
+        fs::create_dir_all(target.join("txt")).unwrap();
+        fs::write(target.join("txt/a.txt"), "a").unwrap();
+        fs::create_dir_all(target.join("rs")).unwrap();
+        fs::write(target.join("rs/b.rs"), "other").unwrap();
+        let command = SortCommand {
+            directory: source.clone(),
+            copy: true,
+            sort_by: SortBy::Ext,
+            ..Default::default()
+        };
+
+        let SortPlan { plan, resolver } = plan_sort(&command, &Cli::default()).unwrap();
+        fs::remove_dir_all(source.parent().unwrap()).unwrap();
+
+        let transfers = plan
+            .operations()
+            .iter()
+            .filter(|operation| !matches!(operation, Operation::CreateDir { .. }))
+            .map(|operation| operation.target().strip_prefix(&target).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transfers,
+            [
+                "no_extension/inner/.hidden",
+                "no_extension/inner/c.txt",
+                "no_extension/inner/deep/d.rs",
+                "rs/b (1).rs"
+            ]
+            .map(Path::new)
+        );
+        assert!(!plan.operations().contains(&Operation::CreateDir {
+            path: target.join("txt")
+        }));
+        assert_eq!(
+            resolver
+                .conflicts()
+                .iter()
+                .map(|conflict| &conflict.decision)
+                .collect::<Vec<_>>(),
+            [
+                &Decision::Renamed(target.join("rs/b (1).rs")),
+                &Decision::Identical
+            ]
+        );
+    }
+}
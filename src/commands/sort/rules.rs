@@ -0,0 +1,206 @@
+//! Rules of `fo sort --rules FILE`. Every file goes to destination of the first rule
+//! whose filters match it, or to fallback destination when no rule matches.
+//!
+//! ```toml
+//! fallback = "Other/{ext}"
+//!
+//! [[rule]]
+//! name = ["*invoice*"]
+//! ext = ["pdf"]
+//! destination = "Finance/{year}/{month}"
+//!
+//! [[rule]]
+//! ext = ["jpg", "jpeg", "png", "heic"]
+//! destination = "Photos/{created:%Y}"
+//! ```
+
+use std::{fs, fs::DirEntry, path::Path};
+
+use anyhow::Context;
+use log::{trace, warn};
+use serde::Deserialize;
+
+use super::sort_directory::SortedFiles;
+use crate::{
+    cli::Cli,
+    commands::find::{FileMatcherTrait, FilterSet},
+};
+use template::Template;
+
+mod template;
+
+/// Destination of files not matched by any rule
+const DEFAULT_FALLBACK: &str = "other";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    fallback: Option<String>,
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleConfig {
+    destination: String,
+    /// Same filters as flags of `fo find`. Kept as table, because unknown keys of
+    /// flattened struct are ignored by serde instead of being rejected
+    #[serde(flatten)]
+    filters: toml::Table,
+}
+
+struct SortRule {
+    matcher: Box<dyn FileMatcherTrait>,
+    destination: Template,
+}
+
+impl SortRule {
+    fn new(config: &RuleConfig, root: &Path, context: &Cli) -> anyhow::Result<Self> {
+        let filters: FilterSet = config.filters.clone().try_into()?;
+        Ok(SortRule {
+            matcher: filters.matcher(root, context)?,
+            destination: Template::parse(&config.destination)?,
+        })
+    }
+}
+
+pub struct SortRules {
+    rules: Vec<SortRule>,
+    fallback: Template,
+}
+
+impl SortRules {
+    /// Load rules file, path filters of rules are relative to `root`
+    pub fn load(path: &Path, root: &Path, context: &Cli) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sort rules {}", path.display()))?;
+        Self::parse(&content, root, context)
+            .with_context(|| format!("Invalid sort rules {}", path.display()))
+    }
+
+    fn parse(content: &str, root: &Path, context: &Cli) -> anyhow::Result<Self> {
+        let file: RulesFile = toml::from_str(content)?;
+        let rules = file
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                SortRule::new(rule, root, context)
+                    .with_context(|| format!("Invalid rule #{}", index + 1))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let fallback = Template::parse(file.fallback.as_deref().unwrap_or(DEFAULT_FALLBACK))?;
+
+        Ok(SortRules { rules, fallback })
+    }
+
+    /// Group files by destination of the first matching rule
+    pub fn sort(&self, files: Vec<DirEntry>, context: &Cli) -> anyhow::Result<SortedFiles> {
+        let mut sorted_files = SortedFiles::new();
+        for file in files {
+            let destination = match self.matching_rule(&file, context)? {
+                Some(rule) => &rule.destination,
+                None => &self.fallback,
+            };
+            trace!(
+                "{} goes to '{}'",
+                file.path().display(),
+                destination.source()
+            );
+            // One oddly named file shouldn't stop the whole sort
+            let directory = match destination.render(&file, context) {
+                Ok(directory) => directory,
+                Err(err) => {
+                    warn!("Skipping {}: {err}", file.path().display());
+                    continue;
+                }
+            };
+            sorted_files
+                .entry(directory.to_string_lossy().into_owned())
+                .or_default()
+                .push(file);
+        }
+        Ok(sorted_files)
+    }
+
+    fn matching_rule(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<Option<&SortRule>> {
+        for rule in &self.rules {
+            if rule.matcher.matches(file, context)? {
+                return Ok(Some(rule));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let temp_dir = std::env::temp_dir().join("fo_test_sort_rules");
+        fs::create_dir_all(&temp_dir).unwrap();
+        for name in ["invoice_march.pdf", "manual.pdf", "cat.JPG", "notes.txt"] {
+            fs::write(temp_dir.join(name), "1").unwrap();
+        }
+        let rules = SortRules::parse(
+            r#"
+            fallback = "Other/{ext}"
+
+            [[rule]]
+            name = ["*invoice*"]
+            ext = ["pdf"]
+            destination = "Finance/{parent}"
+
+            [[rule]]
+            ext = ["pdf"]
+            destination = "Documents"
+
+            [[rule]]
+            ext = ["jpg", "png"]
+            destination = "Photos/{ext}"
+            "#,
+            &temp_dir,
+            &Cli::default(),
+        )
+        .unwrap();
+        let files = fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .collect();
+
+        let sorted_files = rules.sort(files, &Cli::default()).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        let mut destinations = sorted_files
+            .iter()
+            .flat_map(|(directory, files)| {
+                files
+                    .iter()
+                    .map(move |file| format!("{directory}/{}", file.file_name().to_string_lossy()))
+            })
+            .collect::<Vec<_>>();
+        destinations.sort();
+        assert_eq!(
+            destinations,
+            vec![
+                "Documents/manual.pdf",
+                "Finance/fo_test_sort_rules/invoice_march.pdf",
+                "Other/txt/notes.txt",
+                "Photos/jpg/cat.JPG",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let parse = |content| SortRules::parse(content, Path::new("."), &Cli::default());
+
+        assert!(parse("[[rule]]\next = [\"pdf\"]").is_err());
+        assert!(parse("[[rule]]\nextension = [\"pdf\"]\ndestination = \"x\"").is_err());
+        assert!(parse("[[rule]]\nsize = [\"huge\"]\ndestination = \"x\"").is_err());
+        assert!(parse("fallback = \"../x\"").is_err());
+        assert!(parse("").is_ok());
+    }
+}
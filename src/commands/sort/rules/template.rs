@@ -0,0 +1,264 @@
+use std::{
+    fs::DirEntry,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail};
+use time::OffsetDateTime;
+
+use crate::{cli::Cli, commands::sort::DateLayout, timestamps::FileTimestamp};
+
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+enum Placeholder {
+    /// Lowercase extension, `no_extension` without one
+    Ext,
+    /// Parts of creation date
+    Year,
+    Month,
+    Day,
+    SizeBucket,
+    /// File name without extension
+    Name,
+    /// Name of directory containing the file
+    Parent,
+    /// Date formatted by layout, `%Y-%m-%d` by default. Layout can nest directories.
+    Date(FileTimestamp, DateLayout),
+}
+
+/// Destination path relative to sorted directory, e.g. `Finance/{year}/{month}` or `Photos/{created:%Y}`
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let invalid = |reason: &str| anyhow!("Invalid destination '{source}': {reason}");
+        if source.trim().is_empty() {
+            return Err(invalid("destination is empty"));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| invalid("'{' is not closed"))?;
+            push_text(&mut segments, &rest[..start]);
+            segments.push(Segment::Placeholder(
+                parse_placeholder(&rest[start + 1..end])
+                    .map_err(|err| invalid(&err.to_string()))?,
+            ));
+            rest = &rest[end + 1..];
+        }
+        push_text(&mut segments, rest);
+
+        let text = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Text(text) => Some(text.as_str()),
+                Segment::Placeholder(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if text.iter().any(|text| text.contains('}')) {
+            return Err(invalid("'}' without '{'"));
+        }
+        if source.starts_with('/') || source.split('/').any(|part| part == "..") {
+            return Err(invalid("destination must stay inside the sorted directory"));
+        }
+
+        Ok(Template {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    /// Destination directory of file relative to sorted directory
+    pub fn render(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<PathBuf> {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Placeholder(placeholder @ Placeholder::Date(..)) => {
+                    rendered.push_str(&placeholder.value(file, context)?);
+                }
+                Segment::Placeholder(placeholder) => {
+                    let value = placeholder.value(file, context)?;
+                    rendered.push_str(&path_component(&value));
+                }
+            }
+        }
+
+        let path = PathBuf::from(&rendered);
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!(
+                "Destination '{}' of {} is outside of the sorted directory",
+                rendered,
+                file.path().display()
+            );
+        }
+        Ok(path)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &str) {
+    if !text.is_empty() {
+        segments.push(Segment::Text(text.to_string()));
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> anyhow::Result<Placeholder> {
+    let (name, format) = match placeholder.split_once(':') {
+        Some((name, format)) => (name, Some(format)),
+        None => (placeholder, None),
+    };
+    let timestamp = match name {
+        "created" => Some(FileTimestamp::Created),
+        "modified" => Some(FileTimestamp::Modified),
+        "accessed" => Some(FileTimestamp::Accessed),
+        "changed" => Some(FileTimestamp::Changed),
+        _ => None,
+    };
+    if let Some(timestamp) = timestamp {
+        let layout = match format {
+            Some(format) => DateLayout::parse(format)?,
+            None => DateLayout::default(),
+        };
+        return Ok(Placeholder::Date(timestamp, layout));
+    }
+    if format.is_some() {
+        bail!("only date placeholders have a format, not '{{{placeholder}}}'");
+    }
+
+    Ok(match name {
+        "ext" => Placeholder::Ext,
+        "year" => Placeholder::Year,
+        "month" => Placeholder::Month,
+        "day" => Placeholder::Day,
+        "size_bucket" => Placeholder::SizeBucket,
+        "name" => Placeholder::Name,
+        "parent" => Placeholder::Parent,
+        _ => bail!(
+            "unknown placeholder '{{{name}}}', expected ext, year, month, day, size_bucket, name, parent, created, modified, accessed or changed"
+        ),
+    })
+}
+
+impl Placeholder {
+    fn value(&self, file: &DirEntry, context: &Cli) -> anyhow::Result<String> {
+        let path = file.path();
+        let date = |timestamp: FileTimestamp| -> anyhow::Result<OffsetDateTime> {
+            let time = timestamp.read(&file.metadata()?, context.time_source)?;
+            Ok(context.local_time(time))
+        };
+
+        Ok(match self {
+            Placeholder::Ext => match path.extension() {
+                Some(ext) => ext.to_string_lossy().to_lowercase(),
+                None => "no_extension".to_string(),
+            },
+            Placeholder::Year => format!("{:04}", date(FileTimestamp::Created)?.year()),
+            Placeholder::Month => format!("{:02}", date(FileTimestamp::Created)?.month() as u8),
+            Placeholder::Day => format!("{:02}", date(FileTimestamp::Created)?.day()),
+            Placeholder::SizeBucket => context
+                .config
+                .size_buckets
+                .value
+                .bucket(file.metadata()?.len()),
+            Placeholder::Name => file_stem(&path),
+            Placeholder::Parent => path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            Placeholder::Date(timestamp, layout) => layout.format(date(*timestamp)?),
+        })
+    }
+}
+
+/// Value of placeholder as a single path component, whatever the file name contains
+fn path_component(value: &str) -> String {
+    match value {
+        "." | ".." => value.replace('.', "_"),
+        _ => value.replace(['/', '\\'], "_"),
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("Finance/{year}/{month}" ; "date parts")]
+    #[test_case("Photos/{created:%Y}/{modified:%b %d}" ; "date formats")]
+    #[test_case("{ext}/{size_bucket}/{parent}-{name}" ; "file parts")]
+    #[test_case("Scans/{created:[%Y]}" ; "brackets in format")]
+    #[test_case("Photos/{created:quarter}" ; "layout preset")]
+    fn test_parse_valid(source: &str) {
+        assert!(Template::parse(source).is_ok());
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("Photos/{year" ; "not closed")]
+    #[test_case("Photos}/x" ; "not opened")]
+    #[test_case("{album}" ; "unknown placeholder")]
+    #[test_case("{ext:%Y}" ; "format of non date")]
+    #[test_case("{created:%Q}" ; "unsupported format")]
+    #[test_case("../outside/{ext}" ; "parent dir")]
+    #[test_case("/absolute/{ext}" ; "absolute")]
+    fn test_parse_invalid(source: &str) {
+        assert!(Template::parse(source).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let temp_dir = std::env::temp_dir().join("fo_test_template_render/invoices");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("march.PDF"), "12").unwrap();
+        let file = fs::read_dir(&temp_dir).unwrap().next().unwrap().unwrap();
+        let modified = OffsetDateTime::from(file.metadata().unwrap().modified().unwrap());
+        let template =
+            Template::parse("{parent}/{ext}/{name} {size_bucket}/{modified:%Y/%m}").unwrap();
+
+        let rendered = template.render(&file, &Cli::default()).unwrap();
+        fs::remove_dir_all(temp_dir.parent().unwrap()).unwrap();
+
+        assert_eq!(
+            rendered,
+            PathBuf::from(format!(
+                "invoices/pdf/march 0-99 KB/{}/{:02}",
+                modified.year(),
+                modified.month() as u8
+            ))
+        );
+    }
+
+    #[test_case(".", "_" ; "current dir")]
+    #[test_case("..", "__" ; "parent dir")]
+    #[test_case("a/../b", "a_.._b" ; "separators")]
+    #[test_case("..notes", "..notes" ; "leading dots")]
+    fn test_path_component(value: &str, expected: &str) {
+        assert_eq!(path_component(value), expected);
+    }
+}
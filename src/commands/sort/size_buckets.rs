@@ -0,0 +1,255 @@
+use std::fmt::Display;
+
+use anyhow::{anyhow, bail};
+
+use crate::size::parse_size;
+
+/// Buckets of `--size-buckets` used by default
+pub const DEFAULT_SIZE_BUCKETS: &str = "hundreds";
+
+/// Named schemes of `--size-buckets`, written as own boundaries would be
+const PRESETS: &[(&str, &str)] = &[
+    ("log", "1K,1M,100M"),
+    ("labels", "tiny=100K,small=1M,medium=100M,large=1G,huge"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Scheme {
+    /// 100 units wide buckets, e.g. `100-199 KB`, and one bucket per GB
+    Hundreds,
+    /// File belongs to the first bucket whose upper bound is above its size,
+    /// files above the last bound go to the last label
+    Bounds {
+        bounds: Vec<u64>,
+        labels: Vec<String>,
+    },
+}
+
+/// How `--sort-by size` names its directories, e.g. `1K,1M,100M` gives `<1KiB`, `1KiB-1MiB`, `1MiB-100MiB` and `>100MiB`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeBuckets {
+    source: String,
+    scheme: Scheme,
+}
+
+impl Default for SizeBuckets {
+    fn default() -> Self {
+        SizeBuckets::parse(DEFAULT_SIZE_BUCKETS).expect("default buckets are valid")
+    }
+}
+
+impl SizeBuckets {
+    /// Parse ascending boundaries, e.g. `1M,100M,1G`, boundaries with labels, e.g.
+    /// `small=1M,medium=1G,large`, or one of presets: hundreds, log or labels
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        if source == DEFAULT_SIZE_BUCKETS {
+            return Ok(SizeBuckets {
+                source: source.to_string(),
+                scheme: Scheme::Hundreds,
+            });
+        }
+        let buckets = PRESETS
+            .iter()
+            .find(|(name, _)| *name == source)
+            .map_or(source, |(_, preset)| preset);
+
+        let items = buckets.split(',').map(str::trim).collect::<Vec<_>>();
+        let scheme = if items.iter().any(|item| item.contains('=')) {
+            parse_labeled(&items)
+        } else {
+            parse_bounds(&items)
+        }
+        .map_err(|err| anyhow!("Invalid size buckets '{source}': {err}"))?;
+
+        Ok(SizeBuckets {
+            source: source.to_string(),
+            scheme,
+        })
+    }
+
+    /// Name of size directory of file with `bytes`
+    pub fn bucket(&self, bytes: u64) -> String {
+        match &self.scheme {
+            Scheme::Hundreds => FileSize::from_bytes(bytes).to_string(),
+            Scheme::Bounds { bounds, labels } => {
+                let index = bounds
+                    .iter()
+                    .position(|&bound| bytes < bound)
+                    .unwrap_or(bounds.len());
+                labels[index].clone()
+            }
+        }
+    }
+}
+
+impl Display for SizeBuckets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Boundaries without labels, buckets are named after them, e.g. `1KiB-1MiB`
+fn parse_bounds(items: &[&str]) -> anyhow::Result<Scheme> {
+    let bounds = items
+        .iter()
+        .map(|item| parse_size(item))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    check_ascending(&bounds)?;
+
+    let names = items
+        .iter()
+        .map(|item| bound_name(item))
+        .collect::<Vec<_>>();
+    let mut labels = vec![format!("<{}", names[0])];
+    labels.extend(
+        names
+            .windows(2)
+            .map(|pair| format!("{}-{}", pair[0], pair[1])),
+    );
+    labels.push(format!(">{}", names[names.len() - 1]));
+
+    Ok(Scheme::Bounds { bounds, labels })
+}
+
+/// Labels with upper bounds, e.g. `small=1M`, the last label without bound takes the rest
+fn parse_labeled(items: &[&str]) -> anyhow::Result<Scheme> {
+    let (last, labeled) = items.split_last().expect("split gives at least one item");
+    if last.contains('=') {
+        bail!("the last bucket takes all larger files and has no bound, e.g. 'small=1M,large'");
+    }
+
+    let mut bounds = Vec::new();
+    let mut labels = Vec::new();
+    for item in labeled {
+        let Some((label, bound)) = item.split_once('=') else {
+            bail!("bucket '{item}' has no bound, only the last bucket goes without one");
+        };
+        labels.push(label.trim().to_string());
+        bounds.push(parse_size(bound)?);
+    }
+    labels.push(last.to_string());
+    check_ascending(&bounds)?;
+
+    for (index, label) in labels.iter().enumerate() {
+        if label.is_empty() || label == "." || label == ".." || label.contains(['/', '\\']) {
+            bail!("label '{label}' is not a valid directory name");
+        }
+        if labels[..index].contains(label) {
+            bail!("label '{label}' is used more than once");
+        }
+    }
+
+    Ok(Scheme::Bounds { bounds, labels })
+}
+
+fn check_ascending(bounds: &[u64]) -> anyhow::Result<()> {
+    if bounds.contains(&0) {
+        bail!("bounds must be above zero");
+    }
+    if bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+        bail!("bounds must be in ascending order");
+    }
+    Ok(())
+}
+
+/// Bound as written, with single letter binary unit spelled out, e.g. `1M` to `1MiB`,
+/// so it doesn't read as decimal `1MB`
+fn bound_name(bound: &str) -> String {
+    match bound.strip_suffix(|unit: char| "KMGTkmgt".contains(unit)) {
+        Some(number) => format!("{number}{}iB", bound[number.len()..].to_uppercase()),
+        None => bound.to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum FileSize {
+    KB(u64),
+    MB(u64),
+    GB(u64),
+}
+
+impl FileSize {
+    fn from_bytes(bytes: u64) -> Self {
+        if bytes < 1_048_576 {
+            FileSize::KB(bytes / 1024)
+        } else if bytes < 1_073_741_824 {
+            FileSize::MB(bytes / 1024 / 1024)
+        } else {
+            FileSize::GB(bytes / 1024 / 1024 / 1024)
+        }
+    }
+}
+
+impl Display for FileSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileSize::KB(size) => write!(f, "{}-{} KB", size / 100 * 100, size / 100 * 100 + 99),
+            FileSize::MB(size) => write!(f, "{}-{} MB", size / 100 * 100, size / 100 * 100 + 99),
+            FileSize::GB(size) => write!(f, "{} GB", size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(1023, FileSize::KB(0))]
+    #[test_case(1024, FileSize::KB(1))]
+    #[test_case(1_048_575, FileSize::KB(1023))]
+    #[test_case(1_048_576, FileSize::MB(1))]
+    #[test_case(1_073_741_823, FileSize::MB(1023))]
+    #[test_case(1_073_741_824, FileSize::GB(1))]
+    #[test_case(10_737_418_240, FileSize::GB(10))]
+    fn test_file_size_from_bytes(size: u64, expected: FileSize) {
+        let file_size = FileSize::from_bytes(size);
+        assert_eq!(file_size, expected);
+    }
+
+    #[test_case(FileSize::KB(1), "0-99 KB")]
+    #[test_case(FileSize::KB(500), "500-599 KB")]
+    #[test_case(FileSize::KB(1023), "1000-1099 KB")]
+    #[test_case(FileSize::MB(1), "0-99 MB")]
+    #[test_case(FileSize::MB(500), "500-599 MB")]
+    #[test_case(FileSize::MB(1023), "1000-1099 MB")]
+    #[test_case(FileSize::GB(1), "1 GB")]
+    #[test_case(FileSize::GB(10), "10 GB")]
+    fn test_file_size_display(size: FileSize, expected: &str) {
+        let display = size.to_string();
+        assert_eq!(display, expected);
+    }
+
+    #[test_case("hundreds", 150 * 1024, "100-199 KB")]
+    #[test_case("log", 512, "<1KiB")]
+    #[test_case("log", 1024, "1KiB-1MiB")]
+    #[test_case("log", 50 * 1024 * 1024, "1MiB-100MiB")]
+    #[test_case("log", 5 * 1024 * 1024 * 1024, ">100MiB")]
+    #[test_case("labels", 10, "tiny")]
+    #[test_case("labels", 500 * 1024, "small")]
+    #[test_case("labels", 2 * 1024 * 1024 * 1024, "huge")]
+    #[test_case("1m, 100M,1GB", 200 * 1024 * 1024, "100MiB-1GB" ; "own bounds")]
+    #[test_case("1M,1G", 1_000_000, "<1MiB" ; "binary bound")]
+    #[test_case("1MB,1G", 1_000_000, "1MB-1GiB" ; "decimal bound")]
+    #[test_case("photo=20M,video", 20 * 1024 * 1024, "video" ; "own labels")]
+    fn test_bucket(buckets: &str, bytes: u64, expected: &str) {
+        let buckets = SizeBuckets::parse(buckets).unwrap();
+
+        assert_eq!(buckets.bucket(bytes), expected);
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("1M,,1G" ; "empty bound")]
+    #[test_case("1G,1M" ; "descending")]
+    #[test_case("0,1M" ; "zero")]
+    #[test_case("10X" ; "unknown unit")]
+    #[test_case("small=1M,large=1G" ; "last with bound")]
+    #[test_case("small=1M,1G,large" ; "middle without label")]
+    #[test_case("small=1M,small" ; "duplicate label")]
+    #[test_case("a/b=1M,large" ; "nested label")]
+    #[test_case("..=1M,large" ; "parent label")]
+    fn test_parse_invalid(buckets: &str) {
+        assert!(SizeBuckets::parse(buckets).is_err());
+    }
+}
@@ -1,23 +1,99 @@
 use std::{collections::HashMap, fs::DirEntry};
 
 use clap::ValueEnum;
+use serde::Deserialize;
 
-use crate::commands::sort::{sort_by_date, sort_by_extension, sort_by_size};
+use crate::{
+    cli::Cli,
+    commands::sort::{
+        DateLayout, SizeBuckets, sort_by_content, sort_by_date, sort_by_extension, sort_by_size,
+    },
+    timestamps::{FileTimestamp, TimeSource},
+};
 
-#[derive(Clone, Debug, Default, ValueEnum, Copy)]
+#[derive(Clone, Debug, Default, ValueEnum, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum SortBy {
     Size,
     Ext,
+    /// Creation date
     #[default]
     Date,
+    /// Modification date (mtime)
+    Modified,
+    /// Last access date (atime)
+    Accessed,
+    /// Inode change date (ctime), Unix only
+    Changed,
+    /// MIME type detected from content, e.g. image/png
+    Type,
+    /// Kind of content: Images, Videos, Audio, Documents, Archives, Code or Executables
+    Category,
 }
 
-impl SortBy {
-    pub fn sort(self, files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
+/// Where date sorts take dates from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum DateSource {
+    /// Birth time only
+    Birth,
+    /// Modification time
+    Mtime,
+    /// Birth time, falling back to modification time
+    #[default]
+    BirthOrMtime,
+    /// Date photo or video was taken from EXIF or MP4/MOV metadata, falling back to filesystem time
+    Media,
+}
+
+impl From<TimeSource> for DateSource {
+    fn from(source: TimeSource) -> Self {
+        match source {
+            TimeSource::Birth => DateSource::Birth,
+            TimeSource::Mtime => DateSource::Mtime,
+            TimeSource::BirthOrMtime => DateSource::BirthOrMtime,
+        }
+    }
+}
+
+impl DateSource {
+    /// Source of creation time among filesystem timestamps, `None` for media metadata
+    pub fn time_source(self) -> Option<TimeSource> {
         match self {
-            SortBy::Size => sort_by_size(files),
-            SortBy::Ext => sort_by_extension(files),
-            SortBy::Date => sort_by_date(files),
+            DateSource::Birth => Some(TimeSource::Birth),
+            DateSource::Mtime => Some(TimeSource::Mtime),
+            DateSource::BirthOrMtime => Some(TimeSource::BirthOrMtime),
+            DateSource::Media => None,
         }
     }
 }
+
+/// How sorts name their directories
+#[derive(Debug, Default)]
+pub struct Grouping {
+    pub layout: DateLayout,
+    pub source: DateSource,
+    pub size_buckets: SizeBuckets,
+}
+
+impl SortBy {
+    pub fn sort(
+        self,
+        files: Vec<DirEntry>,
+        grouping: &Grouping,
+        context: &Cli,
+    ) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
+        let timestamp = match self {
+            SortBy::Size => return sort_by_size(files, &grouping.size_buckets),
+            SortBy::Ext => return sort_by_extension(files),
+            SortBy::Type => return sort_by_content(files, |mime| mime.name.to_string()),
+            SortBy::Category => {
+                return sort_by_content(files, |mime| mime.category.to_string());
+            }
+            SortBy::Date => FileTimestamp::Created,
+            SortBy::Modified => FileTimestamp::Modified,
+            SortBy::Accessed => FileTimestamp::Accessed,
+            SortBy::Changed => FileTimestamp::Changed,
+        };
+        sort_by_date(files, timestamp, &grouping.layout, grouping.source, context)
+    }
+}
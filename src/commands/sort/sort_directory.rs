@@ -3,14 +3,31 @@ use std::{collections::HashMap, fs::DirEntry};
 use anyhow::bail;
 
 use super::FilesList;
-use crate::commands::sort::SortBy;
+use crate::{
+    cli::Cli,
+    commands::sort::{Grouping, SortBy, rules::SortRules},
+};
 
-// Key is a directory name (e.g. "txt" for extension or "1KB-10KB" for size), value is a list of files in that directory
+// Key is a directory name (e.g. "txt" for extension or "1KiB-10KiB" for size), value is a list of files in that directory.
+// Rules can give nested directories, e.g. "Finance/2025/03"
 pub type SortedFiles = HashMap<String, Vec<DirEntry>>;
 
-pub fn sort_directory(files_list: FilesList, sort_by: SortBy) -> anyhow::Result<SortedFiles> {
+/// How files of a directory are split into sorted directories
+pub enum Sorter {
+    By(SortBy, Grouping),
+    Rules(SortRules),
+}
+
+pub fn sort_directory(
+    files_list: FilesList,
+    sorter: &Sorter,
+    context: &Cli,
+) -> anyhow::Result<SortedFiles> {
     let files = collect_directories(files_list)?;
-    let sorted_files = sort_by.sort(files)?;
+    let sorted_files = match sorter {
+        Sorter::By(sort_by, grouping) => sort_by.sort(files, grouping, context)?,
+        Sorter::Rules(rules) => rules.sort(files, context)?,
+    };
     Ok(sorted_files)
 }
 
@@ -37,8 +54,12 @@ mod tests {
 
     #[test]
     fn test_collect_directories() {
-        let file_list =
-            FilesList::new(&get_path_under_tests("sort_directory"), false, false).unwrap();
+        let directory = get_path_under_tests("sort_directory");
+        // Git doesn't keep empty directories
+        for inner in ["inner_dir_1", "inner_dir_2"] {
+            std::fs::create_dir_all(directory.join(inner)).unwrap();
+        }
+        let file_list = FilesList::new(&directory, false, false).unwrap();
         let files = super::collect_directories(file_list).unwrap();
         let files_names: Vec<String> = files
             .into_iter()
@@ -1,13 +1,22 @@
-use std::{collections::HashMap, fmt::Display, fs::DirEntry};
-
-use time::OffsetDateTime;
+use std::{collections::HashMap, fs::DirEntry};
+
+use crate::{
+    cli::Cli,
+    commands::sort::{DateLayout, DateSource, SizeBuckets},
+    media,
+    mime::MimeType,
+    timestamps::FileTimestamp,
+};
+use anyhow::Context;
+use log::warn;
 
 pub fn sort_by_extension(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
     let mut sorted_files = HashMap::new();
 
     for file in files {
         let extension = match file.path().extension() {
-            Some(ext) => ext.to_string_lossy().into_owned(),
+            // Group "JPG" and "jpg" into the same directory
+            Some(ext) => ext.to_string_lossy().to_lowercase(),
             None => "no_extension".to_string(),
         };
 
@@ -20,12 +29,29 @@ pub fn sort_by_extension(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String,
     Ok(sorted_files)
 }
 
-pub fn sort_by_date(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
+/// Sort by date in local timezone, `layout` can nest directories, e.g. `2025/06/14`
+pub fn sort_by_date(
+    files: Vec<DirEntry>,
+    timestamp: FileTimestamp,
+    layout: &DateLayout,
+    source: DateSource,
+    context: &Cli,
+) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
     let mut sorted_files = HashMap::new();
 
     for file in files {
-        let created_time = file.metadata()?.created()?;
-        let date = format!("{:?}", OffsetDateTime::from(created_time).date());
+        let taken = match source {
+            DateSource::Media => media::taken_date(&file.path(), context.local_offset)?,
+            _ => None,
+        };
+        let date = match taken {
+            Some(taken) => taken.to_offset(context.local_offset),
+            None => {
+                let time_source = source.time_source().unwrap_or(context.time_source);
+                context.local_time(timestamp.read(&file.metadata()?, time_source)?)
+            }
+        };
+        let date = layout.format(date);
 
         sorted_files.entry(date).or_insert_with(Vec::new).push(file);
     }
@@ -33,12 +59,15 @@ pub fn sort_by_date(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<
     Ok(sorted_files)
 }
 
-pub fn sort_by_size(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
+pub fn sort_by_size(
+    files: Vec<DirEntry>,
+    buckets: &SizeBuckets,
+) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
     let mut sorted_files = HashMap::new();
 
     for file in files {
         let file_bytes = file.metadata()?.len();
-        let size = FileSize::from_bytes(file_bytes).to_string();
+        let size = buckets.bucket(file_bytes);
 
         sorted_files.entry(size).or_insert_with(Vec::new).push(file);
     }
@@ -46,33 +75,32 @@ pub fn sort_by_size(files: Vec<DirEntry>) -> anyhow::Result<HashMap<String, Vec<
     Ok(sorted_files)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum FileSize {
-    KB(u64),
-    MB(u64),
-    GB(u64),
-}
+/// Sort by type detected from file content, `directory` names the directory of a type,
+/// e.g. `image/png` or `Images`. Files with extension of another type are reported.
+pub fn sort_by_content(
+    files: Vec<DirEntry>,
+    directory: impl Fn(&MimeType) -> String,
+) -> anyhow::Result<HashMap<String, Vec<DirEntry>>> {
+    let mut sorted_files = HashMap::new();
 
-impl FileSize {
-    fn from_bytes(bytes: u64) -> Self {
-        if bytes < 1_048_576 {
-            FileSize::KB(bytes / 1024)
-        } else if bytes < 1_073_741_824 {
-            FileSize::MB(bytes / 1024 / 1024)
-        } else {
-            FileSize::GB(bytes / 1024 / 1024 / 1024)
+    for file in files {
+        let path = file.path();
+        let mime = MimeType::detect(&path)
+            .with_context(|| format!("Failed to detect type of {}", path.display()))?;
+        if let Some(extension) = mime.contradicted_extension(&path) {
+            warn!(
+                "{} has extension '{extension}', but its content is {mime}",
+                path.display()
+            );
         }
-    }
-}
 
-impl Display for FileSize {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            FileSize::KB(size) => write!(f, "{}-{} KB", size / 100 * 100, size / 100 * 100 + 99),
-            FileSize::MB(size) => write!(f, "{}-{} MB", size / 100 * 100, size / 100 * 100 + 99),
-            FileSize::GB(size) => write!(f, "{} GB", size),
-        }
+        sorted_files
+            .entry(directory(mime))
+            .or_insert_with(Vec::new)
+            .push(file);
     }
+
+    Ok(sorted_files)
 }
 
 #[cfg(test)]
@@ -80,7 +108,6 @@ mod tests {
     use crate::{commands::sort::FilesList, volumes::get_path_under_tests};
 
     use super::*;
-    use test_case::test_case;
 
     #[test]
     fn test_sort_by_extension() {
@@ -102,20 +129,82 @@ mod tests {
         assert!(sorted_files.contains_key("no_extension"));
     }
 
-    // Note: This test relies on the file creation time, that can be tricky to set up in a test environment.
-    // If you want to test this, you should change "2026-02-14" to the actual creation date of the file in the "sorters/sort_by_date" directory.
-    // fn test_sort_by_date() {
-    //     let file_list =
-    //         FilesList::new(&get_path_under_tests("sorters/sort_by_date"), false, false).unwrap();
-    //     let files = file_list
-    //         .into_iter()
-    //         .map(|res| res.unwrap())
-    //         .collect::<Vec<DirEntry>>();
-
-    //     let sorted_files = sort_by_date(files).unwrap();
-    //     assert_eq!(sorted_files.len(), 1);
-    //     assert!(sorted_files.contains_key("2026-02-14"));
-    // }
+    #[test]
+    fn test_sort_by_extension_ignores_case() {
+        let temp_dir = std::env::temp_dir().join("fo_test_sort_by_extension_ignores_case");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("photo.JPG"), "").unwrap();
+        std::fs::write(temp_dir.join("image.jpg"), "").unwrap();
+
+        let files = FilesList::new(&temp_dir, false, false)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect::<Vec<DirEntry>>();
+        let sorted_files = sort_by_extension(files).unwrap();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(sorted_files.len(), 1);
+        assert_eq!(sorted_files["jpg"].len(), 2);
+    }
+
+    #[test]
+    fn test_sort_by_modified_date() {
+        let temp_dir = std::env::temp_dir().join("fo_test_sort_by_modified_date");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let file_path = temp_dir.join("file.txt");
+        let file = std::fs::File::create(&file_path).unwrap();
+        let modified =
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_577_934_000); // 2020-01-02 03:00 UTC
+        file.set_modified(modified).unwrap();
+
+        let files = FilesList::new(&temp_dir, false, false)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect::<Vec<DirEntry>>();
+
+        let sorted_files = sort_by_date(
+            files,
+            FileTimestamp::Modified,
+            &DateLayout::default(),
+            DateSource::Birth,
+            &Cli::default(),
+        )
+        .unwrap();
+        assert_eq!(sorted_files.len(), 1);
+        assert!(sorted_files.contains_key("2020-01-02"));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[test]
+    fn test_sort_by_date_in_local_timezone() {
+        let temp_dir = std::env::temp_dir().join("fo_test_sort_by_date_in_local_timezone");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        let file = std::fs::File::create(temp_dir.join("photo.jpg")).unwrap();
+        let modified =
+            std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_577_847_600); // 2020-01-01 03:00 UTC
+        file.set_modified(modified).unwrap();
+        let context = Cli {
+            local_offset: time::UtcOffset::from_hms(-5, 0, 0).unwrap(),
+            ..Default::default()
+        };
+
+        let files = FilesList::new(&temp_dir, false, false)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect::<Vec<DirEntry>>();
+        let sorted_files = sort_by_date(
+            files,
+            FileTimestamp::Modified,
+            &DateLayout::parse("quarter").unwrap(),
+            DateSource::Mtime,
+            &context,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(sorted_files.keys().collect::<Vec<_>>(), vec!["2019/Q4"]);
+    }
 
     #[test]
     fn test_sort_by_size() {
@@ -126,34 +215,29 @@ mod tests {
             .map(|res| res.unwrap())
             .collect::<Vec<DirEntry>>();
 
-        let sorted_files = sort_by_size(files).unwrap();
+        let sorted_files = sort_by_size(files, &SizeBuckets::default()).unwrap();
         assert_eq!(sorted_files.len(), 2);
         assert!(sorted_files.contains_key("0-99 KB"));
         assert!(sorted_files.contains_key("100-199 KB"));
     }
 
-    #[test_case(1023, FileSize::KB(0))]
-    #[test_case(1024, FileSize::KB(1))]
-    #[test_case(1_048_575, FileSize::KB(1023))]
-    #[test_case(1_048_576, FileSize::MB(1))]
-    #[test_case(1_073_741_823, FileSize::MB(1023))]
-    #[test_case(1_073_741_824, FileSize::GB(1))]
-    #[test_case(10_737_418_240, FileSize::GB(10))]
-    fn test_file_size_from_bytes(size: u64, expected: FileSize) {
-        let file_size = FileSize::from_bytes(size);
-        assert_eq!(file_size, expected);
-    }
+    #[test]
+    fn test_sort_by_content() {
+        let temp_dir = std::env::temp_dir().join("fo_test_sort_by_content");
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        std::fs::write(temp_dir.join("scan"), "%PDF-1.4").unwrap();
+        std::fs::write(temp_dir.join("photo.jpg"), "%PDF-1.4").unwrap();
+        std::fs::write(temp_dir.join("main.rs"), "fn main() {}").unwrap();
+
+        let files = FilesList::new(&temp_dir, false, false)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect::<Vec<DirEntry>>();
+        let sorted_files = sort_by_content(files, |mime| mime.category.to_string()).unwrap();
+        std::fs::remove_dir_all(&temp_dir).unwrap();
 
-    #[test_case(FileSize::KB(1), "0-99 KB")]
-    #[test_case(FileSize::KB(500), "500-599 KB")]
-    #[test_case(FileSize::KB(1023), "1000-1099 KB")]
-    #[test_case(FileSize::MB(1), "0-99 MB")]
-    #[test_case(FileSize::MB(500), "500-599 MB")]
-    #[test_case(FileSize::MB(1023), "1000-1099 MB")]
-    #[test_case(FileSize::GB(1), "1 GB")]
-    #[test_case(FileSize::GB(10), "10 GB")]
-    fn test_file_size_display(size: FileSize, expected: &str) {
-        let display = size.to_string();
-        assert_eq!(display, expected);
+        assert_eq!(sorted_files.len(), 2);
+        assert_eq!(sorted_files["Documents"].len(), 2);
+        assert_eq!(sorted_files["Code"].len(), 1);
     }
 }
@@ -0,0 +1,132 @@
+use std::{fmt::Display, path::Path};
+
+use anstream::println;
+use anyhow::bail;
+use clap::{Parser, Subcommand};
+use log::{debug, info, trace, warn};
+use owo_colors::OwoColorize;
+
+use crate::{
+    cli::Cli,
+    confirmation::confirm,
+    trash::{Trash, TrashedItem},
+};
+
+#[derive(Parser, Debug)]
+pub struct TrashCommand {
+    #[command(subcommand)]
+    action: TrashAction,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+enum TrashAction {
+    #[command(about = "List trashed files of all volumes")]
+    List,
+    #[command(about = "Move trashed files back to where they were deleted from")]
+    Restore {
+        #[arg(
+            required = true,
+            help = "Name in trash or original path. For a path trashed several times the latest one is restored"
+        )]
+        items: Vec<String>,
+    },
+    #[command(about = "Permanently delete all trashed files")]
+    Empty,
+}
+
+impl TrashCommand {
+    pub fn execute(&self, _context: &Cli) -> anyhow::Result<()> {
+        debug!("Executing 'trash' command");
+        trace!("with configuration: {self}");
+
+        let items = trashed_items()?;
+        match &self.action {
+            TrashAction::List => print_items(&items),
+            TrashAction::Restore { items: queries } => {
+                for query in queries {
+                    let Some(item) = find_item(&items, query)? else {
+                        bail!("'{query}' is not in trash");
+                    };
+                    item.restore()?;
+                    println!("{} {}", "Restored:".bright_green(), item.original.display());
+                }
+            }
+            TrashAction::Empty => {
+                if items.is_empty() {
+                    println!("{}", "Trash is empty".bright_green());
+                    return Ok(());
+                }
+                if !confirm(&format!(
+                    "This will permanently delete {} trashed item(s). Are you sure you want to proceed?",
+                    items.len()
+                ))? {
+                    debug!("User declined to empty trash. Aborting command execution.");
+                    return Ok(());
+                }
+
+                let mut purged = 0;
+                for item in &items {
+                    info!("{} {}", "Purging:".bright_red(), item.path.display());
+                    match item.purge() {
+                        Ok(()) => purged += 1,
+                        Err(e) => warn!("Failed to purge {}: {}", item.path.display(), e),
+                    }
+                }
+                println!(
+                    "{} {} item(s) deleted",
+                    "Trash emptied:".bold(),
+                    purged.bright_green()
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Items of all trashes, oldest first
+fn trashed_items() -> anyhow::Result<Vec<TrashedItem>> {
+    let mut items = Vec::new();
+    for trash in Trash::all()? {
+        match trash.items() {
+            Ok(trash_items) => items.extend(trash_items),
+            Err(e) => warn!("Failed to read trash {}: {}", trash.root().display(), e),
+        }
+    }
+    items.sort_by(|a, b| a.deleted_at.cmp(&b.deleted_at));
+    Ok(items)
+}
+
+/// Item with given trash name, or the latest item trashed from given path
+fn find_item<'a>(items: &'a [TrashedItem], query: &str) -> anyhow::Result<Option<&'a TrashedItem>> {
+    if let Some(item) = items.iter().find(|item| item.name == query) {
+        return Ok(Some(item));
+    }
+    let original = std::path::absolute(Path::new(query))?;
+    Ok(items.iter().rev().find(|item| item.original == original))
+}
+
+fn print_items(items: &[TrashedItem]) {
+    for item in items {
+        println!(
+            "{} {} {}",
+            item.deleted_at.replace('T', " ").bright_purple(),
+            item.name.bright_cyan(),
+            item.original.display()
+        );
+    }
+
+    println!();
+    println!(
+        "{} {}",
+        "Trashed items:".bright_green(),
+        items.len().to_string().bright_purple()
+    );
+}
+
+impl Display for TrashCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {:?}", "action".bright_cyan(), self.action)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+use std::fmt::Display;
+
+use anstream::println;
+use anyhow::{anyhow, bail};
+use clap::Parser;
+use log::{debug, trace, warn};
+use owo_colors::OwoColorize;
+
+use crate::{
+    cli::Cli,
+    commands::plan::{Operation, Plan, PlanFormat},
+    config::Config,
+    confirmation::confirm,
+    journal::{Journal, Run},
+};
+
+#[derive(Parser, Debug, Default)]
+pub struct UndoCommand {
+    #[arg(help = "Id of the run to undo, as shown by 'fo history'. Defaults to the last run")]
+    run_id: Option<String>,
+    #[arg(
+        long,
+        help = "Print operations that would revert the run without touching any files"
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "tree",
+        help = "How planned operations are printed by --dry-run"
+    )]
+    plan_format: PlanFormat,
+}
+
+impl UndoCommand {
+    pub fn configure(&mut self, config: &Config) {
+        self.plan_format = config.plan_format.value;
+    }
+
+    pub fn execute(&self, _context: &Cli) -> anyhow::Result<()> {
+        debug!("Executing 'undo' command");
+        trace!("with configuration: {self}");
+
+        let journal = Journal::open()?;
+        let runs = journal.runs()?;
+        let run = select_run(&runs, self.run_id.as_deref())?;
+        debug!("Undoing run {}: {}", run.id, run.command_line);
+
+        let plan = inverse_plan(run)?;
+        if self.dry_run {
+            plan.print(self.plan_format);
+            return Ok(());
+        }
+
+        if plan.is_empty() {
+            println!("{}", "Nothing to undo".bright_green());
+            return Ok(());
+        }
+
+        if !confirm(&format!(
+            "This will revert {} operation(s) of '{}'. Are you sure you want to proceed?",
+            plan.operations().len(),
+            run.command_line
+        ))? {
+            debug!("User declined to undo run. Aborting command execution.");
+            return Ok(());
+        }
+
+        let mut recorder = journal.start_run()?;
+        recorder.mark_undoing(&run.id)?;
+        let summary = plan.execute(&mut recorder)?;
+        recorder.mark_undone(&run.id)?;
+
+        println!(
+            "\n{}\n  {} file(s) restored\n  {} file(s) deleted\n  {} directory(ies) recreated",
+            "Undo summary:".bold(),
+            summary.transferred_files.bright_green(),
+            summary.deleted_files.bright_green(),
+            summary.created_dirs.bright_green(),
+        );
+
+        Ok(())
+    }
+}
+
+/// Run with given id, or the last run that was neither undone nor an undo itself.
+/// Run whose undo failed partway is selected again, so the undo can be finished.
+fn select_run<'a>(runs: &'a [Run], run_id: Option<&str>) -> anyhow::Result<&'a Run> {
+    let is_undo = |run: &Run| {
+        run.undoes.is_some()
+            || runs
+                .iter()
+                .any(|other| other.undone_by.as_deref() == Some(run.id.as_str()))
+    };
+
+    let run = match run_id {
+        Some(run_id) => runs
+            .iter()
+            .find(|run| run.id == run_id)
+            .ok_or_else(|| anyhow!("No run with id '{run_id}' in journal"))?,
+        None => runs
+            .iter()
+            .rev()
+            .find(|run| run.undone_by.is_none() && !is_undo(run))
+            .ok_or_else(|| anyhow!("No runs to undo"))?,
+    };
+
+    if let Some(undo_run_id) = &run.undone_by {
+        bail!("Run '{}' was already undone by '{undo_run_id}'", run.id);
+    }
+    if is_undo(run) {
+        bail!("Run '{}' is an undo itself and can't be undone", run.id);
+    }
+    Ok(run)
+}
+
+/// Operations that revert the run, in reverse order.
+/// Operations already reverted by an undo that failed partway are left out.
+fn inverse_plan(run: &Run) -> anyhow::Result<Plan> {
+    let mut plan = Plan::new();
+    for operation in run.operations.iter().rev() {
+        match operation {
+            Operation::CreateDir { path } => {
+                if path.exists() {
+                    plan.push(Operation::RemoveDir { path: path.clone() });
+                }
+            }
+            Operation::RemoveDir { path } => {
+                if !path.exists() {
+                    plan.push(Operation::CreateDir { path: path.clone() });
+                }
+            }
+            Operation::Move { from, to, .. } if from.exists() && !to.exists() => {}
+            Operation::Move { from, to, size } => {
+                if from.exists() {
+                    bail!(
+                        "Can't move {} back, {} already exists",
+                        to.display(),
+                        from.display()
+                    );
+                }
+                plan.push(Operation::Move {
+                    from: to.clone(),
+                    to: from.clone(),
+                    size: *size,
+                });
+            }
+            Operation::Copy { to, .. } if !to.exists() => {}
+            Operation::Copy { to, size, .. } => plan.push(Operation::Delete {
+                path: to.clone(),
+                size: *size,
+                is_dir: false,
+            }),
+            // Hardlink shares content with original, so it's unlinked before copying,
+            // otherwise the copy would truncate the original
+            Operation::Hardlink {
+                original,
+                path,
+                size,
+            } => {
+                if path.exists() {
+                    plan.push(Operation::Delete {
+                        path: path.clone(),
+                        size: *size,
+                        is_dir: false,
+                    });
+                }
+                plan.push(Operation::Copy {
+                    from: original.clone(),
+                    to: path.clone(),
+                    size: *size,
+                });
+            }
+            Operation::Trash {
+                path,
+                trashed: Some(trashed),
+                ..
+            } if path.exists() && !trashed.exists() => {}
+            Operation::Trash {
+                path,
+                size,
+                trashed: Some(trashed),
+                ..
+            } => plan.push(Operation::Restore {
+                from: trashed.clone(),
+                to: path.clone(),
+                size: *size,
+            }),
+            Operation::Restore { to, .. } if !to.exists() => {}
+            Operation::Restore { to, size, .. } => plan.push(Operation::Trash {
+                path: to.clone(),
+                size: *size,
+                is_dir: to.is_dir(),
+                trashed: None,
+            }),
+            Operation::Delete { path, .. } | Operation::Trash { path, .. } => {
+                warn!(
+                    "{} was permanently deleted and can't be restored, use '--trash' to make deletions undoable",
+                    path.display()
+                );
+            }
+        }
+    }
+    Ok(plan)
+}
+
+impl Display for UndoCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {:?}", "run_id".bright_cyan(), self.run_id)?;
+        writeln!(f, "{}: {}", "dry_run".bright_cyan(), self.dry_run)?;
+        writeln!(f, "{}: {:?}", "plan_format".bright_cyan(), self.plan_format)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn run(id: &str, undone_by: Option<&str>, operations: Vec<Operation>) -> Run {
+        Run {
+            id: id.to_string(),
+            started_at: time::OffsetDateTime::UNIX_EPOCH,
+            command_line: format!("fo {id}"),
+            operations,
+            undone_by: undone_by.map(str::to_string),
+            undoes: None,
+        }
+    }
+
+    #[test]
+    fn test_select_run_skips_undone_and_undo_runs() {
+        let runs = vec![
+            run("sort", None, Vec::new()),
+            run("clean", Some("undo"), Vec::new()),
+            run("undo", None, Vec::new()),
+        ];
+
+        assert_eq!(select_run(&runs, None).unwrap().id, "sort");
+        assert!(select_run(&runs, Some("clean")).is_err());
+        assert!(select_run(&runs, Some("undo")).is_err());
+        assert!(select_run(&runs, Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_undo_sort_restores_files() {
+        let temp_dir = std::env::temp_dir().join("fo_test_undo_sort");
+        let source = temp_dir.join("source");
+        let target = temp_dir.join("source_sorted");
+        fs::create_dir_all(source.join("inner")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("inner/b.txt"), "b").unwrap();
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+
+        let mut sort = Plan::new();
+        sort.push(Operation::CreateDir {
+            path: target.clone(),
+        });
+        sort.push(Operation::CreateDir {
+            path: target.join("txt"),
+        });
+        sort.push(Operation::Move {
+            from: source.join("a.txt"),
+            to: target.join("txt/a.txt"),
+            size: 1,
+        });
+        sort.push(Operation::Move {
+            from: source.join("inner/b.txt"),
+            to: target.join("txt/b.txt"),
+            size: 1,
+        });
+        sort.push(Operation::RemoveDir {
+            path: source.join("inner"),
+        });
+        sort.execute(&mut journal.start_run().unwrap()).unwrap();
+
+        let runs = journal.runs().unwrap();
+        let undo = inverse_plan(&runs[0]).unwrap();
+        undo.execute(&mut journal.start_run().unwrap()).unwrap();
+
+        let restored = source.join("a.txt").exists() && source.join("inner/b.txt").exists();
+        let target_removed = !target.exists();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(restored);
+        assert!(target_removed);
+    }
+
+    #[test]
+    fn test_failed_undo_is_finished_by_retry() {
+        let temp_dir = std::env::temp_dir().join("fo_test_undo_retry");
+        let source = temp_dir.join("source");
+        let target = temp_dir.join("source_sorted");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("b.txt"), "b").unwrap();
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+
+        let mut sort = Plan::new();
+        sort.push(Operation::CreateDir {
+            path: target.clone(),
+        });
+        for name in ["a.txt", "b.txt"] {
+            sort.push(Operation::Move {
+                from: source.join(name),
+                to: target.join(name),
+                size: 1,
+            });
+        }
+        sort.execute(&mut journal.start_run().unwrap()).unwrap();
+
+        // b.txt is moved back first, then a.txt can't be found
+        fs::rename(target.join("a.txt"), temp_dir.join("a.txt")).unwrap();
+        let runs = journal.runs().unwrap();
+        let mut recorder = journal.start_run().unwrap();
+        recorder.mark_undoing(&runs[0].id).unwrap();
+        let failed = inverse_plan(&runs[0]).unwrap().execute(&mut recorder);
+        drop(recorder);
+        fs::rename(temp_dir.join("a.txt"), target.join("a.txt")).unwrap();
+
+        let runs = journal.runs().unwrap();
+        let selected = select_run(&runs, None).unwrap().id.clone();
+        let retry = inverse_plan(&runs[0]).unwrap();
+        retry.execute(&mut journal.start_run().unwrap()).unwrap();
+
+        let restored = source.join("a.txt").exists() && source.join("b.txt").exists();
+        let target_removed = !target.exists();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(failed.is_err());
+        assert_eq!(selected, runs[0].id);
+        assert_eq!(retry.operations().len(), 2);
+        assert!(restored);
+        assert!(target_removed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_hardlink_keeps_original() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = std::env::temp_dir().join("fo_test_undo_hardlink");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let original = temp_dir.join("original.txt");
+        let duplicate = temp_dir.join("duplicate.txt");
+        fs::write(&original, "content").unwrap();
+        fs::write(&duplicate, "content").unwrap();
+        let journal = Journal::at(temp_dir.join("journal.jsonl"));
+
+        let mut dupes = Plan::new();
+        dupes.push(Operation::Hardlink {
+            original: original.clone(),
+            path: duplicate.clone(),
+            size: 7,
+        });
+        dupes.execute(&mut journal.start_run().unwrap()).unwrap();
+
+        let runs = journal.runs().unwrap();
+        inverse_plan(&runs[0])
+            .unwrap()
+            .execute(&mut journal.start_run().unwrap())
+            .unwrap();
+
+        let separate =
+            fs::metadata(&original).unwrap().ino() != fs::metadata(&duplicate).unwrap().ino();
+        let contents = (
+            fs::read_to_string(&original).unwrap(),
+            fs::read_to_string(&duplicate).unwrap(),
+        );
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(separate);
+        assert_eq!(contents, ("content".to_string(), "content".to_string()));
+    }
+}
@@ -0,0 +1,420 @@
+//! Layered configuration. Every setting is taken from the last layer that sets it:
+//!
+//! 1. built-in defaults
+//! 2. user config `$XDG_CONFIG_HOME/fo/config.toml` (or `~/.config/fo/config.toml`)
+//! 3. `.fo.toml` files found by walking up from the target directory, the nearest one last
+//! 4. `FO_*` environment variables
+//! 5. command line flags
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::{
+    cli::ColorMode,
+    commands::{CleanConfig, PlanFormat, SizeBuckets, SortBy},
+    journal::absolute,
+};
+
+pub const DEFAULT_DATETIME_FORMAT: &str = "[day]-[month]-[year] [hour]:[minute]";
+
+const PROJECT_CONFIG_FILE_NAME: &str = ".fo.toml";
+
+/// One config file as written on disk
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    sort_by: Option<SortBy>,
+    size_buckets: Option<String>,
+    hidden: Option<bool>,
+    datetime_format: Option<String>,
+    plan_format: Option<PlanFormat>,
+    color: Option<ColorMode>,
+    /// Junk rules and profiles of `fo clean`
+    #[serde(default)]
+    clean: CleanConfig,
+}
+
+/// Layer a setting was taken from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    Flag,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(name) => write!(f, "env {name}"),
+            Origin::Flag => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub origin: Origin,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            origin: Origin::Default,
+        }
+    }
+
+    fn set(&mut self, value: Option<T>, origin: &Origin) {
+        if let Some(value) = value {
+            self.value = value;
+            self.origin = origin.clone();
+        }
+    }
+}
+
+/// Settings given as command line flags
+#[derive(Debug, Default, PartialEq)]
+pub struct Flags {
+    pub sort_by: Option<SortBy>,
+    pub size_buckets: Option<SizeBuckets>,
+    pub hidden: Option<bool>,
+    pub plan_format: Option<PlanFormat>,
+    pub color: Option<ColorMode>,
+}
+
+/// Merged configuration of all layers
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Default `--sort-by` of `fo sort`
+    pub sort_by: Setting<SortBy>,
+    /// Default `--size-buckets` of `fo sort`, also used by `{size_bucket}` of sort rules
+    pub size_buckets: Setting<SizeBuckets>,
+    /// Whether hidden files are included without `--all`
+    pub hidden: Setting<bool>,
+    pub datetime_format: Setting<String>,
+    /// Default `--plan-format` of dry runs
+    pub plan_format: Setting<PlanFormat>,
+    pub color: Setting<ColorMode>,
+    /// Junk rules and profiles of config files, built-in rules are added by `JunkRules`
+    pub clean: CleanConfig,
+    /// Layer of every junk rule, profile and retention policy, keyed like `rules.logs`
+    pub clean_origins: BTreeMap<String, Origin>,
+    /// Loaded config files, lowest priority first
+    pub files: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            sort_by: Setting::new(SortBy::default()),
+            size_buckets: Setting::new(SizeBuckets::default()),
+            hidden: Setting::new(false),
+            datetime_format: Setting::new(DEFAULT_DATETIME_FORMAT.to_string()),
+            plan_format: Setting::new(PlanFormat::default()),
+            color: Setting::new(ColorMode::default()),
+            clean: CleanConfig::default(),
+            clean_origins: CleanConfig::builtin()
+                .keys()
+                .into_iter()
+                .map(|key| (key, Origin::Default))
+                .collect(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load all layers for command working on `directory`
+    pub fn load(directory: &Path, flags: &Flags) -> anyhow::Result<Self> {
+        Self::load_layers(
+            user_config_path(),
+            directory,
+            |name| std::env::var(name).ok(),
+            flags,
+        )
+    }
+
+    fn load_layers(
+        user_config: Option<PathBuf>,
+        directory: &Path,
+        env: impl Fn(&str) -> Option<String>,
+        flags: &Flags,
+    ) -> anyhow::Result<Self> {
+        let mut config = Config::default();
+
+        let mut files = project_config_paths(directory)?;
+        files.extend(user_config.filter(|path| path.is_file()));
+        for path in files.into_iter().rev() {
+            config.apply_file(path)?;
+        }
+        config.apply_env(env)?;
+        config.apply_flags(flags);
+
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let file: ConfigFile = toml::from_str(&content)
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+
+        let origin = Origin::File(path.clone());
+        self.sort_by.set(file.sort_by, &origin);
+        let size_buckets = file
+            .size_buckets
+            .map(|buckets| SizeBuckets::parse(&buckets))
+            .transpose()
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+        self.size_buckets.set(size_buckets, &origin);
+        self.hidden.set(file.hidden, &origin);
+        self.datetime_format.set(file.datetime_format, &origin);
+        self.plan_format.set(file.plan_format, &origin);
+        self.color.set(file.color, &origin);
+        for key in file.clean.keys() {
+            self.clean_origins.insert(key, origin.clone());
+        }
+        self.clean.merge(file.clean);
+        self.files.push(path);
+        Ok(())
+    }
+
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        let sort_by = env_value(&env, "FO_SORT_BY", |value| SortBy::from_str(value, true))?;
+        self.sort_by.set(sort_by, &Origin::Env("FO_SORT_BY"));
+        let size_buckets = env_value(&env, "FO_SIZE_BUCKETS", SizeBuckets::parse)?;
+        self.size_buckets
+            .set(size_buckets, &Origin::Env("FO_SIZE_BUCKETS"));
+        let hidden = env_value(&env, "FO_HIDDEN", parse_bool)?;
+        self.hidden.set(hidden, &Origin::Env("FO_HIDDEN"));
+        let datetime_format = env_value(&env, "FO_DATETIME_FORMAT", |value| {
+            Ok::<_, String>(value.to_string())
+        })?;
+        self.datetime_format
+            .set(datetime_format, &Origin::Env("FO_DATETIME_FORMAT"));
+        let plan_format = env_value(&env, "FO_PLAN_FORMAT", |value| {
+            PlanFormat::from_str(value, true)
+        })?;
+        self.plan_format
+            .set(plan_format, &Origin::Env("FO_PLAN_FORMAT"));
+        let color = env_value(&env, "FO_COLOR", |value| ColorMode::from_str(value, true))?;
+        self.color.set(color, &Origin::Env("FO_COLOR"));
+        Ok(())
+    }
+
+    fn apply_flags(&mut self, flags: &Flags) {
+        self.sort_by.set(flags.sort_by, &Origin::Flag);
+        self.size_buckets
+            .set(flags.size_buckets.clone(), &Origin::Flag);
+        self.hidden.set(flags.hidden, &Origin::Flag);
+        self.plan_format.set(flags.plan_format, &Origin::Flag);
+        self.color.set(flags.color, &Origin::Flag);
+    }
+}
+
+/// Value of environment variable, empty variable is treated as unset
+fn env_value<T, E: Display>(
+    env: impl Fn(&str) -> Option<String>,
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> anyhow::Result<Option<T>> {
+    match env(name).filter(|value| !value.is_empty()) {
+        Some(value) => parse(&value)
+            .map(Some)
+            .map_err(|err| anyhow!("Invalid value '{value}' of {name}: {err}")),
+        None => Ok(None),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err("expected true or false".to_string()),
+    }
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = match std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(config_home) if config_home.is_absolute() => config_home,
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("fo/config.toml"))
+}
+
+/// `.fo.toml` files of directory and all its parents, the nearest first.
+/// `..` is resolved before walking up, so `sub/..` starts at the parent of `sub`.
+fn project_config_paths(directory: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut normalized = PathBuf::new();
+    for component in absolute(directory)?.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    Ok(normalized
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers_override_each_other() {
+        let temp_dir = std::env::temp_dir().join("fo_test_config_layers");
+        let project = temp_dir.join("project");
+        let nested = project.join("photos");
+        fs::create_dir_all(&nested).unwrap();
+        let user_config = temp_dir.join("config.toml");
+        fs::write(
+            &user_config,
+            "hidden = true\nsort_by = \"ext\"\n[clean.rules.logs]\nmin_age = \"14d\"",
+        )
+        .unwrap();
+        fs::write(
+            project.join(".fo.toml"),
+            "sort_by = \"size\"\nplan_format = \"table\"\n[clean.rules.logs]\nenabled = false",
+        )
+        .unwrap();
+        fs::write(nested.join(".fo.toml"), "sort_by = \"modified\"").unwrap();
+        let env = |name: &str| (name == "FO_PLAN_FORMAT").then(|| "tree".to_string());
+        let flags = Flags {
+            color: Some(ColorMode::Never),
+            ..Default::default()
+        };
+
+        let config = Config::load_layers(Some(user_config.clone()), &nested, env, &flags);
+        fs::remove_dir_all(&temp_dir).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(
+            config.sort_by,
+            Setting {
+                value: SortBy::Modified,
+                origin: Origin::File(nested.join(".fo.toml"))
+            }
+        );
+        assert_eq!(config.hidden.origin, Origin::File(user_config.clone()));
+        assert_eq!(config.plan_format.value, PlanFormat::Tree);
+        assert_eq!(config.plan_format.origin, Origin::Env("FO_PLAN_FORMAT"));
+        assert_eq!(config.color.origin, Origin::Flag);
+        assert_eq!(config.datetime_format.origin, Origin::Default);
+        assert_eq!(
+            config.clean_origins["rules.logs"],
+            Origin::File(project.join(".fo.toml"))
+        );
+        assert_eq!(config.clean_origins["rules.system"], Origin::Default);
+        assert_eq!(
+            config.files,
+            vec![
+                user_config,
+                project.join(".fo.toml"),
+                nested.join(".fo.toml")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parent_of_directory_doesnt_take_its_config() {
+        let temp_dir = std::env::temp_dir().join("fo_test_config_parent_dir");
+        let nested = temp_dir.join("project/sub");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(temp_dir.join("project/.fo.toml"), "sort_by = \"ext\"").unwrap();
+        fs::write(nested.join(".fo.toml"), "sort_by = \"size\"").unwrap();
+
+        let config = Config::load_layers(None, &nested.join(".."), |_| None, &Flags::default());
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            config.unwrap().sort_by,
+            Setting {
+                value: SortBy::Ext,
+                origin: Origin::File(temp_dir.join("project/.fo.toml"))
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_env_value() {
+        let mut config = Config::default();
+        let env = |name: &str| (name == "FO_HIDDEN").then(|| "maybe".to_string());
+
+        assert!(config.apply_env(env).is_err());
+    }
+
+    #[test]
+    fn test_size_buckets_of_file_and_env() {
+        let temp_dir = std::env::temp_dir().join("fo_test_config_size_buckets");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let user_config = temp_dir.join("config.toml");
+        fs::write(&user_config, "size_buckets = \"labels\"").unwrap();
+        let env = |name: &str| (name == "FO_SIZE_BUCKETS").then(|| "1M,1G".to_string());
+
+        let from_file = Config::load_layers(
+            Some(user_config.clone()),
+            &temp_dir,
+            |_| None,
+            &Flags::default(),
+        );
+        let from_env = Config::load_layers(Some(user_config), &temp_dir, env, &Flags::default());
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(
+            from_file.unwrap().size_buckets.value,
+            SizeBuckets::parse("labels").unwrap()
+        );
+        assert_eq!(
+            from_env.unwrap().size_buckets,
+            Setting {
+                value: SizeBuckets::parse("1M,1G").unwrap(),
+                origin: Origin::Env("FO_SIZE_BUCKETS")
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_size_buckets_in_file() {
+        let temp_dir = std::env::temp_dir().join("fo_test_config_invalid_size_buckets");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let user_config = temp_dir.join("config.toml");
+        fs::write(&user_config, "size_buckets = \"1G,1M\"").unwrap();
+
+        let config = Config::load_layers(Some(user_config), &temp_dir, |_| None, &Flags::default());
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_missing_files_give_defaults() {
+        let config = Config::load_layers(
+            Some(PathBuf::from("/fo-test-missing/config.toml")),
+            Path::new("/fo-test-missing"),
+            |_| None,
+            &Flags::default(),
+        )
+        .unwrap();
+
+        assert_eq!(config.sort_by, Setting::new(SortBy::Date));
+        assert!(config.files.is_empty());
+    }
+}
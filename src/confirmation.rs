@@ -1,17 +1,122 @@
-use std::io::{self, Write};
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use anstream::print;
+use anyhow::bail;
 
 pub fn confirm(prompt: &str) -> anyhow::Result<bool> {
     loop {
         print!("{prompt} [y/n]: ");
         io::stdout().flush()?;
 
-        let mut s = String::new();
-        io::stdin().read_line(&mut s)?;
-
-        match s.trim().to_lowercase().as_str() {
+        match read_answer()?.to_lowercase().as_str() {
             "y" | "yes" => return Ok(true),
             "n" | "no" => return Ok(false),
             _ => {}
         }
     }
 }
+
+/// Ask user to pick one of `count` numbered items. Returns `None` when user skips the choice,
+/// fails when stdin is closed before a valid answer.
+pub fn select(prompt: &str, count: usize) -> anyhow::Result<Option<usize>> {
+    loop {
+        print!("{prompt} [1-{count}, s to skip]: ");
+        io::stdout().flush()?;
+
+        match read_answer()?.to_lowercase().as_str() {
+            "s" | "skip" => return Ok(None),
+            answer => {
+                if let Ok(number) = answer.parse::<usize>()
+                    && (1..=count).contains(&number)
+                {
+                    return Ok(Some(number - 1));
+                }
+            }
+        }
+    }
+}
+
+/// Possible answer of [`choose`], selected by its key or full label
+#[derive(Debug, Clone, Copy)]
+pub struct Choice {
+    pub key: char,
+    pub label: &'static str,
+}
+
+impl Choice {
+    pub const fn new(key: char, label: &'static str) -> Self {
+        Self { key, label }
+    }
+}
+
+impl Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.label.strip_prefix(self.key) {
+            Some(rest) => write!(f, "[{}]{rest}", self.key),
+            None => write!(f, "[{}] {}", self.key, self.label),
+        }
+    }
+}
+
+/// Ask user to answer with one of `choices`, returns key of the answer
+pub fn choose(prompt: &str, choices: &[Choice]) -> anyhow::Result<char> {
+    let options = choices
+        .iter()
+        .map(Choice::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    loop {
+        print!("{prompt} {options}: ");
+        io::stdout().flush()?;
+
+        if let Some(key) = parse_choice(&read_answer()?, choices) {
+            return Ok(key);
+        }
+    }
+}
+
+/// Keys are case sensitive, so `a` and `A` can mean different things
+fn parse_choice(answer: &str, choices: &[Choice]) -> Option<char> {
+    choices
+        .iter()
+        .find(|choice| answer.chars().eq([choice.key]) || answer.eq_ignore_ascii_case(choice.label))
+        .map(|choice| choice.key)
+}
+
+/// Read trimmed line from stdin. Closed stdin is an error, otherwise prompts would loop forever.
+fn read_answer() -> anyhow::Result<String> {
+    let mut s = String::new();
+    if io::stdin().read_line(&mut s)? == 0 {
+        bail!("Standard input was closed before an answer was given");
+    }
+    Ok(s.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHOICES: &[Choice] = &[
+        Choice::new('y', "yes"),
+        Choice::new('e', "each item"),
+        Choice::new('q', "stop"),
+    ];
+
+    #[test]
+    fn test_choice_display() {
+        assert_eq!(CHOICES[1].to_string(), "[e]ach item");
+        assert_eq!(CHOICES[2].to_string(), "[q] stop");
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("y", CHOICES), Some('y'));
+        assert_eq!(parse_choice("Each Item", CHOICES), Some('e'));
+        assert_eq!(parse_choice("E", CHOICES), None);
+        assert_eq!(parse_choice("yy", CHOICES), None);
+        assert_eq!(parse_choice("", CHOICES), None);
+    }
+}
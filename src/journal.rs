@@ -0,0 +1,302 @@
+//! On-disk journal of executed operations, used by `fo undo` and `fo history`.
+//!
+//! Journal is a JSON lines file in `$XDG_STATE_HOME/fo` (or `~/.local/state/fo`).
+//! Every line is one entry: start of a run, an executed operation, or a mark that run is being
+//! or was undone.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use anyhow::{Context, anyhow};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::commands::Operation;
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+static RUN_SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum JournalEntry {
+    Run {
+        run_id: String,
+        /// Unix timestamp in seconds
+        started_at: i64,
+        command_line: String,
+    },
+    Operation {
+        run_id: String,
+        operation: Operation,
+    },
+    /// Written before undo executes, so an undo that fails partway stays linked to its run
+    Undoing {
+        run_id: String,
+        undo_run_id: String,
+    },
+    Undone {
+        run_id: String,
+        undo_run_id: String,
+    },
+}
+
+/// Recorded run with all operations it executed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub id: String,
+    pub started_at: OffsetDateTime,
+    pub command_line: String,
+    pub operations: Vec<Operation>,
+    /// Id of the run that undid this one
+    pub undone_by: Option<String>,
+    /// Id of the run this one undoes, also when the undo didn't finish
+    pub undoes: Option<String>,
+}
+
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Journal in the XDG state directory
+    pub fn open() -> anyhow::Result<Self> {
+        let directory = state_dir()?;
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create state directory {}", directory.display()))?;
+        Ok(Self::at(directory.join(JOURNAL_FILE_NAME)))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Record start of a new run, operations are recorded through returned recorder
+    pub fn start_run(&self) -> anyhow::Result<RunRecorder> {
+        let started_at = OffsetDateTime::now_utc();
+        // Sequence number keeps ids unique for runs started in the same millisecond by one process
+        let run_id = format!(
+            "{}{:03}-{}-{}",
+            started_at.unix_timestamp(),
+            started_at.millisecond(),
+            std::process::id(),
+            RUN_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        );
+        let command_line = std::env::args().collect::<Vec<_>>().join(" ");
+
+        let mut recorder = RunRecorder {
+            file: OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .with_context(|| format!("Failed to open journal {}", self.path.display()))?,
+            run_id: run_id.clone(),
+        };
+        recorder.write(&JournalEntry::Run {
+            run_id,
+            started_at: started_at.unix_timestamp(),
+            command_line,
+        })?;
+        debug!("Started journal run {}", recorder.run_id);
+
+        Ok(recorder)
+    }
+
+    /// All recorded runs, oldest first
+    pub fn runs(&self) -> anyhow::Result<Vec<Run>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err).context("Failed to open journal"),
+        };
+
+        let mut runs: Vec<Run> = Vec::new();
+        let mut indexes: HashMap<String, usize> = HashMap::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Broken line (e.g. from interrupted write) shouldn't make the whole journal unreadable
+            let entry: JournalEntry = match serde_json::from_str(&line) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warn!("Skipping broken journal line {}: {}", number + 1, err);
+                    continue;
+                }
+            };
+
+            match entry {
+                JournalEntry::Run {
+                    run_id,
+                    started_at,
+                    command_line,
+                } => {
+                    indexes.insert(run_id.clone(), runs.len());
+                    runs.push(Run {
+                        id: run_id,
+                        started_at: OffsetDateTime::from_unix_timestamp(started_at)?,
+                        command_line,
+                        operations: Vec::new(),
+                        undone_by: None,
+                        undoes: None,
+                    });
+                }
+                JournalEntry::Operation { run_id, operation } => {
+                    if let Some(index) = indexes.get(&run_id) {
+                        runs[*index].operations.push(operation);
+                    }
+                }
+                JournalEntry::Undoing {
+                    run_id,
+                    undo_run_id,
+                } => {
+                    if let Some(index) = indexes.get(&undo_run_id) {
+                        runs[*index].undoes = Some(run_id);
+                    }
+                }
+                JournalEntry::Undone {
+                    run_id,
+                    undo_run_id,
+                } => {
+                    if let Some(index) = indexes.get(&run_id) {
+                        runs[*index].undone_by = Some(undo_run_id.clone());
+                    }
+                    if let Some(index) = indexes.get(&undo_run_id) {
+                        runs[*index].undoes = Some(run_id);
+                    }
+                }
+            }
+        }
+
+        Ok(runs)
+    }
+}
+
+/// Appends operations of one run to journal
+pub struct RunRecorder {
+    file: File,
+    run_id: String,
+}
+
+impl RunRecorder {
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Record executed operation with absolute paths, so it can be undone from any directory
+    pub fn record(&mut self, operation: &Operation) -> anyhow::Result<()> {
+        self.write(&JournalEntry::Operation {
+            run_id: self.run_id.clone(),
+            operation: operation.to_absolute()?,
+        })
+    }
+
+    /// Mark that this run starts to undo other run
+    pub fn mark_undoing(&mut self, run_id: &str) -> anyhow::Result<()> {
+        self.write(&JournalEntry::Undoing {
+            run_id: run_id.to_string(),
+            undo_run_id: self.run_id.clone(),
+        })
+    }
+
+    /// Mark other run as undone by this one
+    pub fn mark_undone(&mut self, run_id: &str) -> anyhow::Result<()> {
+        self.write(&JournalEntry::Undone {
+            run_id: run_id.to_string(),
+            undo_run_id: self.run_id.clone(),
+        })
+    }
+
+    fn write(&mut self, entry: &JournalEntry) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // Single write per line keeps entries whole even when runs overlap
+        self.file
+            .write_all(line.as_bytes())
+            .context("Failed to write journal")?;
+        Ok(())
+    }
+}
+
+/// `$XDG_STATE_HOME/fo`, falling back to `~/.local/state/fo`
+fn state_dir() -> anyhow::Result<PathBuf> {
+    if let Some(state_home) = std::env::var_os("XDG_STATE_HOME").map(PathBuf::from)
+        && state_home.is_absolute()
+    {
+        return Ok(state_home.join("fo"));
+    }
+
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow!("Neither XDG_STATE_HOME nor HOME is set"))?;
+    Ok(home.join(".local/state/fo"))
+}
+
+/// Absolute path without resolving symlinks
+pub fn absolute(path: &Path) -> anyhow::Result<PathBuf> {
+    std::path::absolute(path)
+        .with_context(|| format!("Failed to get absolute path of {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_runs() {
+        let temp_dir = std::env::temp_dir().join("fo_test_journal_runs");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let journal = Journal::at(temp_dir.join(JOURNAL_FILE_NAME));
+
+        let mut sort_run = journal.start_run().unwrap();
+        sort_run
+            .record(&Operation::CreateDir {
+                path: PathBuf::from("/tmp/a_sorted"),
+            })
+            .unwrap();
+        sort_run
+            .record(&Operation::Move {
+                from: PathBuf::from("/tmp/a/1.txt"),
+                to: PathBuf::from("/tmp/a_sorted/1.txt"),
+                size: 10,
+            })
+            .unwrap();
+        let sort_run_id = sort_run.run_id().to_string();
+        drop(sort_run);
+
+        // Appended garbage is skipped
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(temp_dir.join(JOURNAL_FILE_NAME))
+            .unwrap();
+        writeln!(file, "{{not json").unwrap();
+
+        let mut undo_run = journal.start_run().unwrap();
+        undo_run.mark_undoing(&sort_run_id).unwrap();
+        undo_run.mark_undone(&sort_run_id).unwrap();
+        let undo_run_id = undo_run.run_id().to_string();
+
+        let runs = journal.runs().unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, sort_run_id);
+        assert_eq!(runs[0].operations.len(), 2);
+        assert_eq!(runs[0].undone_by, Some(undo_run_id));
+        assert!(runs[1].operations.is_empty());
+        assert_eq!(runs[1].undoes, Some(sort_run_id));
+    }
+
+    #[test]
+    fn test_journal_without_file_has_no_runs() {
+        let journal = Journal::at(std::env::temp_dir().join("fo_test_missing_journal.jsonl"));
+        assert!(journal.runs().unwrap().is_empty());
+    }
+}
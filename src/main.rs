@@ -1,16 +1,23 @@
 mod cli;
 mod commands;
+mod config;
 mod confirmation;
+mod journal;
 mod logger;
+mod media;
+mod mime;
+mod size;
+mod timestamps;
+mod trash;
 #[cfg(test)]
 pub mod volumes;
 
+use anstream::eprintln;
 use anyhow::bail;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use cli::Cli;
-use log::trace;
+use log::{debug, trace};
 use owo_colors::OwoColorize;
-use time::format_description;
 
 #[cfg(all(test, not(target_os = "linux")))]
 compile_error!("This project's tests are Linux-only. Run `cargo test` on Linux.");
@@ -26,13 +33,15 @@ fn main() {
 }
 
 fn run() -> anyhow::Result<()> {
-    let mut cli = Cli::parse();
-    cli.datetime_format = format_description::parse("[day]-[month]-[year] [hour]:[minute]")
-        .expect("Should be valid, because its hardcoded.");
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
 
+    // Config decides colors of log output too, so it's loaded before logger
+    cli.configure(&matches)?;
     logger::init(&cli);
 
     trace!("Logger initialized.");
+    debug!("Loaded config files: {:?}", cli.config.files);
     trace!("{cli}");
 
     match cli.execute_command() {
@@ -0,0 +1,139 @@
+//! Date photos and videos were taken, read from their metadata: EXIF `DateTimeOriginal`
+//! of JPEG, TIFF and HEIC, or creation time in `mvhd` of MP4 and MOV.
+//! Filesystem timestamps of imported media are usually the time of copying.
+
+mod bmff;
+mod exif;
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use log::debug;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::mime::MimeType;
+#[cfg(test)]
+use exif::sample_tiff;
+
+/// TIFF files are read only up to this size, EXIF of raw photos is near the start
+const MAX_TIFF_BYTES: u64 = 1024 * 1024;
+
+const JPEG_START_OF_SCAN: u8 = 0xda;
+const JPEG_END_OF_IMAGE: u8 = 0xd9;
+const JPEG_APP1: u8 = 0xe1;
+
+/// Date photo or video was taken, `None` for other files and media without the date.
+/// Dates without timezone are assumed to be in `local_offset`.
+pub fn taken_date(path: &Path, local_offset: UtcOffset) -> io::Result<Option<OffsetDateTime>> {
+    let mime = MimeType::detect(path)?;
+    let mut file = BufReader::new(File::open(path)?);
+    let taken = match mime.name {
+        "image/jpeg" => jpeg_exif(&mut file)
+            .map(|exif| exif.and_then(|exif| exif::date_taken(&exif, local_offset))),
+        "image/tiff" => {
+            read_head(&mut file, MAX_TIFF_BYTES).map(|tiff| exif::date_taken(&tiff, local_offset))
+        }
+        "image/heic" | "image/avif" => bmff::heif_exif(&mut file)
+            .map(|exif| exif.and_then(|exif| exif::date_taken(&exif, local_offset))),
+        "video/mp4" | "video/quicktime" => bmff::movie_created(&mut file),
+        _ => return Ok(None),
+    };
+
+    // Broken metadata is the same as missing one, the file itself was readable
+    taken.or_else(|err| {
+        debug!("Failed to read media date of {}: {err}", path.display());
+        Ok(None)
+    })
+}
+
+/// TIFF data of EXIF segment of JPEG, which comes before the image data
+fn jpeg_exif<R: Read + Seek>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    reader.seek(SeekFrom::Start(2))?;
+    loop {
+        let mut marker = [0; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xff {
+            return Ok(None);
+        }
+        match marker[1] {
+            JPEG_START_OF_SCAN | JPEG_END_OF_IMAGE => return Ok(None),
+            // Markers without content
+            0x01 | 0xd0..=0xd7 | 0xff => continue,
+            _ => {}
+        }
+
+        let mut length = [0; 2];
+        reader.read_exact(&mut length)?;
+        let length = u16::from_be_bytes(length).saturating_sub(2);
+        if marker[1] == JPEG_APP1 {
+            let segment = read_head(reader, length as u64)?;
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return Ok(Some(tiff.to_vec()));
+            }
+        } else {
+            reader.seek(SeekFrom::Current(length as i64))?;
+        }
+    }
+}
+
+fn read_head(reader: &mut impl Read, max_bytes: u64) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    reader.take(max_bytes).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// JPEG with EXIF segment holding `DateTimeOriginal`, e.g. `2021:07:04 15:30:00`
+#[cfg(test)]
+pub fn sample_jpeg(date: &str) -> Vec<u8> {
+    let tiff = sample_tiff(date, None);
+    let mut jpeg = b"\xff\xd8\xff\xe0\x00\x10JFIF\0\x01\x01\0\0\x01\0\x01\0\0".to_vec();
+    jpeg.extend_from_slice(b"\xff\xe1");
+    jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&tiff);
+    jpeg.extend_from_slice(b"\xff\xda\x00\x02image data");
+    jpeg
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use bmff::{sample_heif, sample_movie};
+
+    #[test]
+    fn test_taken_date_of_every_format() {
+        let temp_dir = std::env::temp_dir().join("fo_test_media_taken_date");
+        fs::create_dir_all(&temp_dir).unwrap();
+        let tiff = sample_tiff("2021:07:04 15:30:00", None);
+        let files = [
+            ("photo.jpg", sample_jpeg("2021:07:04 15:30:00")),
+            ("raw.tif", tiff.clone()),
+            ("photo.heic", sample_heif(&tiff)),
+            ("clip.mp4", sample_movie(1_625_412_600)),
+            ("no_exif.jpg", b"\xff\xd8\xff\xda\x00\x02".to_vec()),
+            ("broken.jpg", b"\xff\xd8\xff\xe1\x00".to_vec()),
+            ("notes.txt", b"2021:07:04 15:30:00".to_vec()),
+        ];
+        for (name, content) in &files {
+            fs::write(temp_dir.join(name), content).unwrap();
+        }
+
+        let taken = files
+            .iter()
+            .map(|(name, _)| {
+                taken_date(&temp_dir.join(name), UtcOffset::UTC)
+                    .unwrap()
+                    .map(OffsetDateTime::unix_timestamp)
+            })
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        let date = Some(1_625_412_600);
+        assert_eq!(taken, [date, date, date, date, None, None, None]);
+    }
+}
@@ -0,0 +1,370 @@
+//! Boxes of ISO base media files: MP4 and MOV videos, HEIC and AVIF images
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use time::OffsetDateTime;
+
+/// Seconds between 1904-01-01, start of media file time, and Unix epoch
+const MEDIA_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// EXIF item of HEIC is read only up to this size
+const MAX_EXIF_BYTES: u64 = 1024 * 1024;
+
+struct BoxHeader {
+    /// Position of content, after header
+    start: u64,
+    end: u64,
+}
+
+/// Child box of given kind between `start` and `end`
+fn find_box<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    kind: &[u8; 4],
+) -> io::Result<Option<BoxHeader>> {
+    let mut position = start;
+    while position
+        .checked_add(8)
+        .is_some_and(|header_end| header_end <= end)
+    {
+        reader.seek(SeekFrom::Start(position))?;
+        let size = read_u32(reader)? as u64;
+        let mut box_kind = [0; 4];
+        reader.read_exact(&mut box_kind)?;
+        let (header_size, size) = match size {
+            // Box extends to the end of its parent
+            0 => (8, end - position),
+            1 => (16, read_u64(reader)?),
+            size => (8, size),
+        };
+        // Sizes of broken files can overflow or point back, which would never end the loop
+        let Some(box_end) = position.checked_add(size) else {
+            return Ok(None);
+        };
+        if size < header_size || box_end > end {
+            return Ok(None);
+        }
+
+        if &box_kind == kind {
+            return Ok(Some(BoxHeader {
+                start: position + header_size,
+                end: box_end,
+            }));
+        }
+        position = box_end;
+    }
+    Ok(None)
+}
+
+/// Creation time from `moov/mvhd` of MP4 or MOV, in UTC
+pub fn movie_created<R: Read + Seek>(reader: &mut R) -> io::Result<Option<OffsetDateTime>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    let Some(moov) = find_box(reader, 0, end, b"moov")? else {
+        return Ok(None);
+    };
+    let Some(mvhd) = find_box(reader, moov.start, moov.end, b"mvhd")? else {
+        return Ok(None);
+    };
+
+    reader.seek(SeekFrom::Start(mvhd.start))?;
+    let (version, _flags) = read_full_box(reader)?;
+    let created = if version == 1 {
+        read_u64(reader)?
+    } else {
+        read_u32(reader)? as u64
+    };
+    // Many encoders leave creation time unset
+    if created == 0 {
+        return Ok(None);
+    }
+    Ok(i64::try_from(created)
+        .ok()
+        .and_then(|created| created.checked_sub(MEDIA_EPOCH_OFFSET))
+        .and_then(|unix_time| OffsetDateTime::from_unix_timestamp(unix_time).ok()))
+}
+
+/// Data of the EXIF item of HEIC or AVIF, starting at its TIFF header
+pub fn heif_exif<R: Read + Seek>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    let Some(meta) = find_box(reader, 0, end, b"meta")? else {
+        return Ok(None);
+    };
+    // Children of `meta` follow its version and flags
+    let children = meta.start + 4;
+    let Some(iinf) = find_box(reader, children, meta.end, b"iinf")? else {
+        return Ok(None);
+    };
+    let Some(item_id) = exif_item_id(reader, &iinf)? else {
+        return Ok(None);
+    };
+    let Some(iloc) = find_box(reader, children, meta.end, b"iloc")? else {
+        return Ok(None);
+    };
+    let Some((offset, length)) = item_location(reader, &iloc, item_id)? else {
+        return Ok(None);
+    };
+
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::new();
+    reader
+        .take(length.min(MAX_EXIF_BYTES))
+        .read_to_end(&mut data)?;
+    // Item starts with offset of TIFF header, usually skipping `Exif\0\0`
+    let Some(tiff_offset) = data.get(..4) else {
+        return Ok(None);
+    };
+    let tiff_offset = 4 + u32::from_be_bytes(tiff_offset.try_into().expect("4 bytes")) as usize;
+    Ok(data.get(tiff_offset..).map(<[u8]>::to_vec))
+}
+
+/// Id of item of type `Exif` listed in `iinf`
+fn exif_item_id<R: Read + Seek>(reader: &mut R, iinf: &BoxHeader) -> io::Result<Option<u32>> {
+    reader.seek(SeekFrom::Start(iinf.start))?;
+    let (version, _flags) = read_full_box(reader)?;
+    let entries_start = iinf.start + if version == 0 { 6 } else { 8 };
+
+    let mut position = entries_start;
+    while let Some(infe) = find_box(reader, position, iinf.end, b"infe")? {
+        reader.seek(SeekFrom::Start(infe.start))?;
+        let (version, _flags) = read_full_box(reader)?;
+        // Older versions have no item type
+        if version >= 2 {
+            let item_id = if version == 2 {
+                read_u16(reader)? as u32
+            } else {
+                read_u32(reader)?
+            };
+            let _protection_index = read_u16(reader)?;
+            let mut item_type = [0; 4];
+            reader.read_exact(&mut item_type)?;
+            if &item_type == b"Exif" {
+                return Ok(Some(item_id));
+            }
+        }
+        position = infe.end;
+    }
+    Ok(None)
+}
+
+/// File offset and length of the first extent of item listed in `iloc`
+fn item_location<R: Read + Seek>(
+    reader: &mut R,
+    iloc: &BoxHeader,
+    item_id: u32,
+) -> io::Result<Option<(u64, u64)>> {
+    reader.seek(SeekFrom::Start(iloc.start))?;
+    let (version, _flags) = read_full_box(reader)?;
+    let sizes = read_u16(reader)?;
+    let offset_size = sizes >> 12;
+    let length_size = (sizes >> 8) & 0xf;
+    let base_offset_size = (sizes >> 4) & 0xf;
+    let index_size = if version >= 1 { sizes & 0xf } else { 0 };
+    let item_count = if version < 2 {
+        read_u16(reader)? as u32
+    } else {
+        read_u32(reader)?
+    };
+
+    for _ in 0..item_count {
+        let id = if version < 2 {
+            read_u16(reader)? as u32
+        } else {
+            read_u32(reader)?
+        };
+        // Only data stored in file itself is supported, not in other items
+        let construction_method = if version >= 1 {
+            read_u16(reader)? & 0xf
+        } else {
+            0
+        };
+        let _data_reference_index = read_u16(reader)?;
+        let base_offset = read_sized(reader, base_offset_size)?;
+        let extent_count = read_u16(reader)?;
+        let mut extents = Vec::with_capacity(extent_count as usize);
+        for _ in 0..extent_count {
+            read_sized(reader, index_size)?;
+            let offset = read_sized(reader, offset_size)?;
+            let length = read_sized(reader, length_size)?;
+            extents.push((base_offset.saturating_add(offset), length));
+        }
+
+        if id == item_id {
+            return Ok(extents
+                .first()
+                .copied()
+                .filter(|_| construction_method == 0));
+        }
+    }
+    Ok(None)
+}
+
+fn read_full_box(reader: &mut impl Read) -> io::Result<(u8, u32)> {
+    let value = read_u32(reader)?;
+    Ok(((value >> 24) as u8, value & 0x00ff_ffff))
+}
+
+/// Big-endian number of 0, 4 or 8 bytes
+fn read_sized(reader: &mut impl Read, size: u16) -> io::Result<u64> {
+    match size {
+        0 => Ok(0),
+        4 => Ok(read_u32(reader)? as u64),
+        8 => read_u64(reader),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported field size {size}"),
+        )),
+    }
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Box with given content, e.g. `sample_box(b"moov", &children)`
+#[cfg(test)]
+pub fn sample_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+    let mut data = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(kind);
+    data.extend_from_slice(content);
+    data
+}
+
+/// MP4 with `mvhd` of version 0 holding creation time
+#[cfg(test)]
+pub fn sample_movie(unix_time: i64) -> Vec<u8> {
+    let mut mvhd = vec![0; 4];
+    mvhd.extend_from_slice(&((unix_time + MEDIA_EPOCH_OFFSET) as u32).to_be_bytes());
+    mvhd.extend_from_slice(&[0; 92]);
+    let mut movie = sample_box(b"ftyp", b"isom\0\0\0\0isom");
+    movie.extend(sample_box(b"mdat", &[0; 16]));
+    movie.extend(sample_box(b"moov", &sample_box(b"mvhd", &mvhd)));
+    movie
+}
+
+/// HEIC with item 1 of type `Exif` holding `tiff` at the end of file
+#[cfg(test)]
+pub fn sample_heif(tiff: &[u8]) -> Vec<u8> {
+    let mut infe = vec![2, 0, 0, 0];
+    infe.extend_from_slice(&1u16.to_be_bytes());
+    infe.extend_from_slice(&0u16.to_be_bytes());
+    infe.extend_from_slice(b"Exif");
+    let mut iinf = vec![0; 4];
+    iinf.extend_from_slice(&1u16.to_be_bytes());
+    iinf.extend(sample_box(b"infe", &infe));
+
+    let mut exif = 6u32.to_be_bytes().to_vec();
+    exif.extend_from_slice(b"Exif\0\0");
+    exif.extend_from_slice(tiff);
+
+    // Version 0 location with 4 byte offsets and lengths, item data follows `meta`
+    let iloc_size = 8 + 4 + 2 + 2 + 2 + 2 + 2 + 4 + 4;
+    let meta_size = 8 + 4 + 8 + iinf.len() + iloc_size;
+    let ftyp = sample_box(b"ftyp", b"heic\0\0\0\0mif1heic");
+    let exif_offset = ftyp.len() + meta_size;
+    let mut iloc = vec![0; 4];
+    iloc.extend_from_slice(&0x4400u16.to_be_bytes());
+    iloc.extend_from_slice(&1u16.to_be_bytes());
+    iloc.extend_from_slice(&1u16.to_be_bytes());
+    iloc.extend_from_slice(&0u16.to_be_bytes());
+    iloc.extend_from_slice(&1u16.to_be_bytes());
+    iloc.extend_from_slice(&(exif_offset as u32).to_be_bytes());
+    iloc.extend_from_slice(&(exif.len() as u32).to_be_bytes());
+
+    let mut meta = vec![0; 4];
+    meta.extend(sample_box(b"iinf", &iinf));
+    meta.extend(sample_box(b"iloc", &iloc));
+    let mut heif = ftyp;
+    heif.extend(sample_box(b"meta", &meta));
+    heif.extend(exif);
+    heif
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::media::exif::sample_tiff;
+
+    #[test]
+    fn test_movie_created() {
+        let movie = sample_movie(1_625_412_600);
+
+        let created = movie_created(&mut Cursor::new(movie)).unwrap();
+
+        assert_eq!(
+            created.map(OffsetDateTime::unix_timestamp),
+            Some(1_625_412_600)
+        );
+    }
+
+    #[test]
+    fn test_movie_without_creation_time() {
+        let movie = sample_movie(-MEDIA_EPOCH_OFFSET);
+        let no_moov = sample_box(b"ftyp", b"isom");
+
+        assert_eq!(movie_created(&mut Cursor::new(movie)).unwrap(), None);
+        assert_eq!(movie_created(&mut Cursor::new(no_moov)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_movie_created_out_of_range() {
+        let mut mvhd = vec![1, 0, 0, 0];
+        mvhd.extend_from_slice(&u64::MAX.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 100]);
+        let movie = sample_box(b"moov", &sample_box(b"mvhd", &mvhd));
+
+        assert_eq!(movie_created(&mut Cursor::new(movie)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_heif_exif() {
+        let tiff = sample_tiff("2021:07:04 15:30:00", None);
+        let heif = sample_heif(&tiff);
+
+        assert_eq!(heif_exif(&mut Cursor::new(heif)).unwrap(), Some(tiff));
+    }
+
+    #[test]
+    fn test_box_with_overflowing_large_size_is_ignored() {
+        let mut data = sample_box(b"ftyp", b"isom");
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(b"free");
+        data.extend_from_slice(&(u64::MAX - 4).to_be_bytes());
+        data.extend(sample_box(b"moov", &[]));
+
+        assert!(
+            find_box(&mut Cursor::new(&data), 0, data.len() as u64, b"moov")
+                .unwrap()
+                .is_none()
+        );
+        assert!(movie_created(&mut Cursor::new(data)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_box_larger_than_parent_is_ignored() {
+        let mut data = 64u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"moov");
+
+        assert!(
+            find_box(&mut Cursor::new(&data), 0, data.len() as u64, b"moov")
+                .unwrap()
+                .is_none()
+        );
+    }
+}
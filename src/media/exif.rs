@@ -0,0 +1,171 @@
+//! Date a photo was taken, from EXIF data stored as TIFF structure
+
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset, format_description};
+
+const EXIF_IFD: u16 = 0x8769;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const DATE_TIME_DIGITIZED: u16 = 0x9004;
+const OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const ASCII: u16 = 2;
+
+/// TIFF structure, offsets are relative to its header
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..4)? {
+            b"II*\0" => true,
+            b"MM\0*" => false,
+            _ => return None,
+        };
+        Some(Tiff {
+            data,
+            little_endian,
+        })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Offset of 12 byte entry of tag in directory at `ifd`
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .map(|index| ifd + 2 + index * 12)
+            .find(|&entry| self.u16(entry) == Some(tag))
+    }
+
+    fn ascii(&self, ifd: usize, tag: u16) -> Option<&'a str> {
+        let entry = self.entry(ifd, tag)?;
+        if self.u16(entry + 2)? != ASCII {
+            return None;
+        }
+        let count = self.u32(entry + 4)? as usize;
+        // Values up to 4 bytes are stored in the entry itself
+        let value = if count <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        let bytes = self.data.get(value..value + count)?;
+        std::str::from_utf8(bytes)
+            .ok()
+            .map(|text| text.trim_end_matches('\0').trim())
+    }
+}
+
+/// `DateTimeOriginal`, or `DateTimeDigitized` when it is missing. Cameras write local time,
+/// which is assumed to be in `local_offset` unless `OffsetTimeOriginal` is present.
+pub fn date_taken(data: &[u8], local_offset: UtcOffset) -> Option<OffsetDateTime> {
+    let tiff = Tiff::new(data)?;
+    let ifd0 = tiff.u32(4)? as usize;
+    let exif_ifd = tiff.u32(tiff.entry(ifd0, EXIF_IFD)? + 8)? as usize;
+
+    let date = tiff
+        .ascii(exif_ifd, DATE_TIME_ORIGINAL)
+        .or_else(|| tiff.ascii(exif_ifd, DATE_TIME_DIGITIZED))?;
+    let offset = tiff
+        .ascii(exif_ifd, OFFSET_TIME_ORIGINAL)
+        .and_then(parse_offset)
+        .unwrap_or(local_offset);
+    parse_date(date).map(|date| date.assume_offset(offset))
+}
+
+/// EXIF date, e.g. `2021:07:04 15:30:00`. Unknown dates are written as zeros or spaces.
+fn parse_date(date: &str) -> Option<PrimitiveDateTime> {
+    let format = format_description::parse("[year]:[month]:[day] [hour]:[minute]:[second]").ok()?;
+    PrimitiveDateTime::parse(date, &format).ok()
+}
+
+/// EXIF offset, e.g. `+02:00`
+fn parse_offset(offset: &str) -> Option<UtcOffset> {
+    let format = format_description::parse("[offset_hour sign:mandatory]:[offset_minute]").ok()?;
+    UtcOffset::parse(offset, &format).ok()
+}
+
+/// Big-endian TIFF with Exif directory holding `DateTimeOriginal` and optional offset
+#[cfg(test)]
+pub fn sample_tiff(date: &str, offset: Option<&str>) -> Vec<u8> {
+    let mut strings = vec![(DATE_TIME_ORIGINAL, format!("{date}\0"))];
+    if let Some(offset) = offset {
+        strings.push((OFFSET_TIME_ORIGINAL, format!("{offset}\0")));
+    }
+    let exif_ifd = 8 + 2 + 12 + 4;
+    let mut values = exif_ifd + 2 + 12 * strings.len() + 4;
+
+    let mut tiff = b"MM\0*\0\0\0\x08".to_vec();
+    tiff.extend_from_slice(&1u16.to_be_bytes());
+    tiff.extend_from_slice(&EXIF_IFD.to_be_bytes());
+    tiff.extend_from_slice(&4u16.to_be_bytes());
+    tiff.extend_from_slice(&1u32.to_be_bytes());
+    tiff.extend_from_slice(&(exif_ifd as u32).to_be_bytes());
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+
+    tiff.extend_from_slice(&(strings.len() as u16).to_be_bytes());
+    for (tag, value) in &strings {
+        tiff.extend_from_slice(&tag.to_be_bytes());
+        tiff.extend_from_slice(&ASCII.to_be_bytes());
+        tiff.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        tiff.extend_from_slice(&(values as u32).to_be_bytes());
+        values += value.len();
+    }
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    for (_, value) in &strings {
+        tiff.extend_from_slice(value.as_bytes());
+    }
+    tiff
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(None, "2021-07-04 15:30:00 +1" ; "local offset")]
+    #[test_case(Some("-05:00"), "2021-07-04 15:30:00 -5" ; "offset of photo")]
+    fn test_date_taken(offset: Option<&str>, expected: &str) {
+        let tiff = sample_tiff("2021:07:04 15:30:00", offset);
+        let local_offset = UtcOffset::from_hms(1, 0, 0).unwrap();
+
+        let date = date_taken(&tiff, local_offset).unwrap();
+
+        let format =
+            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory padding:none]")
+                .unwrap();
+        assert_eq!(date.format(&format).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unknown_date() {
+        let tiff = sample_tiff("0000:00:00 00:00:00", None);
+
+        assert_eq!(date_taken(&tiff, UtcOffset::UTC), None);
+    }
+
+    #[test]
+    fn test_truncated_data() {
+        let tiff = sample_tiff("2021:07:04 15:30:00", None);
+
+        assert_eq!(date_taken(&tiff[..30], UtcOffset::UTC), None);
+        assert_eq!(date_taken(b"II*\0", UtcOffset::UTC), None);
+    }
+}
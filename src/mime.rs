@@ -0,0 +1,380 @@
+//! File type detection from leading magic bytes, used by `fo sort --sort-by type|category`
+//! and `fo find --type`. Extension only refines detected type, e.g. zip into docx.
+
+use std::{fmt::Display, fs::File, io::Read, path::Path};
+
+/// Enough for every signature, tar header ends at 262
+const SNIFF_BYTES: usize = 8 * 1024;
+
+const OCTET_STREAM: &str = "application/octet-stream";
+const TEXT: &str = "text/plain";
+const ZIP: &str = "application/zip";
+const OLE: &str = "application/x-ole-storage";
+
+/// Human group of types, used as sorted directory name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Images,
+    Videos,
+    Audio,
+    Documents,
+    Archives,
+    Code,
+    Executables,
+    Directories,
+    Other,
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct MimeType {
+    pub name: &'static str,
+    pub category: Category,
+    /// Lowercase extensions files of this type usually have
+    extensions: &'static [&'static str],
+    /// Type detected from content that this type is a flavour of, e.g. docx of zip.
+    /// Such types are told apart by extension only.
+    based_on: Option<&'static str>,
+}
+
+const fn mime(
+    name: &'static str,
+    category: Category,
+    extensions: &'static [&'static str],
+) -> MimeType {
+    MimeType {
+        name,
+        category,
+        extensions,
+        based_on: None,
+    }
+}
+
+const fn flavour(
+    name: &'static str,
+    category: Category,
+    extensions: &'static [&'static str],
+    based_on: &'static str,
+) -> MimeType {
+    MimeType {
+        name,
+        category,
+        extensions,
+        based_on: Some(based_on),
+    }
+}
+
+use Category::*;
+
+static MIME_TYPES: &[MimeType] = &[
+    mime("image/png", Images, &["png"]),
+    mime("image/jpeg", Images, &["jpg", "jpeg", "jpe"]),
+    mime("image/gif", Images, &["gif"]),
+    mime("image/bmp", Images, &["bmp"]),
+    mime("image/webp", Images, &["webp"]),
+    mime(
+        "image/tiff",
+        Images,
+        &["tif", "tiff", "dng", "cr2", "nef", "arw"],
+    ),
+    mime("image/x-icon", Images, &["ico"]),
+    mime("image/heic", Images, &["heic", "heif"]),
+    mime("image/avif", Images, &["avif"]),
+    flavour("image/svg+xml", Images, &["svg"], TEXT),
+    mime("video/mp4", Videos, &["mp4", "m4v"]),
+    mime("video/quicktime", Videos, &["mov", "qt"]),
+    mime("video/x-matroska", Videos, &["mkv"]),
+    flavour("video/webm", Videos, &["webm"], "video/x-matroska"),
+    mime("video/x-msvideo", Videos, &["avi"]),
+    mime("video/x-flv", Videos, &["flv"]),
+    mime("audio/mpeg", Audio, &["mp3"]),
+    mime("audio/flac", Audio, &["flac"]),
+    mime("audio/ogg", Audio, &["ogg", "oga", "opus"]),
+    mime("audio/wav", Audio, &["wav"]),
+    mime("audio/mp4", Audio, &["m4a"]),
+    mime("application/pdf", Documents, &["pdf"]),
+    mime("application/rtf", Documents, &["rtf"]),
+    mime(OLE, Documents, &[]),
+    flavour("application/msword", Documents, &["doc"], OLE),
+    flavour("application/vnd.ms-excel", Documents, &["xls"], OLE),
+    flavour("application/vnd.ms-powerpoint", Documents, &["ppt"], OLE),
+    flavour(
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Documents,
+        &["docx"],
+        ZIP,
+    ),
+    flavour(
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Documents,
+        &["xlsx"],
+        ZIP,
+    ),
+    flavour(
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        Documents,
+        &["pptx"],
+        ZIP,
+    ),
+    flavour(
+        "application/vnd.oasis.opendocument.text",
+        Documents,
+        &["odt"],
+        ZIP,
+    ),
+    flavour(
+        "application/vnd.oasis.opendocument.spreadsheet",
+        Documents,
+        &["ods"],
+        ZIP,
+    ),
+    flavour("application/epub+zip", Documents, &["epub"], ZIP),
+    mime(TEXT, Documents, &["txt", "text", "log"]),
+    flavour("text/markdown", Documents, &["md", "markdown"], TEXT),
+    flavour("text/csv", Documents, &["csv", "tsv"], TEXT),
+    mime(ZIP, Archives, &["zip"]),
+    flavour("application/java-archive", Archives, &["jar", "war"], ZIP),
+    mime("application/gzip", Archives, &["gz", "tgz"]),
+    mime("application/x-bzip2", Archives, &["bz2", "tbz2"]),
+    mime("application/x-xz", Archives, &["xz", "txz"]),
+    mime("application/zstd", Archives, &["zst"]),
+    mime("application/x-7z-compressed", Archives, &["7z"]),
+    mime("application/vnd.rar", Archives, &["rar"]),
+    mime("application/x-tar", Archives, &["tar"]),
+    mime("text/x-script", Code, &[]),
+    flavour("text/x-shellscript", Code, &["sh", "bash", "zsh"], TEXT),
+    flavour("text/x-rust", Code, &["rs"], TEXT),
+    flavour("text/x-python", Code, &["py"], TEXT),
+    flavour("text/javascript", Code, &["js", "mjs", "cjs"], TEXT),
+    flavour("text/x-typescript", Code, &["ts", "tsx"], TEXT),
+    flavour("text/x-c", Code, &["c", "h"], TEXT),
+    flavour("text/x-c++", Code, &["cpp", "cc", "hpp"], TEXT),
+    flavour("text/x-java", Code, &["java"], TEXT),
+    flavour("text/x-go", Code, &["go"], TEXT),
+    flavour("text/html", Code, &["html", "htm"], TEXT),
+    flavour("text/css", Code, &["css"], TEXT),
+    flavour("application/json", Code, &["json"], TEXT),
+    flavour("application/xml", Code, &["xml"], TEXT),
+    flavour("application/toml", Code, &["toml"], TEXT),
+    flavour("application/yaml", Code, &["yaml", "yml"], TEXT),
+    mime("application/x-executable", Executables, &["elf", "so", "o"]),
+    mime(
+        "application/vnd.microsoft.portable-executable",
+        Executables,
+        &["exe", "dll"],
+    ),
+    mime("application/x-mach-binary", Executables, &["dylib"]),
+    mime("application/wasm", Executables, &["wasm"]),
+    mime("inode/directory", Directories, &[]),
+    mime("application/x-empty", Other, &[]),
+    mime(OCTET_STREAM, Other, &[]),
+];
+
+/// Bytes expected at offset of content
+type Magic = (usize, &'static [u8]);
+
+/// Parts of content that identify a type
+static SIGNATURES: &[(&[Magic], &str)] = &[
+    (&[(0, b"\x89PNG\r\n\x1a\n")], "image/png"),
+    (&[(0, b"\xff\xd8\xff")], "image/jpeg"),
+    (&[(0, b"GIF87a")], "image/gif"),
+    (&[(0, b"GIF89a")], "image/gif"),
+    (&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    (&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    (&[(0, b"RIFF"), (8, b"AVI ")], "video/x-msvideo"),
+    (&[(0, b"II*\0")], "image/tiff"),
+    (&[(0, b"MM\0*")], "image/tiff"),
+    (&[(0, b"\0\0\x01\0")], "image/x-icon"),
+    (&[(0, b"BM"), (6, b"\0\0\0\0")], "image/bmp"),
+    (&[(4, b"ftypheic")], "image/heic"),
+    (&[(4, b"ftypheix")], "image/heic"),
+    (&[(4, b"ftypmif1")], "image/heic"),
+    (&[(4, b"ftypavif")], "image/avif"),
+    (&[(4, b"ftypqt  ")], "video/quicktime"),
+    (&[(4, b"ftypM4A ")], "audio/mp4"),
+    (&[(4, b"ftyp")], "video/mp4"),
+    (&[(0, b"\x1a\x45\xdf\xa3")], "video/x-matroska"),
+    (&[(0, b"FLV\x01")], "video/x-flv"),
+    (&[(0, b"ID3")], "audio/mpeg"),
+    (&[(0, b"\xff\xfb")], "audio/mpeg"),
+    (&[(0, b"\xff\xf3")], "audio/mpeg"),
+    (&[(0, b"fLaC")], "audio/flac"),
+    (&[(0, b"OggS")], "audio/ogg"),
+    (&[(0, b"%PDF-")], "application/pdf"),
+    (&[(0, b"{\\rtf")], "application/rtf"),
+    (&[(0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1")], OLE),
+    (&[(0, b"PK\x03\x04")], ZIP),
+    (&[(0, b"PK\x05\x06")], ZIP),
+    (&[(0, b"\x1f\x8b")], "application/gzip"),
+    (&[(0, b"BZh")], "application/x-bzip2"),
+    (&[(0, b"\xfd7zXZ\0")], "application/x-xz"),
+    (&[(0, b"\x28\xb5\x2f\xfd")], "application/zstd"),
+    (&[(0, b"7z\xbc\xaf\x27\x1c")], "application/x-7z-compressed"),
+    (&[(0, b"Rar!\x1a\x07")], "application/vnd.rar"),
+    (&[(257, b"ustar")], "application/x-tar"),
+    (&[(0, b"\x7fELF")], "application/x-executable"),
+    (
+        &[(0, b"MZ")],
+        "application/vnd.microsoft.portable-executable",
+    ),
+    (&[(0, b"\xfe\xed\xfa\xce")], "application/x-mach-binary"),
+    (&[(0, b"\xfe\xed\xfa\xcf")], "application/x-mach-binary"),
+    (&[(0, b"\xce\xfa\xed\xfe")], "application/x-mach-binary"),
+    (&[(0, b"\xcf\xfa\xed\xfe")], "application/x-mach-binary"),
+    (&[(0, b"\0asm")], "application/wasm"),
+    (&[(0, b"#!")], "text/x-script"),
+];
+
+impl MimeType {
+    fn find(name: &str) -> &'static MimeType {
+        MIME_TYPES
+            .iter()
+            .find(|mime| mime.name == name)
+            .expect("every detected type is listed")
+    }
+
+    /// Type of file, read from its first bytes
+    pub fn detect(path: &Path) -> std::io::Result<&'static MimeType> {
+        if path.is_dir() {
+            return Ok(MimeType::find("inode/directory"));
+        }
+
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        File::open(path)?
+            .take(SNIFF_BYTES as u64)
+            .read_to_end(&mut head)?;
+        Ok(MimeType::from_content(&head, &extension(path)))
+    }
+
+    /// Type of content, `extension` only picks a flavour of detected type
+    fn from_content(head: &[u8], extension: &str) -> &'static MimeType {
+        if head.is_empty() {
+            return MimeType::find("application/x-empty");
+        }
+
+        let detected = SIGNATURES
+            .iter()
+            .find(|(parts, _)| {
+                parts
+                    .iter()
+                    .all(|(offset, magic)| head.get(*offset..offset + magic.len()) == Some(magic))
+            })
+            .map(|(_, name)| *name)
+            .unwrap_or(if is_text(head) { TEXT } else { OCTET_STREAM });
+
+        MIME_TYPES
+            .iter()
+            .find(|mime| mime.based_on == Some(detected) && mime.has_extension(extension))
+            .unwrap_or_else(|| MimeType::find(detected))
+    }
+
+    fn has_extension(&self, extension: &str) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    /// Extension of file whose content is of another known type, e.g. `.jpg` of a pdf.
+    /// Unknown extensions and content of unknown type never contradict each other.
+    pub fn contradicted_extension(&self, path: &Path) -> Option<String> {
+        let extension = extension(path);
+        if self.extensions.is_empty() || self.has_extension(&extension) {
+            return None;
+        }
+        let is_known = MIME_TYPES.iter().any(|mime| mime.has_extension(&extension));
+        is_known.then_some(extension)
+    }
+}
+
+impl Display for MimeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// No NUL bytes and valid UTF-8, except a character cut at the end of read bytes
+fn is_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(b"\x89PNG\r\n\x1a\n....", "png", "image/png", Images ; "png")]
+    #[test_case(b"\xff\xd8\xff\xe0", "", "image/jpeg", Images ; "jpeg without extension")]
+    #[test_case(b"RIFF\0\0\0\0WEBPVP8", "webp", "image/webp", Images ; "webp")]
+    #[test_case(b"\0\0\0\x18ftypheic", "heic", "image/heic", Images ; "heic")]
+    #[test_case(b"\0\0\0\x18ftypisom", "mp4", "video/mp4", Videos ; "mp4")]
+    #[test_case(b"ID3\x04", "mp3", "audio/mpeg", Audio ; "mp3")]
+    #[test_case(b"%PDF-1.7", "pdf", "application/pdf", Documents ; "pdf")]
+    #[test_case(b"PK\x03\x04", "docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document", Documents ; "docx")]
+    #[test_case(b"PK\x03\x04", "txt", "application/zip", Archives ; "zip named as text")]
+    #[test_case(b"\x1f\x8b\x08", "gz", "application/gzip", Archives ; "gzip")]
+    #[test_case(b"\x7fELF\x02", "", "application/x-executable", Executables ; "elf")]
+    #[test_case(b"#!/bin/sh\necho", "", "text/x-script", Code ; "shebang")]
+    #[test_case(b"fn main() {}", "rs", "text/x-rust", Code ; "rust")]
+    #[test_case(b"hello \xc3", "", "text/plain", Documents ; "text cut inside character")]
+    #[test_case(b"\x00\x01\x02", "png", "application/octet-stream", Other ; "unknown binary")]
+    #[test_case(b"", "txt", "application/x-empty", Other ; "empty")]
+    fn test_from_content(head: &[u8], extension: &str, name: &str, category: Category) {
+        let mime = MimeType::from_content(head, extension);
+
+        assert_eq!((mime.name, mime.category), (name, category));
+    }
+
+    #[test_case(b"%PDF-1.7", "photo.jpg", Some("jpg") ; "pdf named as image")]
+    #[test_case(b"%PDF-1.7", "report.PDF", None ; "matching extension")]
+    #[test_case(b"%PDF-1.7", "report.bin", None ; "unknown extension")]
+    #[test_case(b"\x00\x01\x02", "photo.png", None ; "unknown content")]
+    #[test_case(b"plain notes", "notes.md", None ; "flavour of text")]
+    fn test_contradicted_extension(head: &[u8], name: &str, expected: Option<&str>) {
+        let path = Path::new(name);
+        let mime = MimeType::from_content(head, &extension(path));
+
+        assert_eq!(
+            mime.contradicted_extension(path),
+            expected.map(str::to_string)
+        );
+    }
+
+    #[test]
+    fn test_detect_reads_file_head() {
+        let temp_dir = std::env::temp_dir().join("fo_test_mime_detect");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("image.txt"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let file = MimeType::detect(&temp_dir.join("image.txt")).unwrap();
+        let directory = MimeType::detect(&temp_dir).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(file.name, "image/png");
+        assert_eq!(directory.category, Directories);
+    }
+
+    #[test]
+    fn test_every_signature_has_mime_type() {
+        for (_, name) in SIGNATURES {
+            assert!(MIME_TYPES.iter().any(|mime| mime.name == *name), "{name}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{anyhow, bail};
+
+/// Parse human readable size into bytes.
+///
+/// Accepts plain numbers (`512`) and numbers with unit suffix (`10K`, `1.5MB`, `2GiB`).
+/// Single letter units and IEC units (`KiB`, `MiB`, ...) are binary, SI units (`KB`, `MB`, ...) are decimal.
+pub fn parse_size(value: &str) -> anyhow::Result<u64> {
+    let value = value.trim();
+    let number_end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(number_end);
+
+    if number.is_empty() {
+        bail!(
+            "Invalid size '{value}', expected a number with optional unit, e.g. 512, 10K or 1.5MB"
+        );
+    }
+
+    let multiplier = unit_multiplier(unit.trim()).ok_or_else(|| {
+        anyhow!("Unknown size unit '{unit}' in '{value}', expected one of: B, K, KB, KiB, M, MB, MiB, G, GB, GiB, T, TB, TiB")
+    })?;
+
+    if let Ok(whole) = number.parse::<u64>() {
+        return whole
+            .checked_mul(multiplier)
+            .ok_or_else(|| anyhow!("Size '{value}' is too large"));
+    }
+
+    let fractional = number
+        .parse::<f64>()
+        .map_err(|_| anyhow!("Invalid number '{number}' in size '{value}'"))?;
+    let bytes = fractional * multiplier as f64;
+    if bytes >= u64::MAX as f64 {
+        bail!("Size '{value}' is too large");
+    }
+
+    Ok(bytes.round() as u64)
+}
+
+fn unit_multiplier(unit: &str) -> Option<u64> {
+    const KIB: u64 = 1 << 10;
+    const KB: u64 = 1000;
+
+    let multiplier = match unit {
+        "" | "B" | "b" => 1,
+        "K" | "k" | "KiB" | "kiB" => KIB,
+        "M" | "m" | "MiB" => KIB.pow(2),
+        "G" | "g" | "GiB" => KIB.pow(3),
+        "T" | "t" | "TiB" => KIB.pow(4),
+        "KB" | "kB" | "kb" => KB,
+        "MB" | "mb" => KB.pow(2),
+        "GB" | "gb" => KB.pow(3),
+        "TB" | "tb" => KB.pow(4),
+        _ => return None,
+    };
+
+    Some(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+    use test_case::test_case;
+
+    #[test_case("0", 0)]
+    #[test_case("512", 512)]
+    #[test_case("512B", 512)]
+    #[test_case("10K", 10_240)]
+    #[test_case("10k", 10_240 ; "lowercase unit")]
+    #[test_case("10KiB", 10_240)]
+    #[test_case("10KB", 10_000)]
+    #[test_case("1M", 1_048_576)]
+    #[test_case("1MB", 1_000_000)]
+    #[test_case("1.5M", 1_572_864)]
+    #[test_case("2GiB", 2_147_483_648)]
+    #[test_case("1GB", 1_000_000_000)]
+    #[test_case("1T", 1_099_511_627_776)]
+    #[test_case("1TB", 1_000_000_000_000)]
+    fn test_parse_size(value: &str, expected: u64) {
+        assert_eq!(parse_size(value).unwrap(), expected);
+    }
+
+    #[test_case("")]
+    #[test_case("M")]
+    #[test_case("10X")]
+    #[test_case("1.2.3K")]
+    #[test_case("99999999999T")]
+    fn test_parse_size_invalid(value: &str) {
+        assert!(parse_size(value).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt::Display,
+    fs::Metadata,
+    sync::atomic::{AtomicBool, Ordering},
+    time::SystemTime,
+};
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use log::warn;
+
+/// Set once fallback warning was shown, so it is printed only once per run
+static FALLBACK_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Where creation time is taken from.
+/// Some filesystems (tmpfs on older kernels, many network mounts) don't record birth time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeSource {
+    /// Birth time only, fail if it is not available
+    Birth,
+    /// Always use modification time
+    Mtime,
+    /// Birth time, falling back to modification time if it is not available
+    #[default]
+    BirthOrMtime,
+}
+
+impl Display for TimeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeSource::Birth => write!(f, "birth"),
+            TimeSource::Mtime => write!(f, "mtime"),
+            TimeSource::BirthOrMtime => write!(f, "birth-or-mtime"),
+        }
+    }
+}
+
+/// File timestamp that can be read from metadata
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileTimestamp {
+    /// Birth time
+    Created,
+    /// Content modification time (mtime)
+    Modified,
+    /// Last access time (atime)
+    Accessed,
+    /// Inode change time (ctime), Unix only
+    Changed,
+}
+
+impl FileTimestamp {
+    /// Read timestamp from metadata, creation time is resolved according to time source
+    pub fn read(&self, metadata: &Metadata, source: TimeSource) -> anyhow::Result<SystemTime> {
+        let time = match self {
+            FileTimestamp::Created => return read_created(metadata, source),
+            FileTimestamp::Modified => metadata.modified(),
+            FileTimestamp::Accessed => metadata.accessed(),
+            FileTimestamp::Changed => return read_changed(metadata),
+        };
+
+        time.map_err(|err| anyhow!("Failed to get file {self} time: {err}"))
+    }
+}
+
+fn read_created(metadata: &Metadata, source: TimeSource) -> anyhow::Result<SystemTime> {
+    match source {
+        TimeSource::Birth => metadata.created().map_err(|err| {
+            anyhow!(
+                "Failed to get file creation time: {err}. Use '--time-source birth-or-mtime' to fall back to modification time"
+            )
+        }),
+        TimeSource::Mtime => FileTimestamp::Modified.read(metadata, source),
+        TimeSource::BirthOrMtime => match metadata.created() {
+            Ok(time) => Ok(time),
+            Err(err) => {
+                if !FALLBACK_WARNED.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "Birth time is not available ({err}), falling back to modification time"
+                    );
+                }
+                FileTimestamp::Modified.read(metadata, source)
+            }
+        },
+    }
+}
+
+impl Display for FileTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileTimestamp::Created => write!(f, "creation"),
+            FileTimestamp::Modified => write!(f, "modification"),
+            FileTimestamp::Accessed => write!(f, "access"),
+            FileTimestamp::Changed => write!(f, "change"),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn read_changed(metadata: &Metadata) -> anyhow::Result<SystemTime> {
+    use std::{os::unix::fs::MetadataExt, time::Duration};
+
+    let seconds = Duration::from_secs(metadata.ctime().max(0) as u64);
+    let nanos = Duration::from_nanos(metadata.ctime_nsec().max(0) as u64);
+    Ok(SystemTime::UNIX_EPOCH + seconds + nanos)
+}
+
+#[cfg(not(unix))]
+fn read_changed(_metadata: &Metadata) -> anyhow::Result<SystemTime> {
+    anyhow::bail!("Inode change time is only available on Unix systems")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileTimestamp, TimeSource};
+    use crate::volumes::get_path_under_tests;
+
+    #[test]
+    fn test_read_all_timestamps() {
+        let metadata =
+            std::fs::metadata(get_path_under_tests("filtering/matched/matched.txt")).unwrap();
+
+        for timestamp in [
+            FileTimestamp::Modified,
+            FileTimestamp::Accessed,
+            FileTimestamp::Changed,
+        ] {
+            assert!(timestamp.read(&metadata, TimeSource::Birth).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_changed_not_before_modified() {
+        let metadata =
+            std::fs::metadata(get_path_under_tests("filtering/matched/matched.txt")).unwrap();
+
+        let modified = FileTimestamp::Modified
+            .read(&metadata, TimeSource::Birth)
+            .unwrap();
+        let changed = FileTimestamp::Changed
+            .read(&metadata, TimeSource::Birth)
+            .unwrap();
+        assert!(changed >= modified);
+    }
+
+    #[test]
+    fn test_created_from_mtime_source() {
+        let metadata =
+            std::fs::metadata(get_path_under_tests("filtering/matched/matched.txt")).unwrap();
+
+        let created = FileTimestamp::Created
+            .read(&metadata, TimeSource::Mtime)
+            .unwrap();
+        assert_eq!(created, metadata.modified().unwrap());
+    }
+
+    #[test]
+    fn test_created_with_fallback() {
+        let metadata =
+            std::fs::metadata(get_path_under_tests("filtering/matched/matched.txt")).unwrap();
+
+        let created = FileTimestamp::Created
+            .read(&metadata, TimeSource::BirthOrMtime)
+            .unwrap();
+        let expected = metadata
+            .created()
+            .unwrap_or_else(|_| metadata.modified().unwrap());
+        assert_eq!(created, expected);
+    }
+}
@@ -0,0 +1,481 @@
+//! Trash can following the freedesktop.org Trash specification.
+//!
+//! Files from the home volume go to `$XDG_DATA_HOME/Trash` (or `~/.local/share/Trash`),
+//! files from other mounts go to `$topdir/.Trash/$uid` or `$topdir/.Trash-$uid`,
+//! so trashing never copies data between volumes.
+
+use std::{
+    fs::{self, DirBuilder, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use log::{debug, warn};
+use time::{OffsetDateTime, format_description};
+
+const INFO_EXTENSION: &str = "trashinfo";
+
+/// Trash directory with `files` and `info` subdirectories
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trash {
+    root: PathBuf,
+    /// Mount point that relative original paths are resolved against, only for volume trashes
+    top_dir: Option<PathBuf>,
+}
+
+/// File or directory that is currently in trash
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashedItem {
+    /// Name inside trash `files` directory
+    pub name: String,
+    /// Where item is stored in trash
+    pub path: PathBuf,
+    /// Where item was trashed from
+    pub original: PathBuf,
+    /// Deletion date as written in `.trashinfo`, `YYYY-MM-DDThh:mm:ss` in local time
+    pub deleted_at: String,
+    info: PathBuf,
+}
+
+impl Trash {
+    pub fn at(root: PathBuf, top_dir: Option<PathBuf>) -> Self {
+        Self { root, top_dir }
+    }
+
+    /// Trash of the user home volume
+    pub fn home() -> io::Result<Self> {
+        let data_home = match std::env::var_os("XDG_DATA_HOME").map(PathBuf::from) {
+            Some(data_home) if data_home.is_absolute() => data_home,
+            _ => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local/share"))
+                .ok_or_else(|| io::Error::other("Neither XDG_DATA_HOME nor HOME is set"))?,
+        };
+        Ok(Self::at(data_home.join("Trash"), None))
+    }
+
+    /// Trash on the same volume as `path`
+    pub fn for_path(path: &Path) -> io::Result<Self> {
+        let home = Self::home()?;
+        let path = std::path::absolute(path)?;
+        let device = device_id(&path)?;
+
+        // Home trash may not exist yet, then its closest existing parent decides the volume
+        let home_device = home.root.ancestors().find_map(|dir| device_id(dir).ok());
+        if home_device.is_none() || home_device == Some(device) {
+            return Ok(home);
+        }
+
+        let top_dir = mount_point(&path, device);
+        let uid = current_uid()?;
+        let shared = top_dir.join(".Trash");
+        if is_valid_shared_trash(&shared) {
+            return Ok(Self::at(shared.join(uid.to_string()), Some(top_dir)));
+        }
+        Ok(Self::at(
+            top_dir.join(format!(".Trash-{uid}")),
+            Some(top_dir),
+        ))
+    }
+
+    /// Home trash and trashes of all mounted volumes that exist
+    pub fn all() -> io::Result<Vec<Self>> {
+        let mut trashes = vec![Self::home()?];
+        let uid = current_uid()?;
+        for top_dir in mount_points() {
+            for root in [
+                top_dir.join(".Trash").join(uid.to_string()),
+                top_dir.join(format!(".Trash-{uid}")),
+            ] {
+                if root.join("info").is_dir() && !trashes.iter().any(|trash| trash.root == root) {
+                    trashes.push(Self::at(root, Some(top_dir.clone())));
+                }
+            }
+        }
+        Ok(trashes)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Move file or directory into trash, returns where it is stored
+    pub fn put(&self, path: &Path) -> io::Result<PathBuf> {
+        let files_dir = self.root.join("files");
+        let info_dir = self.root.join("info");
+        // Trash may contain private files, so it is only accessible by the user
+        let mut builder = DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&files_dir)?;
+        builder.create(&info_dir)?;
+
+        let original = std::path::absolute(path)?;
+        let stored_path = match &self.top_dir {
+            Some(top_dir) => original.strip_prefix(top_dir).unwrap_or(&original),
+            None => &original,
+        };
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::other("Path has no file name"))?
+            .to_string_lossy()
+            .into_owned();
+
+        for number in 0.. {
+            let name = match number {
+                0 => file_name.clone(),
+                _ => format!("{file_name}.{number}"),
+            };
+            let info = info_dir.join(format!("{name}.{INFO_EXTENSION}"));
+            // Creating info file first reserves the name, as required by the spec
+            let mut info_file = match OpenOptions::new().write(true).create_new(true).open(&info) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            };
+            let trashed = files_dir.join(&name);
+            if trashed.symlink_metadata().is_ok() {
+                // Leftover without info file, keep it untouched
+                fs::remove_file(&info)?;
+                continue;
+            }
+
+            let result = write!(
+                info_file,
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                encode_path(stored_path),
+                deletion_date()
+            )
+            .and_then(|_| fs::rename(path, &trashed));
+            if let Err(err) = result {
+                let _ = fs::remove_file(&info);
+                return Err(err);
+            }
+
+            debug!("Trashed {} as {}", path.display(), trashed.display());
+            return Ok(trashed);
+        }
+        unreachable!("Numbered names are never exhausted")
+    }
+
+    /// Items in trash, sorted by deletion date
+    pub fn items(&self) -> io::Result<Vec<TrashedItem>> {
+        let entries = match fs::read_dir(self.root.join("info")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut items = Vec::new();
+        for entry in entries {
+            let info = entry?.path();
+            if info.extension().is_none_or(|ext| ext != INFO_EXTENSION) {
+                continue;
+            }
+            match self.read_info(&info) {
+                Ok(item) => items.push(item),
+                Err(err) => warn!("Skipping broken trash info {}: {}", info.display(), err),
+            }
+        }
+
+        items.sort_by(|a, b| a.deleted_at.cmp(&b.deleted_at).then(a.name.cmp(&b.name)));
+        Ok(items)
+    }
+
+    fn read_info(&self, info: &Path) -> io::Result<TrashedItem> {
+        let content = fs::read_to_string(info)?;
+        let value = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+                .map(str::trim)
+        };
+
+        let stored_path = value("Path")
+            .map(decode_path)
+            .ok_or_else(|| io::Error::other("Missing Path key"))?;
+        let original = match &self.top_dir {
+            Some(top_dir) if stored_path.is_relative() => top_dir.join(stored_path),
+            _ => stored_path,
+        };
+        let name = info
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        Ok(TrashedItem {
+            path: self.root.join("files").join(&name),
+            name,
+            original,
+            deleted_at: value("DeletionDate").unwrap_or_default().to_string(),
+            info: info.to_path_buf(),
+        })
+    }
+}
+
+impl TrashedItem {
+    /// Move item back to its original location, recreating missing parent directories
+    pub fn restore(&self) -> io::Result<()> {
+        restore(&self.path, &self.original)
+    }
+
+    /// Permanently delete item from trash
+    pub fn purge(&self) -> io::Result<()> {
+        let metadata = self.path.symlink_metadata();
+        match metadata {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&self.path)?,
+            Ok(_) => fs::remove_file(&self.path)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        fs::remove_file(&self.info)
+    }
+}
+
+/// Move trashed file at `trashed` back to `original` and remove its info file
+pub fn restore(trashed: &Path, original: &Path) -> io::Result<()> {
+    if original.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", original.display()),
+        ));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(trashed, original)?;
+
+    let name = trashed.file_name().unwrap_or_default().to_string_lossy();
+    if let Some(root) = trashed.parent().and_then(Path::parent) {
+        let info = root.join("info").join(format!("{name}.{INFO_EXTENSION}"));
+        if let Err(err) = fs::remove_file(&info) {
+            warn!("Failed to remove trash info {}: {}", info.display(), err);
+        }
+    }
+    Ok(())
+}
+
+fn deletion_date() -> String {
+    let format = format_description::parse("[year]-[month]-[day]T[hour]:[minute]:[second]")
+        .expect("Should be valid, because its hardcoded.");
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .format(&format)
+        .unwrap_or_default()
+}
+
+/// Percent-encode path as URL path, keeping `/` and unreserved characters
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(*byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn decode_path(encoded: &str) -> PathBuf {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| encoded.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// Highest directory of `path` that is still on `device`
+fn mount_point(path: &Path, device: u64) -> PathBuf {
+    let mut top_dir = path;
+    while let Some(parent) = top_dir.parent() {
+        if device_id(parent).ok() != Some(device) {
+            break;
+        }
+        top_dir = parent;
+    }
+    top_dir.to_path_buf()
+}
+
+/// Shared `.Trash` must be a real directory with sticky bit, otherwise it can't be trusted
+fn is_valid_shared_trash(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(path.symlink_metadata()?.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> io::Result<u64> {
+    Ok(0)
+}
+
+/// Owner of `/proc/self` is the user running the process
+#[cfg(unix)]
+fn current_uid() -> io::Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    Ok(fs::metadata("/proc/self")?.uid())
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> io::Result<u32> {
+    Ok(0)
+}
+
+/// Mount points from `/proc/self/mounts`, octal escapes like `\040` are decoded
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|mount_point| {
+            let mut decoded = String::new();
+            let mut rest = mount_point;
+            while let Some(index) = rest.find('\\') {
+                decoded.push_str(&rest[..index]);
+                match rest
+                    .get(index + 1..index + 4)
+                    .and_then(|octal| u8::from_str_radix(octal, 8).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte as char);
+                        rest = &rest[index + 4..];
+                    }
+                    None => {
+                        decoded.push('\\');
+                        rest = &rest[index + 1..];
+                    }
+                }
+            }
+            decoded.push_str(rest);
+            PathBuf::from(decoded)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("/home/user/notes.txt", "/home/user/notes.txt" ; "plain")]
+    #[test_case("/home/user/my notes%.txt", "/home/user/my%20notes%25.txt" ; "escaped")]
+    #[test_case("/home/user/заметки", "/home/user/%D0%B7%D0%B0%D0%BC%D0%B5%D1%82%D0%BA%D0%B8" ; "utf8")]
+    fn test_encode_path(path: &str, expected: &str) {
+        assert_eq!(encode_path(Path::new(path)), expected);
+        assert_eq!(decode_path(expected), PathBuf::from(path));
+    }
+
+    #[test]
+    fn test_put_list_and_restore() {
+        let temp_dir = std::env::temp_dir().join("fo_test_trash_put");
+        let source = temp_dir.join("source");
+        fs::create_dir_all(source.join("dir")).unwrap();
+        fs::write(source.join("a.tmp"), "first").unwrap();
+        fs::write(source.join("dir/inner.tmp"), "inner").unwrap();
+        let trash = Trash::at(temp_dir.join("Trash"), None);
+
+        let first = trash.put(&source.join("a.tmp")).unwrap();
+        // Same name gets a numbered name in trash
+        fs::write(source.join("a.tmp"), "second").unwrap();
+        let second = trash.put(&source.join("a.tmp")).unwrap();
+        trash.put(&source.join("dir")).unwrap();
+
+        let items = trash.items().unwrap();
+        let names = items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+        let trashed_away = !source.join("a.tmp").exists() && !source.join("dir").exists();
+        let first_item = items.iter().find(|item| item.path == first).unwrap();
+        first_item.restore().unwrap();
+        let restored = fs::read_to_string(source.join("a.tmp")).unwrap();
+        items
+            .iter()
+            .find(|item| item.path == second)
+            .unwrap()
+            .purge()
+            .unwrap();
+        let remaining = trash.items().unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(first.file_name().unwrap(), "a.tmp");
+        assert_eq!(second.file_name().unwrap(), "a.tmp.1");
+        assert_eq!(names.len(), 3);
+        assert!(trashed_away);
+        assert_eq!(first_item.original, source.join("a.tmp"));
+        assert_eq!(restored, "first");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].original, source.join("dir"));
+    }
+
+    #[test]
+    fn test_volume_trash_stores_relative_paths() {
+        let temp_dir = std::env::temp_dir().join("fo_test_trash_volume");
+        fs::create_dir_all(temp_dir.join("data")).unwrap();
+        fs::write(temp_dir.join("data/file.txt"), "content").unwrap();
+        let trash = Trash::at(temp_dir.join(".Trash-1000"), Some(temp_dir.clone()));
+
+        let trashed = trash.put(&temp_dir.join("data/file.txt")).unwrap();
+        let info =
+            fs::read_to_string(temp_dir.join(".Trash-1000/info/file.txt.trashinfo")).unwrap();
+        let items = trash.items().unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert_eq!(trashed, temp_dir.join(".Trash-1000/files/file.txt"));
+        assert!(info.contains("Path=data/file.txt\n"));
+        assert_eq!(items[0].original, temp_dir.join("data/file.txt"));
+    }
+
+    #[test]
+    fn test_restore_refuses_to_overwrite() {
+        let temp_dir = std::env::temp_dir().join("fo_test_trash_restore_existing");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(temp_dir.join("file.txt"), "old").unwrap();
+        let trash = Trash::at(temp_dir.join("Trash"), None);
+        let trashed = trash.put(&temp_dir.join("file.txt")).unwrap();
+        fs::write(temp_dir.join("file.txt"), "new").unwrap();
+
+        let result = restore(&trashed, &temp_dir.join("file.txt"));
+        let content = fs::read_to_string(temp_dir.join("file.txt")).unwrap();
+        fs::remove_dir_all(&temp_dir).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(content, "new");
+    }
+}